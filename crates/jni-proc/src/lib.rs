@@ -1,6 +1,8 @@
 use proc_macro::TokenStream;
 use syn::{parse::Parse, Token};
 
+mod native;
+
 struct JClasser {
     rust: syn::Type,
     java: syn::Ident
@@ -26,13 +28,13 @@ pub fn generate_jclass_impls(input: TokenStream) -> TokenStream {
 
     let mut tokens = quote::quote! {
         impl<'a> crate::class::JClassExt<#rust> for crate::class::JClass<'a> {
-            fn set_static_field(cls:&crate::class::JClass, name: &str, sig: &str, new_value: #rust) -> Result<(), ()> {
+            fn set_static_field(cls:&crate::class::JClass, name: &str, sig: &str, new_value: #rust) -> Result<(), crate::error::JniError> {
                 cls.#setf(name, sig, new_value)
             }
-            fn get_static_field(cls:&crate::class::JClass, name: &str, sig: &str) -> Result<#rust, ()> {
+            fn get_static_field(cls:&crate::class::JClass, name: &str, sig: &str) -> Result<#rust, crate::error::JniError> {
                 cls.#getf(name, sig)
             }
-            fn call_static_method(cls:&crate::class::JClass, name: &str, sig: &str, args:&Vec<crate::jvalue::JValue>) -> Result<#rust, ()> {
+            fn call_static_method(cls:&crate::class::JClass, name: &str, sig: &str, args:&Vec<crate::jvalue::JValue>) -> Result<#rust, crate::error::JniError> {
                 cls.#callm(name, sig, args)
             }
         }
@@ -58,10 +60,10 @@ pub fn generate_joaf_impls(input: TokenStream) -> TokenStream {
                     return_type : PhantomData,
                 }
             }
-            pub fn get(&self) -> Result<#rust,()> {
+            pub fn get(&self) -> Result<#rust, crate::error::JniError> {
                 self.parent.#getf(&self.name,&self.sig)
             }
-            pub fn set(&self,new_value:#rust) -> Result<(),()> {
+            pub fn set(&self,new_value:#rust) -> Result<(), crate::error::JniError> {
                 self.parent.#setf(&self.name,&self.sig,new_value)
             }
         }
@@ -74,11 +76,11 @@ pub fn generate_joaf_impls(input: TokenStream) -> TokenStream {
                     return_type : PhantomData,
                 }
             }
-            pub fn get(&self,env:&'a Jenv) -> Result<#rust,()> {
-                env.find_class(&self.class_sig)?.#getfs(&self.name,&self.sig)
+            pub fn get(&self,env:&'a Jenv) -> Result<#rust, crate::error::JniError> {
+                env.find_class_cached(&self.class_sig)?.#getfs(&self.name,&self.sig)
             }
-            pub fn set(&self,env:&'a Jenv,new_value:#rust) -> Result<(),()> {
-                env.find_class(&self.class_sig)?.#setfs(&self.name,&self.sig,new_value)
+            pub fn set(&self,env:&'a Jenv,new_value:#rust) -> Result<(), crate::error::JniError> {
+                env.find_class_cached(&self.class_sig)?.#setfs(&self.name,&self.sig,new_value)
             }
         }
     };
@@ -101,7 +103,7 @@ pub fn generate_joam_impls(input: TokenStream) -> TokenStream {
                     return_type : PhantomData,
                 }
             }
-            pub fn call(&self) -> Result<#rust,()> {
+            pub fn call(&self) -> Result<#rust, crate::error::JniError> {
                 self.parent.#callm(&self.name,&self.sig,&self.args)
             }
         }
@@ -115,8 +117,8 @@ pub fn generate_joam_impls(input: TokenStream) -> TokenStream {
                     return_type : PhantomData,
                 }
             }
-            pub fn call(&self,env: &'a Jenv) -> Result<#rust,()> {
-                env.find_class(&self.class_sig)?.#callms(&self.name,&self.sig,&self.args)
+            pub fn call(&self,env: &'a Jenv) -> Result<#rust, crate::error::JniError> {
+                env.find_class_cached(&self.class_sig)?.#callms(&self.name,&self.sig,&self.args)
             }
         }
     };
@@ -130,4 +132,13 @@ pub fn generate_jobject_impls(input: TokenStream) -> TokenStream {
         jni_proc::generate_joaf_impls!(#rust, #java);
         jni_proc::generate_joam_impls!(#rust, #java);
     }).into()
-}
\ No newline at end of file
+}
+/// Exposes a rust function as a java `native` method. the function takes the env and `this` (or the class for static methods)
+/// followed by the java arguments, the descriptor is worked out from the rust types unless given with `sig = ".."`.
+/// Generates a `<NAME>_NATIVE` `jni::native::NativeMethod` const to pass to `JClass::register_natives`.
+#[proc_macro_attribute]
+pub fn jni_native(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let func = syn::parse_macro_input!(input as syn::ItemFn);
+    native::expand(args, func).unwrap_or_else(|e| e.to_compile_error()).into()
+}
@@ -0,0 +1,202 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, AttributeArgs, FnArg, GenericArgument, ItemFn, Lit, Meta, NestedMeta, PathArguments, ReturnType, Type};
+
+/// `#[jni_native(name = "..", sig = "..")]`, both optional
+#[derive(Default)]
+struct NativeArgs {
+    name : Option<String>,
+    sig : Option<String>,
+}
+
+impl NativeArgs {
+    fn parse(args : AttributeArgs) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for arg in args {
+            let NestedMeta::Meta(Meta::NameValue(nv)) = &arg else {
+                return Err(syn::Error::new(arg.span(), "expected `name = \"..\"` or `sig = \"..\"`"));
+            };
+            let Lit::Str(value) = &nv.lit else {
+                return Err(syn::Error::new(nv.lit.span(), "expected a string"));
+            };
+            if nv.path.is_ident("name") {
+                out.name = Some(value.value());
+            } else if nv.path.is_ident("sig") {
+                out.sig = Some(value.value());
+            } else {
+                return Err(syn::Error::new(nv.path.span(), "unknown argument, expected `name` or `sig`"));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// the jni descriptor of a rust parameter / return type, `None` for types we can not know the java class of
+fn type_sig(ty : &Type) -> Option<String> {
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Some("V".to_string()),
+        Type::Reference(reference) => type_sig(&reference.elem),
+        Type::Paren(paren) => type_sig(&paren.elem),
+        Type::Path(path) => {
+            let last = path.path.segments.last()?;
+            let first_type = || match &last.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().find_map(|a| match a {
+                    GenericArgument::Type(t) => Some(t),
+                    _ => None,
+                }),
+                _ => None,
+            };
+            Some(match last.ident.to_string().as_str() {
+                "bool" => "Z".to_string(),
+                "i8" => "B".to_string(),
+                "char" => "C".to_string(),
+                "i16" => "S".to_string(),
+                "i32" => "I".to_string(),
+                "i64" => "J".to_string(),
+                "f32" => "F".to_string(),
+                "f64" => "D".to_string(),
+                "JObject" => "Ljava/lang/Object;".to_string(),
+                "JString" => "Ljava/lang/String;".to_string(),
+                "JClass" => "Ljava/lang/Class;".to_string(),
+                "JArray" => format!("[{}", type_sig(first_type()?)?),
+                "Result" => type_sig(first_type()?)?,
+                _ => return None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// the raw jni types of each argument and of the return value of a descriptor
+fn raw_types(sig : &str) -> Option<(Vec<TokenStream>, TokenStream)> {
+    let raw = |c : u8| -> TokenStream {
+        match c {
+            b'Z' => quote!(::jni::native::jboolean),
+            b'B' => quote!(::jni::native::jbyte),
+            b'C' => quote!(::jni::native::jchar),
+            b'S' => quote!(::jni::native::jshort),
+            b'I' => quote!(::jni::native::jint),
+            b'J' => quote!(::jni::native::jlong),
+            b'F' => quote!(::jni::native::jfloat),
+            b'D' => quote!(::jni::native::jdouble),
+            b'V' => quote!(()),
+            _ => quote!(::jni::native::jobject),
+        }
+    };
+    // skips one field descriptor starting at `idx`, returns the index after it
+    fn skip(bytes : &[u8], mut idx : usize) -> Option<usize> {
+        while *bytes.get(idx)? == b'[' {
+            idx += 1;
+        }
+        match bytes.get(idx)? {
+            b'L' => Some(idx + bytes[idx..].iter().position(|b| *b == b';')? + 1),
+            b'Z' | b'B' | b'C' | b'S' | b'I' | b'J' | b'F' | b'D' | b'V' => Some(idx + 1),
+            _ => None,
+        }
+    }
+    let bytes = sig.as_bytes();
+    if bytes.first() != Some(&b'(') {
+        return None;
+    }
+    let mut args = vec![];
+    let mut idx = 1;
+    while *bytes.get(idx)? != b')' {
+        args.push(raw(bytes[idx]));
+        idx = skip(bytes, idx)?;
+    }
+    let ret = skip(bytes, idx + 1)?;
+    if ret != bytes.len() {
+        return None;
+    }
+    Some((args, raw(bytes[idx + 1])))
+}
+
+pub fn expand(args : AttributeArgs, func : ItemFn) -> syn::Result<TokenStream> {
+    let args = NativeArgs::parse(args)?;
+    let ident = &func.sig.ident;
+    let inputs = func.sig.inputs.iter().map(|arg| match arg {
+        FnArg::Typed(typed) => Ok(&*typed.ty),
+        FnArg::Receiver(r) => Err(syn::Error::new(r.span(), "native methods can not take self")),
+    }).collect::<syn::Result<Vec<_>>>()?;
+    if inputs.len() < 2 {
+        return Err(syn::Error::new(func.sig.inputs.span(), "native methods take the env and `this` (or the class for static methods) first, eg. `env: &Jenv, this: JObject`"));
+    }
+
+    let sig = match args.sig {
+        Some(sig) => sig,
+        None => {
+            let mut sig = "(".to_string();
+            for ty in &inputs[2..] {
+                sig.push_str(&type_sig(ty).ok_or_else(|| syn::Error::new(ty.span(), "can not work out the java type, pass the descriptor with `#[jni_native(sig = \"..\")]`"))?);
+            }
+            sig.push(')');
+            match &func.sig.output {
+                ReturnType::Default => sig.push('V'),
+                ReturnType::Type(_, ty) => sig.push_str(&type_sig(ty).ok_or_else(|| syn::Error::new(ty.span(), "can not work out the java return type, pass the descriptor with `#[jni_native(sig = \"..\")]`"))?),
+            }
+            sig
+        }
+    };
+    let Some((raw_args, raw_ret)) = raw_types(&sig) else {
+        return Err(syn::Error::new(Span::call_site(), format!("`{sig}` is not a valid method descriptor")));
+    };
+    if raw_args.len() != inputs.len() - 2 {
+        return Err(syn::Error::new(func.sig.inputs.span(), format!("`{sig}` takes {} arguments but the function takes {} after the env and `this`", raw_args.len(), inputs.len() - 2)));
+    }
+
+    let name = args.name.unwrap_or_else(|| ident.to_string());
+    let vis = &func.vis;
+    let trampoline = format_ident!("__jni_native_{}", ident);
+    let constant = format_ident!("{}_NATIVE", ident.to_string().to_uppercase());
+    let arg_names = (0..raw_args.len()).map(|i| format_ident!("arg{}", i)).collect::<Vec<_>>();
+
+    Ok(quote! {
+        #func
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        unsafe extern "system" fn #trampoline(env : *mut ::jni::native::JNIEnv, this : ::jni::native::jobject #(, #arg_names : #raw_args)*) -> #raw_ret {
+            let env = ::jni::env::Jenv::from_raw(env);
+            ::jni::native::run_native(&env, |env| #ident(env, ::jni::native::FromNative::from_native(this, env) #(, ::jni::native::FromNative::from_native(#arg_names, env))*))
+        }
+
+        #vis const #constant : ::jni::native::NativeMethod = ::jni::native::NativeMethod {
+            name : #name,
+            sig : #sig,
+            fn_ptr : #trampoline as *mut ::std::ffi::c_void,
+        };
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(ty : &str) -> Option<String> {
+        type_sig(&syn::parse_str::<Type>(ty).unwrap())
+    }
+
+    #[test]
+    fn type_sigs() {
+        assert_eq!(sig("()").as_deref(), Some("V"));
+        assert_eq!(sig("i64").as_deref(), Some("J"));
+        assert_eq!(sig("&'a JString<'a>").as_deref(), Some("Ljava/lang/String;"));
+        assert_eq!(sig("JArray<'a, JArray<'a, f32>>").as_deref(), Some("[[F"));
+        assert_eq!(sig("Result<JObject<'a>, JniError>").as_deref(), Some("Ljava/lang/Object;"));
+        assert_eq!(sig("jni::prelude::JClass<'a>").as_deref(), Some("Ljava/lang/Class;"));
+        assert_eq!(sig("Minecraft<'a>"), None);
+        assert_eq!(sig("JArray"), None);
+    }
+
+    #[test]
+    fn raw_type_lists() {
+        let (args, ret) = raw_types("(I[JLjava/lang/String;[[Ljava/lang/Object;Z)V").unwrap();
+        let args = args.iter().map(|a| a.to_string().replace(' ', "")).collect::<Vec<_>>();
+        assert_eq!(args, ["::jni::native::jint", "::jni::native::jobject", "::jni::native::jobject", "::jni::native::jobject", "::jni::native::jboolean"]);
+        assert_eq!(ret.to_string().replace(' ', ""), "()");
+        assert_eq!(raw_types("()D").unwrap().1.to_string().replace(' ', ""), "::jni::native::jdouble");
+        for bad in ["I", "(I", "(Ljava/lang/String)V", "(Q)V", "()VV", "()"] {
+            assert!(raw_types(bad).is_none(), "{bad}");
+        }
+    }
+}
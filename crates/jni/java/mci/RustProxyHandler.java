@@ -0,0 +1,82 @@
+package mci;
+
+import java.lang.ref.Cleaner;
+import java.lang.ref.Reference;
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Method;
+
+/**
+ * Routes proxy invocations to a boxed rust closure, see jni::proxy.
+ * The closure is released by a {@link Cleaner} once the handler (and so every proxy using it) is unreachable, or earlier by {@link #close()}.
+ * Either way it is never released while a call is still using it.
+ * Compiled from crates/jni/java with `javac --release 9 -d . mci/RustProxyHandler.java` and embedded into the jni crate.
+ * Nothing here may compile to a second class file, the jni crate only defines this one.
+ */
+public final class RustProxyHandler implements InvocationHandler, AutoCloseable {
+    private static final Cleaner CLEANER = Cleaner.create();
+
+    private final long ptr;
+    private final Cleaner.Cleanable release;
+    private final Object lock = new Object();
+    // guarded by lock
+    private boolean closed;
+    private int calls;
+
+    private RustProxyHandler(long ptr) {
+        this.ptr = ptr;
+        // the action must not capture `this` or the handler never becomes unreachable
+        this.release = CLEANER.register(this, () -> release0(ptr));
+    }
+
+    @Override
+    public Object invoke(Object proxy, Method method, Object[] args) throws Throwable {
+        if (method.getDeclaringClass() == Object.class) {
+            switch (method.getName()) {
+                case "hashCode":
+                    return System.identityHashCode(proxy);
+                case "equals":
+                    return proxy == args[0];
+                case "toString":
+                    return "RustProxy@" + Integer.toHexString(System.identityHashCode(proxy));
+            }
+        }
+        synchronized (lock) {
+            if (closed) {
+                throw new IllegalStateException("rust proxy handler is closed");
+            }
+            calls++;
+        }
+        try {
+            return invoke0(ptr, proxy, method, args == null ? new Object[0] : args);
+        } finally {
+            boolean last;
+            synchronized (lock) {
+                last = --calls == 0 && closed;
+            }
+            if (last) {
+                release.clean();
+            }
+            // the cleaner may not run while the closure is in use, even though nothing reads `this` after `ptr`
+            Reference.reachabilityFence(this);
+        }
+    }
+
+    /**
+     * Releases the closure now instead of once the handler is collected, calls made after this throw.
+     * Calls still running (including the one closing it) finish first, the last of them releases it.
+     */
+    @Override
+    public void close() {
+        synchronized (lock) {
+            closed = true;
+            if (calls > 0) {
+                return;
+            }
+        }
+        release.clean();
+    }
+
+    private static native Object invoke0(long ptr, Object proxy, Method method, Object[] args);
+
+    private static native void release0(long ptr);
+}
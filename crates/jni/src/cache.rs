@@ -0,0 +1,345 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use jdk_sys::{jfieldID, jmethodID, jvalue, JNI_TRUE};
+
+use crate::{unchecked_jnic, unchecked_jnice, env::Jenv, class::JClass, object::{JObject, JClassInstance}, jvalue::JValue, error::JniError, refs::GlobalRef, vm::JavaVm};
+
+/// class global refs by name, for the vm they were resolved on. they are never deleted, which keeps every cached id valid.
+static CLASSES : RwLock<Option<(usize, HashMap<String, GlobalRef<JObject<'static>>>)>> = RwLock::new(None);
+
+fn current_vm(env : &Jenv) -> Result<usize,JniError> {
+    Ok(JavaVm::from_env(env)?.as_raw() as usize)
+}
+
+/// [`Jenv::find_class`] but the class is looked up once per jvm and kept as a global reference
+pub fn find_class_cached<'a>(env : &'a Jenv<'a>, name : &str) -> Result<JClass<'a>,JniError> {
+    let vm = current_vm(env)?;
+    if let Some((cached_vm, classes)) = CLASSES.read().unwrap().as_ref() {
+        if *cached_vm == vm {
+            if let Some(class) = classes.get(name) {
+                return Ok(JClass::new(class.as_raw(), env));
+            }
+        }
+    }
+    let local = env.find_class(name)?;
+    let global = GlobalRef::<JObject<'static>>::from_raw(env, local.ptr)?;
+    unchecked_jnic!(env.ptr,DeleteLocalRef, local.ptr);
+    let ptr = global.as_raw();
+
+    let mut lock = CLASSES.write().unwrap();
+    match lock.as_mut() {
+        Some((cached_vm, classes)) if *cached_vm == vm => {
+            classes.insert(name.to_string(), global);
+        }
+        _ => {
+            *lock = Some((vm, HashMap::from([(name.to_string(), global)])));
+        }
+    }
+    Ok(JClass::new(ptr, env))
+}
+
+/// an id which has been resolved against a cached class, `is_static` tells which jni family has to be used with it
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedId {
+    vm : usize,
+    class : usize,
+    id : usize,
+    pub is_static : bool,
+}
+
+impl ResolvedId {
+    pub fn class<'a>(&self, env : &'a Jenv<'a>) -> JClass<'a> {
+        JClass::new(self.class as jdk_sys::jclass, env)
+    }
+    pub fn field_id(&self) -> jfieldID {
+        self.id as jfieldID
+    }
+    pub fn method_id(&self) -> jmethodID {
+        self.id as jmethodID
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lookup {
+    /// instance member, falls back onto a static one with the same name and signature
+    Instance,
+    Static,
+}
+
+/// A field id which is resolved the first time it is used, meant to be put in a `static` (the generated bindings do this for every field).
+#[derive(Debug)]
+pub struct CachedField {
+    class_sig : &'static str,
+    name : &'static str,
+    sig : &'static str,
+    lookup : Lookup,
+    resolved : RwLock<Option<ResolvedId>>,
+}
+
+/// Same as [`CachedField`] for methods and constructors.
+#[derive(Debug)]
+pub struct CachedMethod {
+    class_sig : &'static str,
+    name : &'static str,
+    sig : &'static str,
+    lookup : Lookup,
+    resolved : RwLock<Option<ResolvedId>>,
+}
+
+fn resolve_cached(resolved : &RwLock<Option<ResolvedId>>, env : &Jenv, lookup : impl FnOnce(usize) -> Result<ResolvedId,JniError>) -> Result<ResolvedId,JniError> {
+    let vm = current_vm(env)?;
+    if let Some(id) = *resolved.read().unwrap() {
+        if id.vm == vm {
+            return Ok(id);
+        }
+    }
+    let id = lookup(vm)?;
+    *resolved.write().unwrap() = Some(id);
+    Ok(id)
+}
+
+impl CachedField {
+    pub const fn new(class_sig : &'static str, name : &'static str, sig : &'static str) -> Self {
+        Self { class_sig, name, sig, lookup : Lookup::Instance, resolved : RwLock::new(None) }
+    }
+    pub const fn new_static(class_sig : &'static str, name : &'static str, sig : &'static str) -> Self {
+        Self { class_sig, name, sig, lookup : Lookup::Static, resolved : RwLock::new(None) }
+    }
+    pub fn resolve<'a>(&self, env : &'a Jenv<'a>) -> Result<ResolvedId,JniError> {
+        resolve_cached(&self.resolved, env, |vm| {
+            let class = find_class_cached(env, self.class_sig)?;
+            let (id, is_static) = if self.lookup == Lookup::Instance {
+                match class.get_field_id(self.name, self.sig) {
+                    Ok(id) => (id, false),
+                    Err(e) if e.is_lookup_failure() => (class.get_static_field_id(self.name, self.sig)?, true),
+                    Err(e) => return Err(e),
+                }
+            } else {
+                (class.get_static_field_id(self.name, self.sig)?, true)
+            };
+            Ok(ResolvedId { vm, class : class.ptr as usize, id : id as usize, is_static })
+        })
+    }
+    /// reads the field from `obj`, or from its class if the field turned out to be static
+    pub fn get<'a, T : JavaField<'a>>(&self, obj : &JObject<'a>) -> Result<T,JniError> {
+        let id = self.resolve(obj.env)?;
+        if id.is_static {
+            T::get_static_field(&id.class(obj.env), id.field_id())
+        } else {
+            T::get_field(obj, id.field_id())
+        }
+    }
+    pub fn get_static<'a, T : JavaField<'a>>(&self, env : &'a Jenv<'a>) -> Result<T,JniError> {
+        let id = self.resolve(env)?;
+        T::get_static_field(&id.class(env), id.field_id())
+    }
+    pub fn set<'a, T : JavaField<'a>>(&self, obj : &JObject<'a>, new_value : T) -> Result<(),JniError> {
+        let id = self.resolve(obj.env)?;
+        if id.is_static {
+            new_value.set_static_field(&id.class(obj.env), id.field_id())
+        } else {
+            new_value.set_field(obj, id.field_id())
+        }
+    }
+    pub fn set_static<'a, T : JavaField<'a>>(&self, env : &'a Jenv<'a>, new_value : T) -> Result<(),JniError> {
+        let id = self.resolve(env)?;
+        new_value.set_static_field(&id.class(env), id.field_id())
+    }
+}
+
+impl CachedMethod {
+    pub const fn new(class_sig : &'static str, name : &'static str, sig : &'static str) -> Self {
+        Self { class_sig, name, sig, lookup : Lookup::Instance, resolved : RwLock::new(None) }
+    }
+    pub const fn new_static(class_sig : &'static str, name : &'static str, sig : &'static str) -> Self {
+        Self { class_sig, name, sig, lookup : Lookup::Static, resolved : RwLock::new(None) }
+    }
+    pub fn resolve<'a>(&self, env : &'a Jenv<'a>) -> Result<ResolvedId,JniError> {
+        resolve_cached(&self.resolved, env, |vm| {
+            let class = find_class_cached(env, self.class_sig)?;
+            let (id, is_static) = if self.lookup == Lookup::Instance {
+                match class.get_method_id(self.name, self.sig) {
+                    Ok(id) => (id, false),
+                    Err(e) if e.is_lookup_failure() => (class.get_static_method_id(self.name, self.sig)?, true),
+                    Err(e) => return Err(e),
+                }
+            } else {
+                (class.get_static_method_id(self.name, self.sig)?, true)
+            };
+            Ok(ResolvedId { vm, class : class.ptr as usize, id : id as usize, is_static })
+        })
+    }
+    /// calls the method on `obj`, or on its class if the method turned out to be static
+    pub fn call<'a, T : JavaReturn<'a>>(&self, obj : &JObject<'a>, args : &Vec<JValue>) -> Result<T,JniError> {
+        let id = self.resolve(obj.env)?;
+        let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
+        if id.is_static {
+            T::call_static_method(&id.class(obj.env), id.method_id(), &args)
+        } else {
+            T::call_method(obj, id.method_id(), &args)
+        }
+    }
+    pub fn call_static<'a, T : JavaReturn<'a>>(&self, env : &'a Jenv<'a>, args : &Vec<JValue>) -> Result<T,JniError> {
+        let id = self.resolve(env)?;
+        let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
+        T::call_static_method(&id.class(env), id.method_id(), &args)
+    }
+    /// for `<init>` methods
+    pub fn new_object<'a, T : From<JObject<'a>>>(&self, env : &'a Jenv<'a>, args : &Vec<JValue>) -> Result<T,JniError> {
+        let id = self.resolve(env)?;
+        let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
+        let obj = unchecked_jnice!(env.ptr,NewObjectA, id.class as jdk_sys::jclass, id.method_id(), args.as_ptr())?;
+        if obj.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(T::from(JObject::new(obj, env)))
+    }
+}
+
+/// types which can be read from / written to a field through an already resolved id
+pub trait JavaField<'a> : Sized {
+    fn get_field(obj : &JObject<'a>, id : jfieldID) -> Result<Self,JniError>;
+    fn get_static_field(class : &JClass<'a>, id : jfieldID) -> Result<Self,JniError>;
+    fn set_field(self, obj : &JObject<'a>, id : jfieldID) -> Result<(),JniError>;
+    fn set_static_field(self, class : &JClass<'a>, id : jfieldID) -> Result<(),JniError>;
+}
+
+/// types which can be returned from a method called through an already resolved id
+pub trait JavaReturn<'a> : Sized {
+    fn call_method(obj : &JObject<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError>;
+    fn call_static_method(class : &JClass<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError>;
+}
+
+impl<'a, T : From<JObject<'a>> + JClassInstance> JavaField<'a> for T {
+    fn get_field(obj : &JObject<'a>, id : jfieldID) -> Result<Self,JniError> {
+        let ptr = unchecked_jnice!(obj.env.ptr,GetObjectField, obj.ptr, id)?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(T::from(JObject::new(ptr, obj.env)))
+    }
+    fn get_static_field(class : &JClass<'a>, id : jfieldID) -> Result<Self,JniError> {
+        let ptr = unchecked_jnice!(class.env.ptr,GetStaticObjectField, class.ptr, id)?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(T::from(JObject::new(ptr, class.env)))
+    }
+    fn set_field(self, obj : &JObject<'a>, id : jfieldID) -> Result<(),JniError> {
+        unchecked_jnice!(obj.env.ptr,SetObjectField, obj.ptr, id, self.get_jobject().ptr)
+    }
+    fn set_static_field(self, class : &JClass<'a>, id : jfieldID) -> Result<(),JniError> {
+        unchecked_jnice!(class.env.ptr,SetStaticObjectField, class.ptr, id, self.get_jobject().ptr)
+    }
+}
+
+impl<'a, T : From<JObject<'a>>> JavaReturn<'a> for T {
+    fn call_method(obj : &JObject<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+        let ptr = unchecked_jnice!(obj.env.ptr,CallObjectMethodA, obj.ptr, id, args.as_ptr())?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(T::from(JObject::new(ptr, obj.env)))
+    }
+    fn call_static_method(class : &JClass<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+        let ptr = unchecked_jnice!(class.env.ptr,CallStaticObjectMethodA, class.ptr, id, args.as_ptr())?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(T::from(JObject::new(ptr, class.env)))
+    }
+}
+
+impl<'a> JavaReturn<'a> for () {
+    fn call_method(obj : &JObject<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+        unchecked_jnice!(obj.env.ptr,CallVoidMethodA, obj.ptr, id, args.as_ptr())
+    }
+    fn call_static_method(class : &JClass<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+        unchecked_jnice!(class.env.ptr,CallStaticVoidMethodA, class.ptr, id, args.as_ptr())
+    }
+}
+
+macro_rules! primitive_impls {
+    ($rust:ty, $get:ident, $get_static:ident, $set:ident, $set_static:ident, $call:ident, $call_static:ident, |$x:ident| $from_java:expr, |$y:ident| $to_java:expr) => {
+        impl<'a> JavaField<'a> for $rust {
+            fn get_field(obj : &JObject<'a>, id : jfieldID) -> Result<Self,JniError> {
+                let $x = unchecked_jnice!(obj.env.ptr,$get, obj.ptr, id)?;
+                Ok($from_java)
+            }
+            fn get_static_field(class : &JClass<'a>, id : jfieldID) -> Result<Self,JniError> {
+                let $x = unchecked_jnice!(class.env.ptr,$get_static, class.ptr, id)?;
+                Ok($from_java)
+            }
+            fn set_field(self, obj : &JObject<'a>, id : jfieldID) -> Result<(),JniError> {
+                let $y = self;
+                unchecked_jnice!(obj.env.ptr,$set, obj.ptr, id, $to_java)
+            }
+            fn set_static_field(self, class : &JClass<'a>, id : jfieldID) -> Result<(),JniError> {
+                let $y = self;
+                unchecked_jnice!(class.env.ptr,$set_static, class.ptr, id, $to_java)
+            }
+        }
+        impl<'a> JavaReturn<'a> for $rust {
+            fn call_method(obj : &JObject<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+                let $x = unchecked_jnice!(obj.env.ptr,$call, obj.ptr, id, args.as_ptr())?;
+                Ok($from_java)
+            }
+            fn call_static_method(class : &JClass<'a>, id : jmethodID, args : &[jvalue]) -> Result<Self,JniError> {
+                let $x = unchecked_jnice!(class.env.ptr,$call_static, class.ptr, id, args.as_ptr())?;
+                Ok($from_java)
+            }
+        }
+    };
+}
+
+primitive_impls!(bool, GetBooleanField, GetStaticBooleanField, SetBooleanField, SetStaticBooleanField, CallBooleanMethodA, CallStaticBooleanMethodA, |x| x == JNI_TRUE as u8, |y| y as u8);
+primitive_impls!(i8, GetByteField, GetStaticByteField, SetByteField, SetStaticByteField, CallByteMethodA, CallStaticByteMethodA, |x| x, |y| y);
+primitive_impls!(char, GetCharField, GetStaticCharField, SetCharField, SetStaticCharField, CallCharMethodA, CallStaticCharMethodA, |x| char::from_u32(x as u32).unwrap_or(char::REPLACEMENT_CHARACTER), |y| y as u16);
+primitive_impls!(i16, GetShortField, GetStaticShortField, SetShortField, SetStaticShortField, CallShortMethodA, CallStaticShortMethodA, |x| x, |y| y);
+primitive_impls!(i32, GetIntField, GetStaticIntField, SetIntField, SetStaticIntField, CallIntMethodA, CallStaticIntMethodA, |x| x, |y| y);
+primitive_impls!(i64, GetLongField, GetStaticLongField, SetLongField, SetStaticLongField, CallLongMethodA, CallStaticLongMethodA, |x| x, |y| y);
+primitive_impls!(f32, GetFloatField, GetStaticFloatField, SetFloatField, SetStaticFloatField, CallFloatMethodA, CallStaticFloatMethodA, |x| x, |y| y);
+primitive_impls!(f64, GetDoubleField, GetStaticDoubleField, SetDoubleField, SetStaticDoubleField, CallDoubleMethodA, CallStaticDoubleMethodA, |x| x, |y| y);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jstring::JString, vm::tests::attach};
+
+    #[test]
+    fn classes() {
+        let env = attach();
+        let integer = find_class_cached(&env, "java/lang/Integer").unwrap();
+        assert_eq!(integer.ptr, env.find_class_cached("java/lang/Integer").unwrap().ptr);
+        // 2 is JNIGlobalRefType
+        assert_eq!(unchecked_jnic!(env.ptr,GetObjectRefType, integer.ptr) as u32, 2);
+        assert!(matches!(find_class_cached(&env, "does/not/Exist"), Err(JniError::ClassNotFound(_))));
+    }
+
+    #[test]
+    fn members() {
+        static PARSE : CachedMethod = CachedMethod::new("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I");
+        static LENGTH : CachedMethod = CachedMethod::new("java/lang/String", "length", "()I");
+        static BUILDER : CachedMethod = CachedMethod::new("java/lang/StringBuilder", "<init>", "(Ljava/lang/String;)V");
+        static MAX : CachedField = CachedField::new_static("java/lang/Integer", "MAX_VALUE", "I");
+        static MISSING : CachedMethod = CachedMethod::new("java/lang/Integer", "missing", "()V");
+        let env = attach();
+        let text = JString::new("12", &env);
+
+        assert_eq!(PARSE.call_static::<i32>(&env, &vec![JValue::from(&text.obj)]).unwrap(), 12);
+        // the instance lookup fell back onto the static method, and the id is kept
+        let parse = PARSE.resolve(&env).unwrap();
+        assert!(parse.is_static);
+        assert_eq!(PARSE.resolve(&env).unwrap().method_id(), parse.method_id());
+        assert_eq!(LENGTH.call::<i32>(&text.obj, &vec![]).unwrap(), 2);
+        assert!(!LENGTH.resolve(&env).unwrap().is_static);
+        let builder : JObject = BUILDER.new_object(&env, &vec![JValue::from(&text.obj)]).unwrap();
+        assert!(!builder.ptr.is_null());
+        assert_eq!(MAX.get_static::<i32>(&env).unwrap(), i32::MAX);
+        // failed lookups are tried again next time
+        for _ in 0..2 {
+            assert!(matches!(MISSING.resolve(&env), Err(JniError::MethodNotFound {..})));
+        }
+        assert!(MISSING.resolved.read().unwrap().is_none());
+    }
+}
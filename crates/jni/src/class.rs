@@ -2,7 +2,7 @@ use std::{ffi::CString, ptr};
 
 use jdk_sys::{jfieldID, jmethodID, jvalue, JNI_TRUE};
 
-use crate::{env::Jenv, object::JObject, unchecked_jnic, unchecked_jnice, jvalue::JValue};
+use crate::{env::Jenv, object::JObject, unchecked_jnic, unchecked_jnice, jvalue::JValue, error::JniError};
 #[derive(Debug, Clone,Copy)]
 pub struct JClass<'a> {
     pub ptr : jdk_sys::jclass,
@@ -15,7 +15,7 @@ impl<'a> JClass<'a> {
             env,
         }
     }
-    pub fn find(env : &'a Jenv,clz:&str) -> Result<Self,()> {
+    pub fn find(env : &'a Jenv,clz:&str) -> Result<Self,JniError> {
         env.find_class(clz)
     }
     pub fn null(env : &'a Jenv) -> Self {
@@ -24,113 +24,117 @@ impl<'a> JClass<'a> {
             env
         }
     }
-    pub fn get_super<T: From<JObject<'a>>>(&self) -> Result<JClass<'a>,()> {
+    pub fn get_super<T: From<JObject<'a>>>(&self) -> Result<JClass<'a>,JniError> {
         let obj = unchecked_jnic!(self.env.ptr,GetSuperclass, self.ptr);
         if obj.is_null() {
-            return Err(());
+            return Err(JniError::NullReturn);
         }
 
         Ok(JClass::new(obj,self.env))
     }
-    pub fn new_object<T : From<JObject<'a>>>(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<T,()> {
+    pub fn new_object<T : From<JObject<'a>>>(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<T,JniError> {
         let mid=  self.get_method_id(name,sig)?;
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
 
-        let obj = unchecked_jnic!(self.env.ptr,NewObjectA, self.ptr, mid,args.as_ptr());
+        let obj = unchecked_jnice!(self.env.ptr,NewObjectA, self.ptr, mid,args.as_ptr())?;
         if obj.is_null() {
-            return Err(());
+            return Err(JniError::NullReturn);
         }
         Ok(T::from(JObject::new(obj,self.env)))
     }
 
-    pub fn get_field_id(&self, name:&str, sig:&str) -> Result<jfieldID,()> {
-        let name = CString::new(name).unwrap();
-        let sig = CString::new(sig).unwrap();
-        unchecked_jnice!(self.env.ptr,GetFieldID, self.ptr, name.as_ptr(), sig.as_ptr())
+    pub fn get_field_id(&self, name:&str, sig:&str) -> Result<jfieldID,JniError> {
+        let c_name = CString::new(name)?;
+        let c_sig = CString::new(sig)?;
+        unchecked_jnice!(self.env.ptr,GetFieldID, self.ptr, c_name.as_ptr(), c_sig.as_ptr())
+            .map_err(|_| JniError::FieldNotFound { name: name.to_string(), sig: sig.to_string() })
 
     }
-    pub fn get_static_field_id(&self, name:&str, sig:&str) -> Result<jfieldID,()> {
-        let name = CString::new(name).unwrap();
-        let sig = CString::new(sig).unwrap();
-        unchecked_jnice!(self.env.ptr,GetStaticFieldID, self.ptr, name.as_ptr(), sig.as_ptr())
+    pub fn get_static_field_id(&self, name:&str, sig:&str) -> Result<jfieldID,JniError> {
+        let c_name = CString::new(name)?;
+        let c_sig = CString::new(sig)?;
+        unchecked_jnice!(self.env.ptr,GetStaticFieldID, self.ptr, c_name.as_ptr(), c_sig.as_ptr())
+            .map_err(|_| JniError::FieldNotFound { name: name.to_string(), sig: sig.to_string() })
         
     }
-    pub fn get_method_id(&self, name:&str, sig:&str) -> Result<jmethodID,()> {
-        let name = CString::new(name).unwrap();
-        let sig = CString::new(sig).unwrap();
-        unchecked_jnice!(self.env.ptr,GetMethodID, self.ptr, name.as_ptr(), sig.as_ptr())
+    pub fn get_method_id(&self, name:&str, sig:&str) -> Result<jmethodID,JniError> {
+        let c_name = CString::new(name)?;
+        let c_sig = CString::new(sig)?;
+        unchecked_jnice!(self.env.ptr,GetMethodID, self.ptr, c_name.as_ptr(), c_sig.as_ptr())
+            .map_err(|_| JniError::MethodNotFound { name: name.to_string(), sig: sig.to_string() })
 
     }
-    pub fn get_static_method_id(&self, name:&str, sig:&str) -> Result<jmethodID,()> {
-        let name = CString::new(name).unwrap();
-        let sig = CString::new(sig).unwrap();
-        unchecked_jnice!(self.env.ptr,GetStaticMethodID, self.ptr, name.as_ptr(), sig.as_ptr())
+    pub fn get_static_method_id(&self, name:&str, sig:&str) -> Result<jmethodID,JniError> {
+        let c_name = CString::new(name)?;
+        let c_sig = CString::new(sig)?;
+        unchecked_jnice!(self.env.ptr,GetStaticMethodID, self.ptr, c_name.as_ptr(), c_sig.as_ptr())
+            .map_err(|_| JniError::MethodNotFound { name: name.to_string(), sig: sig.to_string() })
         
     }
 
     // static methods
 
-    pub fn call_static_object_method<T:From<JObject<'a>>>(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<T,()> {
+    pub fn call_static_object_method<T:From<JObject<'a>>>(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<T,JniError> {
         let mut obj = ptr::null_mut();
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
 
         let mid=  self.get_static_method_id(name,sig)?;
         obj = unchecked_jnice!(self.env.ptr,CallStaticObjectMethodA, self.ptr, mid,args.as_ptr())?;
         if obj.is_null() {
-            return Err(());
+            return Err(JniError::NullReturn);
         }
 
         Ok(T::from(JObject::new(obj,self.env)))
     }
-    pub fn call_static_boolean_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<bool,()> {
+    pub fn call_static_boolean_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<bool,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         Ok(unchecked_jnice!(self.env.ptr,CallStaticBooleanMethodA, self.ptr, mid,args.as_ptr() )? == JNI_TRUE as u8)
     }
-    pub fn call_static_byte_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i8,()> {
+    pub fn call_static_byte_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i8,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticByteMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_char_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<char,()> {
+    pub fn call_static_char_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<char,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         Ok(unchecked_jnice!(self.env.ptr,CallStaticCharMethodA, self.ptr, mid,args.as_ptr())? as u8 as char)
     }
-    pub fn call_static_short_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i16,()> {
+    pub fn call_static_short_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i16,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticShortMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_int_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i32,()> {
+    pub fn call_static_int_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i32,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticIntMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_long_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i64,()> {
+    pub fn call_static_long_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<i64,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticLongMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_float_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<f32,()> {
+    pub fn call_static_float_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<f32,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticFloatMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_double_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<f64,()> {
+    pub fn call_static_double_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<f64,JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
         unchecked_jnice!(self.env.ptr,CallStaticDoubleMethodA, self.ptr, mid,args.as_ptr() )
     }
-    pub fn call_static_void_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<(),()> {
+    pub fn call_static_void_method(&self,name:&str,sig:&str,args:&Vec<JValue>) -> Result<(),JniError> {
         let args = args.iter().map(|f|f.get_c_style()).collect::<Vec<jvalue>>();
         let mid=  self.get_static_method_id(name,sig)?;
 
@@ -141,86 +145,86 @@ impl<'a> JClass<'a> {
 
     // get
 
-    pub fn get_static_object_field<T: From<JObject<'a>>>(&self,name:&str,sig:&str) -> Result<T,()> {
+    pub fn get_static_object_field<T: From<JObject<'a>>>(&self,name:&str,sig:&str) -> Result<T,JniError> {
         let mut obj = ptr::null_mut();
         let fid = self.get_static_field_id(name,sig)?;
         obj = unchecked_jnice!(self.env.ptr,GetStaticObjectField, self.ptr, fid)?;
     
         if obj.is_null() {
-            return Err(());
+            return Err(JniError::NullReturn);
         }
         Ok(T::from(JObject::new(obj,self.env)))
     }
 
-    pub fn get_static_boolean_field(&self,name:&str,sig:&str) -> Result<bool,()> {
+    pub fn get_static_boolean_field(&self,name:&str,sig:&str) -> Result<bool,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticBooleanField, self.ptr, fid)? == JNI_TRUE as u8)
     }
-    pub fn get_static_byte_field(&self,name:&str,sig:&str) -> Result<i8,()> {
+    pub fn get_static_byte_field(&self,name:&str,sig:&str) -> Result<i8,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticByteField, self.ptr, fid)? as i8)
     }
-    pub fn get_static_char_field(&self,name:&str,sig:&str) -> Result<char,()> {
+    pub fn get_static_char_field(&self,name:&str,sig:&str) -> Result<char,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticCharField, self.ptr, fid)? as u8 as char)
     }
-    pub fn get_static_short_field(&self,name:&str,sig:&str) -> Result<i16,()> {
+    pub fn get_static_short_field(&self,name:&str,sig:&str) -> Result<i16,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticShortField, self.ptr, fid)?)
     }
-    pub fn get_static_int_field(&self,name:&str,sig:&str) -> Result<i32,()> {
+    pub fn get_static_int_field(&self,name:&str,sig:&str) -> Result<i32,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticIntField, self.ptr, fid)?)
     }
-    pub fn get_static_long_field(&self,name:&str,sig:&str) -> Result<i64,()> {
+    pub fn get_static_long_field(&self,name:&str,sig:&str) -> Result<i64,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticLongField, self.ptr, fid)?)
     }
-    pub fn get_static_float_field(&self,name:&str,sig:&str) -> Result<f32,()> {
+    pub fn get_static_float_field(&self,name:&str,sig:&str) -> Result<f32,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticFloatField, self.ptr, fid)?)
     }
-    pub fn get_static_double_field(&self,name:&str,sig:&str) -> Result<f64,()> {
+    pub fn get_static_double_field(&self,name:&str,sig:&str) -> Result<f64,JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         Ok(unchecked_jnice!(self.env.ptr,GetStaticDoubleField, self.ptr, fid)?)
     }
 
     // set
 
-    pub fn set_static_object_field(&self,name:&str,sig:&str,new_value:&'a JObject<'a>) -> Result<(),()> {
+    pub fn set_static_object_field(&self,name:&str,sig:&str,new_value:&'a JObject<'a>) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticObjectField, self.ptr, fid,new_value.ptr)
     }
 
-    pub fn set_static_boolean_field(&self,name:&str,sig:&str, new_value:bool) -> Result<(),()> {
+    pub fn set_static_boolean_field(&self,name:&str,sig:&str, new_value:bool) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticBooleanField, self.ptr, fid,new_value as u8)
     }
-    pub fn set_static_byte_field(&self,name:&str,sig:&str,new_value:i8) -> Result<(),()> {
+    pub fn set_static_byte_field(&self,name:&str,sig:&str,new_value:i8) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticByteField, self.ptr, fid,new_value as i8)
     }
-    pub fn set_static_char_field(&self,name:&str,sig:&str,new_value:char) -> Result<(),()> {
+    pub fn set_static_char_field(&self,name:&str,sig:&str,new_value:char) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticCharField, self.ptr, fid, new_value as u16)
     }
-    pub fn set_static_short_field(&self,name:&str,sig:&str,new_value:i16) -> Result<(),()> {
+    pub fn set_static_short_field(&self,name:&str,sig:&str,new_value:i16) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticShortField, self.ptr, fid,new_value)
     }
-    pub fn set_static_int_field(&self,name:&str,sig:&str,new_value:i32) -> Result<(),()> {
+    pub fn set_static_int_field(&self,name:&str,sig:&str,new_value:i32) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticIntField, self.ptr, fid,new_value)
     }
-    pub fn set_static_long_field(&self,name:&str,sig:&str,new_value:i64) -> Result<(),()> {
+    pub fn set_static_long_field(&self,name:&str,sig:&str,new_value:i64) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticLongField, self.ptr, fid,new_value)
     }
-    pub fn set_static_float_field(&self,name:&str,sig:&str,new_value:f32) -> Result<(),()> {
+    pub fn set_static_float_field(&self,name:&str,sig:&str,new_value:f32) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticFloatField, self.ptr, fid,new_value)
     }
-    pub fn set_static_double_field(&self,name:&str,sig:&str,new_value:f64) -> Result<(),()> {
+    pub fn set_static_double_field(&self,name:&str,sig:&str,new_value:f64) -> Result<(),JniError> {
         let fid = self.get_static_field_id(name,sig)?;
         unchecked_jnice!(self.env.ptr,SetStaticDoubleField, self.ptr, fid,new_value)
     }
@@ -234,9 +238,9 @@ impl<'a> JClass<'a> {
 }
 
 pub trait JClassExt<T> {
-    fn set_static_field(cls:&JClass, name: &str, sig: &str, new_value: T) -> Result<(), ()>;
-    fn get_static_field(cls:&JClass, name: &str, sig: &str) -> Result<T, ()>;
-    fn call_static_method(cls:&JClass, name: &str, sig: &str, args:&Vec<JValue>) -> Result<T, ()>;
+    fn set_static_field(cls:&JClass, name: &str, sig: &str, new_value: T) -> Result<(),JniError>;
+    fn get_static_field(cls:&JClass, name: &str, sig: &str) -> Result<T,JniError>;
+    fn call_static_method(cls:&JClass, name: &str, sig: &str, args:&Vec<JValue>) -> Result<T,JniError>;
 }
 
 jni_proc::generate_jclass_impls!(bool,boolean);
@@ -1,8 +1,8 @@
 use std::{ffi::CString, marker::PhantomData, ptr};
 
-use jdk_sys::{JNIEnv, JNI_VERSION_10, JNI_VERSION_1_1, JNI_VERSION_1_2, JNI_VERSION_1_4, JNI_VERSION_1_6, JNI_VERSION_1_8};
+use jdk_sys::{JNIEnv, jobject, JNI_OK, JNI_VERSION_10, JNI_VERSION_1_1, JNI_VERSION_1_2, JNI_VERSION_1_4, JNI_VERSION_1_6, JNI_VERSION_1_8};
 
-use crate::{unchecked_jnic, unchecked_jnice, class::JClass};
+use crate::{unchecked_jnic, unchecked_jnice, class::JClass, object::{JObject, JClassInstance}, jstring::JString, jvalue::JValue, error::JniError, jthrowable::{JThrowable, ThrowableError, ExceptionDefault}};
 
 #[repr(transparent)]
 #[derive(Copy, Clone,Debug)]
@@ -11,6 +11,13 @@ pub struct Jenv<'a>{
     lifetime : PhantomData<&'a ()>,
 }
 impl Jenv<'_> {
+    /// wraps an env pointer handed to us by the jvm (native method arguments, `GetEnv`, ...)
+    pub fn from_raw(ptr : *mut JNIEnv) -> Self {
+        Jenv {
+            ptr,
+            lifetime : PhantomData,
+        }
+    }
     fn get_lowest(&self) -> jdk_sys::jniNativeInterface {
         if self.ptr.is_null() {
             panic!("JNIEnv is null");
@@ -36,11 +43,11 @@ impl Jenv<'_> {
             _=> "unknown",
         }.to_string()
     }
-    pub fn find_class(&self, name:&str) -> Result<JClass,()> {
-        let name = CString::new(name).unwrap();
-        let jobj = unchecked_jnice!(self.ptr,FindClass, name.as_ptr())?;
+    pub fn find_class(&self, name:&str) -> Result<JClass,JniError> {
+        let c_name = CString::new(name)?;
+        let jobj = unchecked_jnice!(self.ptr,FindClass, c_name.as_ptr()).map_err(|_| JniError::ClassNotFound(name.to_string()))?;
         if jobj.is_null(){
-            return Err(())
+            return Err(JniError::ClassNotFound(name.to_string()))
         }
         // if unchecked_jnic!(self.ptr,ExceptionCheck) == jdk_sys::JNI_TRUE as u8 {
         //     unchecked_jnic!(self.ptr,ExceptionDescribe);
@@ -49,7 +56,147 @@ impl Jenv<'_> {
         let jobj_custom = JClass::new(jobj,&self);
         Ok(jobj_custom)
     }
+    /// see [`crate::cache::find_class_cached`]
+    pub fn find_class_cached<'b>(&'b self, name:&str) -> Result<JClass<'b>,JniError> {
+        crate::cache::find_class_cached(self, name)
+    }
+    /// `DefineClass`, loads a class from its class file bytes (eg. `include_bytes!("Helper.class")`) into `loader`, `None` is the bootstrap loader.
+    /// `name` uses slashes (`net/example/Helper`) and must match the name inside the class file.
+    pub fn define_class<'b>(&'b self, name:&str, loader:Option<&JObject>, bytes:&[u8]) -> Result<JClass<'b>,JniError> {
+        let c_name = CString::new(name)?;
+        let loader = loader.map(|l| l.ptr).unwrap_or(ptr::null_mut());
+        let class = unchecked_jnice!(self.ptr,DefineClass, c_name.as_ptr(), loader, bytes.as_ptr() as *const i8, bytes.len() as i32)?;
+        if class.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(JClass::new(class, self))
+    }
+    /// the context class loader of the current thread, on the game's threads this is the loader the minecraft classes live in.
+    /// threads attached from rust usually have none (`NullReturn`), use [`Self::class_loader_of`] there.
+    pub fn context_class_loader<'b>(&'b self) -> Result<JObject<'b>,JniError> {
+        let thread : JObject = self.find_class_cached("java/lang/Thread")?.call_static_object_method("currentThread", "()Ljava/lang/Thread;", &vec![])?;
+        self.thread_context_class_loader(&thread)
+    }
+    pub fn thread_context_class_loader<'b>(&'b self, thread:&JObject) -> Result<JObject<'b>,JniError> {
+        let loader = unchecked_jnice!(self.ptr,CallObjectMethodA, thread.ptr, self.find_class_cached("java/lang/Thread")?.get_method_id("getContextClassLoader", "()Ljava/lang/ClassLoader;")?, ptr::null())?;
+        if loader.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(JObject::new(loader, self))
+    }
+    /// the loader that loaded the class of `obj`, eg. `class_loader_of(&minecraft_client)` for the game's loader from any thread
+    pub fn class_loader_of<'b>(&'b self, obj:&impl JClassInstance) -> Result<JObject<'b>,JniError> {
+        let class = unchecked_jnic!(self.ptr,GetObjectClass, obj.get_jobject().ptr);
+        let loader = unchecked_jnice!(self.ptr,CallObjectMethodA, class, self.find_class_cached("java/lang/Class")?.get_method_id("getClassLoader", "()Ljava/lang/ClassLoader;")?, ptr::null());
+        unchecked_jnic!(self.ptr,DeleteLocalRef, class);
+        let loader = loader?;
+        if loader.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(JObject::new(loader, self))
+    }
+    /// `ClassLoader::loadClass`, unlike [`Self::find_class`] this sees classes which are only visible to `loader`. `name` uses slashes.
+    pub fn load_class<'b>(&'b self, name:&str, loader:&JObject) -> Result<JClass<'b>,JniError> {
+        let binary_name = JString::new(&name.replace('/', "."), self);
+        let class = unchecked_jnice!(self.ptr,CallObjectMethodA, loader.ptr,
+            self.find_class_cached("java/lang/ClassLoader")?.get_method_id("loadClass", "(Ljava/lang/String;)Ljava/lang/Class;")?,
+            [JValue::from(&binary_name.obj).get_c_style()].as_ptr())
+            .map_err(|_| JniError::ClassNotFound(name.to_string()))?;
+        if class.is_null() {
+            return Err(JniError::ClassNotFound(name.to_string()));
+        }
+        Ok(JClass::new(class, self))
+    }
+    /// takes and clears the pending java exception, if there is one
+    pub fn take_exception(&self) -> Option<JThrowable> {
+        JThrowable::catch(self.ptr)
+    }
+    /// constructs and throws a new exception of `class` (eg. `java/lang/IllegalStateException`), it is raised once control returns to java
+    pub fn throw_new(&self, class:&str, message:&str) -> Result<(),JniError> {
+        let clz = self.find_class(class)?;
+        let c_message = CString::new(message)?;
+        let status = unchecked_jnic!(self.ptr,ThrowNew, clz.ptr, c_message.as_ptr());
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        Ok(())
+    }
+    /// rethrows an exception which was previously caught, eg. from [`JniError::JavaException`].
+    /// if its reference is gone a new exception of the same class and message is thrown instead, which loses the stack trace
+    pub fn throw(&self, throwable:&JThrowable) -> Result<(),JniError> {
+        let live = throwable.reference.as_ref().map(|r| r.as_raw())
+            // 0 is JNIInvalidRefType
+            .filter(|raw| !raw.is_null() && unchecked_jnic!(self.ptr,GetObjectRefType, *raw) as u32 != 0);
+        let Some(raw) = live else {
+            return self.throw_new(&throwable.exception_class(), throwable.message.as_deref().unwrap_or(""));
+        };
+        let status = unchecked_jnic!(self.ptr,Throw, raw);
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        Ok(())
+    }
+    /// runs `f` inside a new local reference frame (`PushLocalFrame` / `PopLocalFrame`), every local reference created inside is freed afterwards,
+    /// also when `f` panics. objects created inside can't be returned, see [`Self::with_local_frame_returning`] to keep one of them.
+    /// java exceptions raised inside are safe to return, [`JThrowable`] holds a global reference
+    pub fn with_local_frame<R>(&self, capacity:i32, f:impl FnOnce(&Jenv) -> Result<R,JniError>) -> Result<R,JniError> {
+        let frame = LocalFrame::push(self, capacity)?;
+        let inner = *self;
+        let result = f(&inner);
+        drop(frame);
+        result
+    }
+    /// like [`Self::with_local_frame`], but the object returned by `f` is promoted into the outer frame
+    pub fn with_local_frame_returning<'b>(&'b self, capacity:i32, f:impl for<'f> FnOnce(&'f Jenv<'f>) -> Result<JObject<'f>,JniError>) -> Result<JObject<'b>,JniError> {
+        let frame = LocalFrame::push(self, capacity)?;
+        let inner = *self;
+        let keep = f(&inner)?.ptr;
+        let obj = frame.pop(keep);
+        if obj.is_null() {
+            return Ok(JObject::null(self));
+        }
+        Ok(JObject::new(obj, self))
+    }
+    /// for native methods, unwraps `result` or throws the error into java and returns a placeholder value which java will ignore
+    pub fn unwrap_or_throw<T : ExceptionDefault, E : ThrowableError>(&self, result:Result<T,E>) -> T {
+        match result {
+            Ok(x) => x,
+            Err(e) => {
+                e.throw_into(self);
+                T::exception_default()
+            }
+        }
+    }
+}
+/// a pushed local reference frame, popped when dropped so unwinding out of [`Jenv::with_local_frame`] doesn't leave it on the stack
+struct LocalFrame<'a> {
+    env : &'a Jenv<'a>,
+    popped : bool,
+}
+
+impl<'a> LocalFrame<'a> {
+    fn push(env : &'a Jenv<'a>, capacity:i32) -> Result<Self,JniError> {
+        let status = unchecked_jnic!(env.ptr,PushLocalFrame, capacity);
+        if status != JNI_OK as i32 {
+            return Err(env.take_exception().map(JniError::JavaException).unwrap_or(JniError::Status(status)));
+        }
+        Ok(Self { env, popped : false })
+    }
+    /// pops the frame, `keep` (a reference from inside it, or null) comes back as a reference in the outer frame
+    fn pop(mut self, keep:jobject) -> jobject {
+        self.popped = true;
+        unchecked_jnic!(self.env.ptr,PopLocalFrame, keep)
+    }
+}
+
+impl Drop for LocalFrame<'_> {
+    fn drop(&mut self) {
+        if !self.popped {
+            unchecked_jnic!(self.env.ptr,PopLocalFrame, ptr::null_mut());
+        }
+    }
 }
+
 impl Default for Jenv<'_> {
     fn default() -> Self {
         Jenv {
@@ -57,4 +204,52 @@ impl Default for Jenv<'_> {
             lifetime : PhantomData,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+    use crate::vm::tests::attach;
+
+    #[test]
+    fn local_frames() {
+        let env = attach();
+        let length = env.with_local_frame(4, |env| Ok(JString::new("inside", env).to_rust_string()?.len())).unwrap();
+        assert_eq!(length, 6);
+        assert!(matches!(env.with_local_frame(4, |env| env.find_class("does/not/Exist").map(|_| ())), Err(JniError::ClassNotFound(_))));
+
+        let kept = env.with_local_frame_returning(4, |env| {
+            let _ = JString::new("dropped", env);
+            Ok(JString::new("kept", env).obj)
+        }).unwrap();
+        // 1 is JNILocalRefType, the object now lives in the outer frame
+        assert_eq!(unchecked_jnic!(env.ptr,GetObjectRefType, kept.ptr) as u32, 1);
+        assert_eq!(String::try_from(&JString::from(kept)).unwrap(), "kept");
+        assert!(env.with_local_frame_returning(4, |env| Ok(JObject::null(env))).unwrap().ptr.is_null());
+
+        // the frame is popped on the way out of a panic
+        assert!(catch_unwind(AssertUnwindSafe(|| env.with_local_frame(4, |_| -> Result<(),JniError> { panic!("inside a frame") }))).is_err());
+        assert!(env.find_class("java/lang/Object").is_ok());
+        assert!(env.with_local_frame(-1, |_| Ok(())).is_err());
+        assert!(env.take_exception().is_none());
+    }
+
+    #[test]
+    fn defined_classes() {
+        const NAME : &str = "mci/RustProxyHandler";
+        let env = attach();
+        let urls = crate::jarray::JArray::<JObject>::create_object(&env, 0, &env.find_class("java/net/URL").unwrap(), None).unwrap();
+        let loader : JObject = env.find_class("java/net/URLClassLoader").unwrap().new_object("<init>", "([Ljava/net/URL;)V", &vec![JValue::from(&urls.ptr)]).unwrap();
+        let defined = env.define_class(NAME, Some(&loader), include_bytes!("../java/mci/RustProxyHandler.class")).unwrap();
+        let loaded = env.load_class(NAME, &loader).unwrap();
+        assert_eq!(unchecked_jnic!(env.ptr,IsSameObject, defined.ptr, loaded.ptr), jdk_sys::JNI_TRUE as u8);
+        // only visible through its loader
+        assert!(matches!(env.load_class("mci/Missing", &loader), Err(JniError::ClassNotFound(_))));
+        // a class can only be defined once per loader
+        assert!(matches!(env.define_class(NAME, Some(&loader), include_bytes!("../java/mci/RustProxyHandler.class")), Err(JniError::JavaException(t)) if t.class_name == "java.lang.LinkageError"));
+        // URLClassLoader itself comes from the bootstrap loader
+        assert!(matches!(env.class_loader_of(&loader), Err(JniError::NullReturn)));
+        assert!(env.take_exception().is_none());
+    }
+}
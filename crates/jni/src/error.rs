@@ -0,0 +1,99 @@
+use std::{error::Error, ffi::NulError, fmt::{Display, Formatter}};
+
+use crate::jthrowable::JThrowable;
+
+#[derive(Debug, Clone)]
+pub enum JniError {
+    /// `FindClass` could not resolve the class, the name is the one that was looked up
+    ClassNotFound(String),
+    MethodNotFound {
+        name : String,
+        sig : String,
+    },
+    FieldNotFound {
+        name : String,
+        sig : String,
+    },
+    /// a call that must produce an object returned `null`
+    NullReturn,
+    /// a java exception was thrown (and cleared) during the call
+    JavaException(JThrowable),
+    /// string data coming from / going to the jvm was not valid
+    InvalidUtf(String),
+    IndexOutOfBounds {
+        index : usize,
+        length : usize,
+    },
+    /// a raw jni status code that was not `JNI_OK`
+    Status(i32),
+    /// a jvmti call failed, the code is a `jvmtiError` (see [`crate::jvmti::error_name`])
+    Jvmti(u32),
+}
+
+impl JniError {
+    /// true when the error came from a failed member lookup, used to decide whether to fall back onto the static variant
+    pub fn is_lookup_failure(&self) -> bool {
+        matches!(self, JniError::MethodNotFound {..} | JniError::FieldNotFound {..})
+    }
+}
+
+impl Display for JniError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JniError::ClassNotFound(name) => write!(f, "class not found: {}", name),
+            JniError::MethodNotFound { name, sig } => write!(f, "method not found: {}{}", name, sig),
+            JniError::FieldNotFound { name, sig } => write!(f, "field not found: {} {}", name, sig),
+            JniError::NullReturn => write!(f, "jni call returned null"),
+            JniError::JavaException(throwable) => write!(f, "java exception was thrown: {}", throwable),
+            JniError::InvalidUtf(reason) => write!(f, "invalid string data: {}", reason),
+            JniError::IndexOutOfBounds { index, length } => write!(f, "index {} is out of bounds for length {}", index, length),
+            JniError::Status(code) => write!(f, "jni call failed with status {}", code),
+            JniError::Jvmti(code) => write!(f, "jvmti call failed with {} ({})", crate::jvmti::error_name(*code), code),
+        }
+    }
+}
+
+impl Error for JniError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JniError::JavaException(throwable) => Some(throwable),
+            _ => None,
+        }
+    }
+}
+
+impl From<NulError> for JniError {
+    fn from(x: NulError) -> Self {
+        JniError::InvalidUtf(x.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[test]
+    fn messages() {
+        let missing = JniError::MethodNotFound { name : "add".into(), sig : "(I)I".into() };
+        assert_eq!(missing.to_string(), "method not found: add(I)I");
+        assert!(missing.is_lookup_failure() && JniError::FieldNotFound { name : "x".into(), sig : "D".into() }.is_lookup_failure());
+        assert!(!JniError::ClassNotFound("a/B".into()).is_lookup_failure());
+        assert_eq!(JniError::IndexOutOfBounds { index : 3, length : 2 }.to_string(), "index 3 is out of bounds for length 2");
+        let nul = JniError::from(CString::new("a\0b").unwrap_err());
+        assert!(matches!(nul, JniError::InvalidUtf(_)) && nul.source().is_none());
+    }
+
+    #[test]
+    fn failed_lookups() {
+        let env = crate::vm::tests::attach();
+        assert!(matches!(env.find_class("does/not/Exist"), Err(JniError::ClassNotFound(name)) if name == "does/not/Exist"));
+        let object = env.find_class("java/lang/Object").unwrap();
+        let err = object.get_method_id("missing", "()V").unwrap_err();
+        assert!(matches!(&err, JniError::MethodNotFound { name, sig } if name == "missing" && sig == "()V"));
+        assert!(matches!(object.get_static_field_id("missing", "I"), Err(JniError::FieldNotFound {..})));
+        // the NoClassDefFoundError / NoSuchMethodError behind them are cleared
+        assert!(env.take_exception().is_none());
+    }
+}
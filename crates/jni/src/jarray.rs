@@ -1,7 +1,10 @@
-use std::{ops::{Index, Deref, DerefMut}, default, marker::PhantomData};
+use std::{ops::{Deref, DerefMut}, marker::PhantomData, ptr};
 
-use crate::{unchecked_jnic, object::JObject, jvalue::JValue};
+use jdk_sys::{JNIEnv, jarray, jboolean, jbyte, jchar, jshort, jint, jlong, jfloat, jdouble, JNI_ABORT, JNI_TRUE};
+
+use crate::{unchecked_jnic, unchecked_jnice, object::JObject, class::JClass, error::JniError};
 use crate::object::JClassInstance;
+use crate::refs::{JRefTarget, LocalRef};
 use super::env::Jenv;
 pub struct JArray<'a,T> {
     pub ptr : JObject<'a>,
@@ -11,7 +14,7 @@ pub struct JArray<'a,T> {
 
 impl<'a,T> JArray<'a,T> {
     pub fn new(obj:JObject<'a>) -> JArray<'a,T> {
-        let length = unchecked_jnic!(obj.env.ptr,GetArrayLength, obj.ptr) as usize;
+        let length = if obj.ptr.is_null() { 0 } else { unchecked_jnic!(obj.env.ptr,GetArrayLength, obj.ptr) as usize };
 
         JArray {
             ptr : obj,
@@ -22,18 +25,101 @@ impl<'a,T> JArray<'a,T> {
     pub fn len(&self) -> usize {
         self.length
     }
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+    fn check_range(&self, start:usize, len:usize) -> Result<(),JniError> {
+        if start + len > self.length {
+            return Err(JniError::IndexOutOfBounds { index: start + len, length: self.length });
+        }
+        Ok(())
+    }
 }
 
-impl<'a,T> From<JObject<'a>> for JArray<'a,T> {
-    fn from(obj : JObject<'a>) -> Self {
-        Self::new(obj)
+impl<'a,T : ArrayElement<'a>> JArray<'a,T> {
+    pub fn get(&self, index:usize) -> Result<T,JniError> {
+        self.check_range(index, 1)?;
+        T::get_region(&self.ptr, index, 1)?.pop().ok_or(JniError::NullReturn)
+    }
+    pub fn set(&self, index:usize, value:T) -> Result<(),JniError> {
+        self.check_range(index, 1)?;
+        T::set_region(&self.ptr, index, &[value])
+    }
+    /// copies `len` elements starting at `start` out of the array
+    pub fn get_region(&self, start:usize, len:usize) -> Result<Vec<T>,JniError> {
+        self.check_range(start, len)?;
+        T::get_region(&self.ptr, start, len)
+    }
+    /// copies `values` into the array starting at `start`
+    pub fn set_region(&self, start:usize, values:&[T]) -> Result<(),JniError> {
+        self.check_range(start, values.len())?;
+        T::set_region(&self.ptr, start, values)
+    }
+    pub fn get_all(&self) -> Result<Vec<T>,JniError> {
+        T::get_region(&self.ptr, 0, self.length)
+    }
+    pub fn iter(&self) -> JArrayIter<'_,'a,T> {
+        JArrayIter {
+            array : self,
+            index : 0,
+        }
     }
 }
-impl<'a> JArray<'a,i32> {
-    pub fn get_all(&self) -> Vec<i32> {
-        unsafe {
-            Vec::from_raw_parts(unchecked_jnic!(self.ptr.env.ptr,GetIntArrayElements, self.ptr.ptr, std::ptr::null_mut()), self.length, self.length)
+
+impl<'a,T : JPrimitive + ArrayElement<'a>> JArray<'a,T> {
+    /// `New<Type>Array`, every element starts as 0 / false
+    pub fn create(env:&'a Jenv<'a>, length:usize) -> Result<Self,JniError> {
+        let ptr = T::new_array(env.ptr, length)?;
+        Ok(Self::new(JObject::new(ptr, env)))
+    }
+    pub fn from_slice(env:&'a Jenv<'a>, values:&[T]) -> Result<Self,JniError> {
+        let array = Self::create(env, values.len())?;
+        array.set_region(0, values)?;
+        Ok(array)
+    }
+    /// `Get<Type>ArrayElements`, the elements are written back and released when the guard is dropped.
+    /// borrows the array mutably so there is only ever one guard handing out its elements
+    pub fn elements(&mut self) -> Result<ArrayElements<'_,'a,T>,JniError> {
+        let elements = T::get_elements(self.ptr.env.ptr, self.ptr.ptr);
+        if elements.is_null() {
+            return Err(self.ptr.env.take_exception().map(JniError::JavaException).unwrap_or(JniError::NullReturn));
         }
+        Ok(ArrayElements {
+            array : self,
+            elements,
+        })
+    }
+    /// `GetPrimitiveArrayCritical`, runs `f` with direct access to the array (without copying where the jvm allows it)
+    /// and releases it once `f` is done with the guard, the gc may be paused meanwhile.
+    /// no other jni calls may be made (and the thread must not block) in between, so `f` has to be `Send`,
+    /// which keeps it from capturing the env or anything holding a reference
+    pub fn critical<R>(&mut self, f : impl for<'r> FnOnce(CriticalArray<'r,'a,T>) -> R + Send) -> Result<R,JniError> {
+        let elements = unchecked_jnic!(self.ptr.env.ptr,GetPrimitiveArrayCritical, self.ptr.ptr, ptr::null_mut()) as *mut T::Raw;
+        if elements.is_null() {
+            return Err(self.ptr.env.take_exception().map(JniError::JavaException).unwrap_or(JniError::NullReturn));
+        }
+        Ok(f(CriticalArray {
+            array : self,
+            elements,
+        }))
+    }
+}
+
+impl<'a,T : From<JObject<'a>> + JClassInstance> JArray<'a,T> {
+    /// `NewObjectArray`, `init` is put in every slot (null when `None`)
+    pub fn create_object(env:&'a Jenv<'a>, length:usize, element_class:&JClass, init:Option<&T>) -> Result<Self,JniError> {
+        let init = init.map(|i| i.get_jobject().ptr).unwrap_or(ptr::null_mut());
+        let ptr = unchecked_jnice!(env.ptr,NewObjectArray, length as jint, element_class.ptr, init)?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self::new(JObject::new(ptr, env)))
+    }
+}
+
+impl<'a,T> From<JObject<'a>> for JArray<'a,T> {
+    fn from(obj : JObject<'a>) -> Self {
+        Self::new(obj)
     }
 }
 
@@ -41,4 +127,307 @@ impl<T> JClassInstance for JArray<'_,T> {
     fn get_jobject(&self) -> JObject {
         self.ptr.clone()
     }
-}
\ No newline at end of file
+}
+impl<T> JRefTarget for JArray<'_,T> {
+    type Bound<'a> = JArray<'a,T>;
+}
+
+pub struct JArrayIter<'r,'a,T> {
+    array : &'r JArray<'a,T>,
+    index : usize,
+}
+
+impl<'r,'a,T : ArrayElement<'a>> Iterator for JArrayIter<'r,'a,T> {
+    type Item = Result<T,JniError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.array.length {
+            return None;
+        }
+        self.index += 1;
+        Some(self.array.get(self.index - 1))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.array.length - self.index;
+        (left, Some(left))
+    }
+}
+
+impl<'r,'a,T : ArrayElement<'a>> IntoIterator for &'r JArray<'a,T> {
+    type Item = Result<T,JniError>;
+    type IntoIter = JArrayIter<'r,'a,T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// what happens to changes made through an [`ArrayElements`] / [`CriticalArray`] guard when it is released
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReleaseMode {
+    /// copy the changes back into the array
+    #[default]
+    Commit,
+    /// throw the changes away, only possible when the jvm handed out a copy
+    Abort,
+}
+
+impl ReleaseMode {
+    fn as_raw(self) -> jint {
+        match self {
+            ReleaseMode::Commit => 0,
+            ReleaseMode::Abort => JNI_ABORT as jint,
+        }
+    }
+}
+
+/// Guard over `Get<Type>ArrayElements`. dropping it copies the elements back into the array and frees them,
+/// [`ArrayElements::abort`] frees them without writing anything back.
+pub struct ArrayElements<'r,'a,T : JPrimitive> {
+    array : &'r mut JArray<'a,T>,
+    elements : *mut T::Raw,
+}
+
+impl<'r,'a,T : JPrimitive> ArrayElements<'r,'a,T> {
+    pub fn release(self, mode:ReleaseMode) {
+        T::release_elements(self.array.ptr.env.ptr, self.array.ptr.ptr, self.elements, mode.as_raw());
+        std::mem::forget(self);
+    }
+    /// same as dropping the guard
+    pub fn commit(self) {
+        self.release(ReleaseMode::Commit)
+    }
+    /// releases the elements, discarding any changes made through the guard
+    pub fn abort(self) {
+        self.release(ReleaseMode::Abort)
+    }
+}
+
+impl<T : JPrimitive> Deref for ArrayElements<'_,'_,T> {
+    type Target = [T::Raw];
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.elements, self.array.length) }
+    }
+}
+impl<T : JPrimitive> DerefMut for ArrayElements<'_,'_,T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { std::slice::from_raw_parts_mut(self.elements, self.array.length) }
+    }
+}
+impl<T : JPrimitive> Drop for ArrayElements<'_,'_,T> {
+    fn drop(&mut self) {
+        T::release_elements(self.array.ptr.env.ptr, self.array.ptr.ptr, self.elements, ReleaseMode::Commit.as_raw());
+    }
+}
+
+/// Guard over `GetPrimitiveArrayCritical`, only handed to the closure of [`JArray::critical`]. dropping it commits any changes.
+pub struct CriticalArray<'r,'a,T : JPrimitive> {
+    array : &'r mut JArray<'a,T>,
+    elements : *mut T::Raw,
+}
+
+impl<'r,'a,T : JPrimitive> CriticalArray<'r,'a,T> {
+    pub fn release(self, mode:ReleaseMode) {
+        unchecked_jnic!(self.array.ptr.env.ptr,ReleasePrimitiveArrayCritical, self.array.ptr.ptr, self.elements as _, mode.as_raw());
+        std::mem::forget(self);
+    }
+    /// same as dropping the guard
+    pub fn commit(self) {
+        self.release(ReleaseMode::Commit)
+    }
+    /// releases the array, discarding any changes made through the guard if it was a copy.
+    /// hotspot hands out the array itself, so writes have already happened
+    pub fn abort(self) {
+        self.release(ReleaseMode::Abort)
+    }
+}
+
+impl<T : JPrimitive> Deref for CriticalArray<'_,'_,T> {
+    type Target = [T::Raw];
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.elements, self.array.length) }
+    }
+}
+impl<T : JPrimitive> DerefMut for CriticalArray<'_,'_,T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { std::slice::from_raw_parts_mut(self.elements, self.array.length) }
+    }
+}
+impl<T : JPrimitive> Drop for CriticalArray<'_,'_,T> {
+    fn drop(&mut self) {
+        unchecked_jnic!(self.array.ptr.env.ptr,ReleasePrimitiveArrayCritical, self.array.ptr.ptr, self.elements as _, ReleaseMode::Commit.as_raw());
+    }
+}
+
+/// the eight java primitives, `Raw` is the type jni stores in the array (eg. `char` is kept as a utf-16 `jchar`)
+pub trait JPrimitive : Copy {
+    type Raw : Copy;
+    fn to_raw(self) -> Self::Raw;
+    fn from_raw(raw:Self::Raw) -> Self;
+    fn new_array(env:*mut JNIEnv, length:usize) -> Result<jarray,JniError>;
+    fn get_raw_region(env:*mut JNIEnv, array:jarray, start:usize, buf:&mut [Self::Raw]) -> Result<(),JniError>;
+    fn set_raw_region(env:*mut JNIEnv, array:jarray, start:usize, buf:&[Self::Raw]) -> Result<(),JniError>;
+    fn get_elements(env:*mut JNIEnv, array:jarray) -> *mut Self::Raw;
+    fn release_elements(env:*mut JNIEnv, array:jarray, elements:*mut Self::Raw, mode:jint);
+}
+
+/// anything that can be stored in a [`JArray`], the primitives and every object wrapper
+pub trait ArrayElement<'a> : Sized {
+    fn get_region(array:&JObject<'a>, start:usize, len:usize) -> Result<Vec<Self>,JniError>;
+    fn set_region(array:&JObject<'a>, start:usize, values:&[Self]) -> Result<(),JniError>;
+}
+
+impl<'a,T : From<JObject<'a>> + JClassInstance> ArrayElement<'a> for T {
+    fn get_region(array:&JObject<'a>, start:usize, len:usize) -> Result<Vec<Self>,JniError> {
+        // one local reference per element, handed to the caller (wrapping them looks nothing up, their class is resolved on use).
+        // read big arrays inside `Jenv::with_local_frame` or in chunks
+        let mut out = Vec::with_capacity(len);
+        for idx in start..start + len {
+            let obj = match unchecked_jnice!(array.env.ptr,GetObjectArrayElement, array.ptr, idx as jint) {
+                Ok(obj) => obj,
+                Err(e) => {
+                    for element in out {
+                        drop(LocalRef::new(element));
+                    }
+                    return Err(e);
+                }
+            };
+            out.push(T::from(if obj.is_null() { JObject::null(array.env) } else { JObject::new(obj, array.env) }));
+        }
+        Ok(out)
+    }
+    fn set_region(array:&JObject<'a>, start:usize, values:&[Self]) -> Result<(),JniError> {
+        for (idx, value) in values.iter().enumerate() {
+            unchecked_jnice!(array.env.ptr,SetObjectArrayElement, array.ptr, (start + idx) as jint, value.get_jobject().ptr)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! primitive_array {
+    ($rust:ty, $raw:ty, $new:ident, $get_region:ident, $set_region:ident, $get_elements:ident, $release_elements:ident, |$x:ident| $from_raw:expr, |$y:ident| $to_raw:expr) => {
+        impl JPrimitive for $rust {
+            type Raw = $raw;
+            fn to_raw(self) -> Self::Raw {
+                let $y = self;
+                $to_raw
+            }
+            fn from_raw(raw:Self::Raw) -> Self {
+                let $x = raw;
+                $from_raw
+            }
+            fn new_array(env:*mut JNIEnv, length:usize) -> Result<jarray,JniError> {
+                let ptr = unchecked_jnice!(env,$new, length as jint)?;
+                if ptr.is_null() {
+                    return Err(JniError::NullReturn);
+                }
+                Ok(ptr)
+            }
+            fn get_raw_region(env:*mut JNIEnv, array:jarray, start:usize, buf:&mut [Self::Raw]) -> Result<(),JniError> {
+                unchecked_jnice!(env,$get_region, array, start as jint, buf.len() as jint, buf.as_mut_ptr())
+            }
+            fn set_raw_region(env:*mut JNIEnv, array:jarray, start:usize, buf:&[Self::Raw]) -> Result<(),JniError> {
+                unchecked_jnice!(env,$set_region, array, start as jint, buf.len() as jint, buf.as_ptr())
+            }
+            fn get_elements(env:*mut JNIEnv, array:jarray) -> *mut Self::Raw {
+                unchecked_jnic!(env,$get_elements, array, ptr::null_mut())
+            }
+            fn release_elements(env:*mut JNIEnv, array:jarray, elements:*mut Self::Raw, mode:jint) {
+                unchecked_jnic!(env,$release_elements, array, elements, mode)
+            }
+        }
+        impl<'a> ArrayElement<'a> for $rust {
+            fn get_region(array:&JObject<'a>, start:usize, len:usize) -> Result<Vec<Self>,JniError> {
+                let mut buf : Vec<$raw> = vec![Default::default(); len];
+                <$rust>::get_raw_region(array.env.ptr, array.ptr, start, &mut buf)?;
+                Ok(buf.into_iter().map(<$rust>::from_raw).collect())
+            }
+            fn set_region(array:&JObject<'a>, start:usize, values:&[Self]) -> Result<(),JniError> {
+                let buf = values.iter().map(|v| v.to_raw()).collect::<Vec<$raw>>();
+                <$rust>::set_raw_region(array.env.ptr, array.ptr, start, &buf)
+            }
+        }
+    };
+}
+
+primitive_array!(bool, jboolean, NewBooleanArray, GetBooleanArrayRegion, SetBooleanArrayRegion, GetBooleanArrayElements, ReleaseBooleanArrayElements, |x| x == JNI_TRUE as jboolean, |y| y as jboolean);
+primitive_array!(i8, jbyte, NewByteArray, GetByteArrayRegion, SetByteArrayRegion, GetByteArrayElements, ReleaseByteArrayElements, |x| x, |y| y);
+primitive_array!(char, jchar, NewCharArray, GetCharArrayRegion, SetCharArrayRegion, GetCharArrayElements, ReleaseCharArrayElements, |x| char::from_u32(x as u32).unwrap_or(char::REPLACEMENT_CHARACTER), |y| y as jchar);
+primitive_array!(i16, jshort, NewShortArray, GetShortArrayRegion, SetShortArrayRegion, GetShortArrayElements, ReleaseShortArrayElements, |x| x, |y| y);
+primitive_array!(i32, jint, NewIntArray, GetIntArrayRegion, SetIntArrayRegion, GetIntArrayElements, ReleaseIntArrayElements, |x| x, |y| y);
+primitive_array!(i64, jlong, NewLongArray, GetLongArrayRegion, SetLongArrayRegion, GetLongArrayElements, ReleaseLongArrayElements, |x| x, |y| y);
+primitive_array!(f32, jfloat, NewFloatArray, GetFloatArrayRegion, SetFloatArrayRegion, GetFloatArrayElements, ReleaseFloatArrayElements, |x| x, |y| y);
+primitive_array!(f64, jdouble, NewDoubleArray, GetDoubleArrayRegion, SetDoubleArrayRegion, GetDoubleArrayElements, ReleaseDoubleArrayElements, |x| x, |y| y);
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+    use crate::{jstring::JString, vm::tests::attach};
+
+    fn round_trip<'a, T : JPrimitive + ArrayElement<'a> + PartialEq + Debug>(env : &'a Jenv<'a>, values : &[T]) {
+        let array = JArray::from_slice(env, values).unwrap();
+        assert_eq!(array.len(), values.len());
+        assert_eq!(array.get_all().unwrap(), values);
+        assert_eq!(array.iter().collect::<Result<Vec<_>,_>>().unwrap(), values);
+    }
+
+    #[test]
+    fn primitives() {
+        let env = attach();
+        round_trip(&env, &[true, false, true]);
+        round_trip(&env, &[i8::MIN, 0, i8::MAX]);
+        round_trip(&env, &['a', 'é', '✓']);
+        round_trip(&env, &[i16::MIN, i16::MAX]);
+        round_trip(&env, &[i32::MIN, -1, i32::MAX]);
+        round_trip(&env, &[i64::MIN, i64::MAX]);
+        round_trip(&env, &[f32::MIN_POSITIVE, -0.5]);
+        round_trip(&env, &[f64::MAX, 1.5]);
+
+        let array = JArray::<i32>::create(&env, 4).unwrap();
+        assert_eq!(array.get_all().unwrap(), [0; 4]);
+        array.set(3, 9).unwrap();
+        array.set_region(1, &[5, 6]).unwrap();
+        assert_eq!(array.get_region(1, 3).unwrap(), [5, 6, 9]);
+        assert_eq!(array.iter().size_hint(), (4, Some(4)));
+        assert!(matches!(array.get(4), Err(JniError::IndexOutOfBounds { index : 5, length : 4 })));
+        assert!(matches!(array.set_region(3, &[1, 2]), Err(JniError::IndexOutOfBounds {..})));
+        assert!(JArray::<i32>::from(JObject::null(&env)).is_empty());
+    }
+
+    #[test]
+    fn objects() {
+        let env = attach();
+        let string = env.find_class("java/lang/String").unwrap();
+        let fill = JString::new("fill", &env);
+        let array = JArray::<JString>::create_object(&env, 3, &string, Some(&fill)).unwrap();
+        array.set(1, JString::new("one", &env)).unwrap();
+        let all = array.get_all().unwrap().iter().map(|s| s.to_rust_string().unwrap()).collect::<Vec<_>>();
+        assert_eq!(all, ["fill", "one", "fill"]);
+        let empty = JArray::<JString>::create_object(&env, 2, &string, None).unwrap();
+        assert!(empty.get(1).unwrap().obj.ptr.is_null());
+        assert!(matches!(empty.get(2), Err(JniError::IndexOutOfBounds {..})));
+    }
+
+    #[test]
+    fn guards() {
+        let env = attach();
+        let mut array = JArray::from_slice(&env, &[1i32, 2, 3]).unwrap();
+        let mut elements = array.elements().unwrap();
+        elements[0] = 10;
+        drop(elements);
+        let mut elements = array.elements().unwrap();
+        elements[1] = 20;
+        elements.abort();
+        assert_eq!(array.get_all().unwrap(), [10, 2, 3]);
+
+        let sum = array.critical(|mut critical| {
+            critical[2] = 30;
+            critical.iter().sum::<i32>()
+        }).unwrap();
+        assert_eq!((sum, array.get_all().unwrap()), (42, vec![10, 2, 30]));
+
+        // booleans are handed out as their raw jboolean
+        let mut flags = JArray::from_slice(&env, &[false, true]).unwrap();
+        assert_eq!(flags.critical(|critical| critical.to_vec()).unwrap(), [0, 1]);
+    }
+}
@@ -1,6 +1,6 @@
-use std::{borrow::Cow, fmt::{Formatter, Display, Debug}, ops::{Deref, DerefMut}, ffi::CString};
+use std::{borrow::Cow, fmt::{Formatter, Display, Debug}, ops::Deref, ptr};
 
-use crate::{object::JObject, unchecked_jnic, jvalue::JValue, env::Jenv};
+use crate::{object::{JObject, JClassInstance}, refs::JRefTarget, unchecked_jnic, unchecked_jnice, env::Jenv, error::JniError, mutf8};
 
 pub struct JString<'a,> {
     pub obj : JObject<'a>,
@@ -11,39 +11,85 @@ impl<'a> JString<'a> {
             obj,
         }
     }
+    /// encodes `string_ref` as modified utf-8 for `NewStringUTF`, so nul and non-bmp characters survive
     pub fn new(string_ref : &str, env : &'a Jenv) -> Self {
-        let mut added_nul = string_ref.to_string();
-        added_nul.push('\0');
-        let ptr = unchecked_jnic!(env.ptr,NewStringUTF, added_nul.as_ptr() as *const i8);
+        let mut encoded = mutf8::encode(string_ref).into_owned();
+        encoded.push(0);
+        let ptr = unchecked_jnic!(env.ptr,NewStringUTF, encoded.as_ptr() as *const i8);
         Self {
             obj : JObject::new(ptr, env),
         }
     }
+    /// `NewString` from utf-16 units, any rust string round trips through this
+    pub fn from_str(string_ref : &str, env : &'a Jenv) -> Result<Self,JniError> {
+        Self::from_utf16(&string_ref.encode_utf16().collect::<Vec<u16>>(), env)
+    }
+    pub fn from_utf16(units : &[u16], env : &'a Jenv) -> Result<Self,JniError> {
+        let ptr = unchecked_jnice!(env.ptr,NewString, units.as_ptr(), units.len() as i32)?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self {
+            obj : JObject::new(ptr, env),
+        })
+    }
 
+    /// length of the modified utf-8 encoding in bytes
     pub fn length(&self) -> usize {
         unchecked_jnic!(self.obj.env.ptr,GetStringUTFLength, self.obj.ptr) as usize
     }
+    /// length in utf-16 units, what java's `String::length` returns
+    pub fn utf16_length(&self) -> usize {
+        unchecked_jnic!(self.obj.env.ptr,GetStringLength, self.obj.ptr) as usize
+    }
     pub fn to_string(&self) -> ReturnedJString {
         let chr_ptr = unchecked_jnic!(self.obj.env.ptr,GetStringUTFChars, self.obj.ptr, &mut 0u8 as *mut u8);
         ReturnedJString {
             ptr: chr_ptr,
-            inner: String::from_utf8_lossy(unsafe { std::slice::from_raw_parts(chr_ptr as *const u8, self.length()) }),
+            inner: mutf8::decode_lossy(unsafe { std::slice::from_raw_parts(chr_ptr as *const u8, self.length()) }),
             from: self
         }
     }
-    /// Will only error when `start` + `length` is greater than the [`Self::length`] of the string
-    pub fn get_substring(&self, start:usize, length:usize) -> Result<String,()> {
-        if start + length > self.length() {
-            return Err(());
+    /// `GetStringChars`, the utf-16 units of the string
+    pub fn to_utf16(&self) -> Result<Vec<u16>,JniError> {
+        let length = self.utf16_length();
+        let chars = unchecked_jnice!(self.obj.env.ptr,GetStringChars, self.obj.ptr, ptr::null_mut())?;
+        if chars.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        let units = unsafe { std::slice::from_raw_parts(chars, length) }.to_vec();
+        unchecked_jnic!(self.obj.env.ptr,ReleaseStringChars, self.obj.ptr, chars);
+        Ok(units)
+    }
+    /// decodes the string without losing anything, the way to get a rust string out of java.
+    /// errors when the jni call fails or the string has unpaired surrogates, which a rust string can't hold
+    pub fn to_rust_string(&self) -> Result<String,JniError> {
+        String::from_utf16(&self.to_utf16()?).map_err(|e| JniError::InvalidUtf(e.to_string()))
+    }
+    /// `GetStringCritical`, runs `f` on the utf-16 contents of the string (without copying where the jvm allows it).
+    /// no other jni calls may be made (and the thread must not block) until `f` returns, so like [`crate::jarray::JArray::critical`] it has to be `Send`
+    pub fn critical<R>(&self, f : impl for<'r> FnOnce(CriticalString<'r>) -> R + Send) -> Result<R,JniError> {
+        let length = unchecked_jnic!(self.obj.env.ptr,GetStringLength, self.obj.ptr) as usize;
+        let chars = unchecked_jnic!(self.obj.env.ptr,GetStringCritical, self.obj.ptr, ptr::null_mut());
+        if chars.is_null() {
+            return Err(self.obj.env.take_exception().map(JniError::JavaException).unwrap_or(JniError::NullReturn));
+        }
+        Ok(f(CriticalString {
+            chars,
+            length,
+            from: self
+        }))
+    }
+    /// `start` and `length` are in utf-16 units, will only error when `start` + `length` is greater than the [`Self::utf16_length`] of the string
+    pub fn get_substring(&self, start:usize, length:usize) -> Result<String,JniError> {
+        if start + length > self.utf16_length() {
+            return Err(JniError::IndexOutOfBounds { index: start + length, length: self.utf16_length() });
         }
 
-        let mut buf = vec![1u8; length + 1];
-        buf.push('\0' as u8);
-        let mut cstr = CString::from_vec_with_nul(buf).unwrap();
-        let chr_ptr = cstr.as_ptr() as *mut i8;
-        unchecked_jnic!(self.obj.env.ptr,GetStringUTFRegion, self.obj.ptr, start as i32, length as i32, chr_ptr);
-        Ok(cstr.to_string_lossy().to_string())
-    } 
+        let mut buf = vec![0u16; length];
+        unchecked_jnice!(self.obj.env.ptr,GetStringRegion, self.obj.ptr, start as i32, length as i32, buf.as_mut_ptr())?;
+        Ok(String::from_utf16_lossy(&buf))
+    }
 }
 impl Display for JString<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -60,6 +106,27 @@ impl<'a> From<JObject<'a>> for JString<'a> {
         Self::new_from_obj(obj)
     }
 }
+impl<'a> JClassInstance for JString<'a> {
+    fn get_jobject(&self) -> JObject<'a> {
+        self.obj.clone()
+    }
+}
+impl TryFrom<JString<'_>> for String {
+    type Error = JniError;
+    fn try_from(string : JString<'_>) -> Result<Self,JniError> {
+        string.to_rust_string()
+    }
+}
+/// same as [`JString::to_rust_string`]
+impl TryFrom<&JString<'_>> for String {
+    type Error = JniError;
+    fn try_from(string : &JString<'_>) -> Result<Self,JniError> {
+        string.to_rust_string()
+    }
+}
+impl JRefTarget for JString<'_> {
+    type Bound<'a> = JString<'a>;
+}
 
 pub struct ReturnedJString<'a> {
     ptr: *const i8,
@@ -86,4 +153,55 @@ impl Debug for ReturnedJString<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         Debug::fmt(&self.inner, f)
     }
-}
\ No newline at end of file
+}
+/// Guard over `GetStringCritical`, only handed to the closure of [`JString::critical`]. strings are immutable so there is nothing to commit.
+pub struct CriticalString<'a> {
+    chars: *const u16,
+    length: usize,
+    from: &'a JString<'a>,
+}
+impl Drop for CriticalString<'_> {
+    fn drop(&mut self) {
+        unchecked_jnic!(self.from.obj.env.ptr,ReleaseStringCritical, self.from.obj.ptr, self.chars);
+    }
+}
+impl Deref for CriticalString<'_> {
+    type Target = [u16];
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.chars, self.length) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::tests::attach;
+
+    #[test]
+    fn critical() {
+        let env = attach();
+        let string = JString::new("chat 😀", &env);
+        let units = string.critical(|critical| critical.to_vec()).unwrap();
+        assert_eq!(units, "chat 😀".encode_utf16().collect::<Vec<_>>());
+        assert_eq!(units.len(), string.utf16_length());
+        assert_eq!(string.get_substring(5, 2).unwrap(), "😀");
+    }
+
+    #[test]
+    fn round_trip() {
+        let env = attach();
+        const TEXT : &str = "nul \0 and 😀 in chat";
+        let string = JString::from_str(TEXT, &env).unwrap();
+        assert_eq!((string.utf16_length(), string.length()), (20, 25));
+        assert_eq!(string.to_rust_string().unwrap(), TEXT);
+        // modified utf-8 both ways
+        let encoded = JString::new(TEXT, &env);
+        assert_eq!(encoded.to_utf16().unwrap(), TEXT.encode_utf16().collect::<Vec<_>>());
+        assert_eq!(&*encoded.to_string(), TEXT);
+        assert_eq!(String::try_from(encoded).unwrap(), TEXT);
+
+        let unpaired = JString::from_utf16(&[0x61, 0xd83d], &env).unwrap();
+        assert!(matches!(String::try_from(&unpaired), Err(JniError::InvalidUtf(_))));
+        assert_eq!(&*unpaired.to_string(), "a\u{fffd}");
+    }
+}
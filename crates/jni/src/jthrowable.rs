@@ -0,0 +1,350 @@
+use std::{borrow::Cow, error::Error, fmt::{Display, Formatter, Write}, ffi::CStr, ptr, sync::Arc};
+
+use jdk_sys::{JNIEnv, jobject, jthrowable, jmethodID, JNI_TRUE};
+
+use crate::{unchecked_jnic, env::Jenv, error::JniError, object::JObject, refs::GlobalRef};
+
+/// causes deeper than this are dropped, java allows cycles in the cause chain
+const MAX_CAUSE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub class_name : String,
+    pub method_name : String,
+    pub file_name : Option<String>,
+    /// negative when unknown, `-2` for native methods (same as `StackTraceElement::getLineNumber`)
+    pub line_number : i32,
+}
+
+/// A java exception which has been taken out of the jvm, everything apart from [`Self::reference`] is copied into rust strings when caught.
+/// it holds no local references, so it can outlive the frame it was caught in and be sent to other threads.
+#[derive(Debug, Clone)]
+pub struct JThrowable {
+    /// the exception object, kept alive by a global reference (deleted once the last clone is dropped) so it can be rethrown.
+    /// `None` when the global reference could not be created
+    pub reference : Option<Arc<GlobalRef<JObject<'static>>>>,
+    pub class_name : String,
+    pub message : Option<String>,
+    pub cause : Option<Box<JThrowable>>,
+    pub stack_trace : Vec<StackFrame>,
+}
+
+impl JThrowable {
+    /// takes the pending exception (if any) and clears it so jni calls can continue
+    pub fn catch(env : *mut JNIEnv) -> Option<Self> {
+        if unchecked_jnic!(env,ExceptionCheck) != JNI_TRUE as u8 {
+            return None;
+        }
+        let ptr = unchecked_jnic!(env,ExceptionOccurred);
+        unchecked_jnic!(env,ExceptionClear);
+        if ptr.is_null() {
+            return None;
+        }
+        let throwable = Self::from_raw(env, ptr, 0);
+        delete_local(env, ptr);
+        Some(throwable)
+    }
+
+    /// reads the class name, message, cause and stack trace from an existing throwable, `ptr` is left for the caller to delete
+    pub fn from_raw(env : *mut JNIEnv, ptr : jthrowable, depth : usize) -> Self {
+        let class_name = {
+            let cls = unchecked_jnic!(env,GetObjectClass, ptr);
+            let name = call_string(env, cls, b"java/lang/Class\0", b"getName\0", b"()Ljava/lang/String;\0");
+            delete_local(env, cls);
+            name.unwrap_or_else(|| "<unknown>".to_string())
+        };
+        let message = call_string(env, ptr, b"java/lang/Throwable\0", b"getMessage\0", b"()Ljava/lang/String;\0");
+        let cause = if depth < MAX_CAUSE_DEPTH {
+            call_object(env, ptr, b"java/lang/Throwable\0", b"getCause\0", b"()Ljava/lang/Throwable;\0")
+                .and_then(|c| {
+                    let cause = (unchecked_jnic!(env,IsSameObject, c, ptr) != JNI_TRUE as u8).then(|| Box::new(Self::from_raw(env, c, depth + 1)));
+                    delete_local(env, c);
+                    cause
+                })
+        } else {
+            None
+        };
+        let stack_trace = read_stack_trace(env, ptr);
+        let reference = GlobalRef::from_raw(&Jenv::from_raw(env), ptr).ok().map(Arc::new);
+        Self {
+            reference,
+            class_name,
+            message,
+            cause,
+            stack_trace,
+        }
+    }
+
+    /// iterates this throwable followed by every cause
+    pub fn chain(&self) -> impl Iterator<Item = &JThrowable> {
+        std::iter::successors(Some(self), |t| t.cause.as_deref())
+    }
+
+    /// formats the same way `Throwable::printStackTrace` does, including every cause
+    pub fn format_stack_trace(&self) -> String {
+        let mut out = String::new();
+        for (idx, throwable) in self.chain().enumerate() {
+            if idx > 0 {
+                out.push_str("Caused by: ");
+            }
+            let _ = writeln!(out, "{}", throwable);
+            for frame in &throwable.stack_trace {
+                let _ = writeln!(out, "\tat {}", frame);
+            }
+        }
+        out
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.file_name, self.line_number) {
+            (_, -2) => write!(f, "{}.{}(Native Method)", self.class_name, self.method_name),
+            (Some(file), line) if line >= 0 => write!(f, "{}.{}({}:{})", self.class_name, self.method_name, file, line),
+            (Some(file), _) => write!(f, "{}.{}({})", self.class_name, self.method_name, file),
+            (None, _) => write!(f, "{}.{}(Unknown Source)", self.class_name, self.method_name),
+        }
+    }
+}
+
+impl Display for JThrowable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", self.class_name, message),
+            None => write!(f, "{}", self.class_name),
+        }
+    }
+}
+
+impl Error for JThrowable {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause.as_deref().map(|c| c as &(dyn Error + 'static))
+    }
+}
+
+// the helpers below deliberately avoid `unchecked_jnice!`, they run while building the error and must never recurse into it.
+
+fn clear_exception(env : *mut JNIEnv) -> bool {
+    if unchecked_jnic!(env,ExceptionCheck) == JNI_TRUE as u8 {
+        unchecked_jnic!(env,ExceptionClear);
+        return true;
+    }
+    false
+}
+
+fn delete_local(env : *mut JNIEnv, obj : jobject) {
+    if !obj.is_null() {
+        unchecked_jnic!(env,DeleteLocalRef, obj);
+    }
+}
+
+fn lookup_method(env : *mut JNIEnv, class : &[u8], name : &[u8], sig : &[u8]) -> Option<jmethodID> {
+    let cls = unchecked_jnic!(env,FindClass, class.as_ptr() as *const i8);
+    if clear_exception(env) || cls.is_null() {
+        return None;
+    }
+    let mid = unchecked_jnic!(env,GetMethodID, cls, name.as_ptr() as *const i8, sig.as_ptr() as *const i8);
+    delete_local(env, cls);
+    if clear_exception(env) || mid.is_null() {
+        return None;
+    }
+    Some(mid)
+}
+
+fn call_object(env : *mut JNIEnv, obj : jobject, class : &[u8], name : &[u8], sig : &[u8]) -> Option<jobject> {
+    let mid = lookup_method(env, class, name, sig)?;
+    let ret = unchecked_jnic!(env,CallObjectMethodA, obj, mid, ptr::null());
+    if clear_exception(env) || ret.is_null() {
+        return None;
+    }
+    Some(ret)
+}
+
+fn call_string(env : *mut JNIEnv, obj : jobject, class : &[u8], name : &[u8], sig : &[u8]) -> Option<String> {
+    let jstr = call_object(env, obj, class, name, sig)?;
+    let chars = unchecked_jnic!(env,GetStringUTFChars, jstr, ptr::null_mut());
+    if chars.is_null() {
+        clear_exception(env);
+        delete_local(env, jstr);
+        return None;
+    }
+    let string = unsafe { CStr::from_ptr(chars) }.to_string_lossy().to_string();
+    unchecked_jnic!(env,ReleaseStringUTFChars, jstr, chars);
+    delete_local(env, jstr);
+    Some(string)
+}
+
+fn call_int(env : *mut JNIEnv, obj : jobject, class : &[u8], name : &[u8], sig : &[u8]) -> Option<i32> {
+    let mid = lookup_method(env, class, name, sig)?;
+    let ret = unchecked_jnic!(env,CallIntMethodA, obj, mid, ptr::null());
+    if clear_exception(env) {
+        return None;
+    }
+    Some(ret)
+}
+
+fn read_stack_trace(env : *mut JNIEnv, throwable : jthrowable) -> Vec<StackFrame> {
+    let Some(elements) = call_object(env, throwable, b"java/lang/Throwable\0", b"getStackTrace\0", b"()[Ljava/lang/StackTraceElement;\0") else {
+        return vec![];
+    };
+    let length = unchecked_jnic!(env,GetArrayLength, elements);
+    let mut frames = Vec::with_capacity(length.max(0) as usize);
+    for idx in 0..length {
+        let element = unchecked_jnic!(env,GetObjectArrayElement, elements, idx);
+        if clear_exception(env) || element.is_null() {
+            continue;
+        }
+        const STE : &[u8] = b"java/lang/StackTraceElement\0";
+        frames.push(StackFrame {
+            class_name: call_string(env, element, STE, b"getClassName\0", b"()Ljava/lang/String;\0").unwrap_or_default(),
+            method_name: call_string(env, element, STE, b"getMethodName\0", b"()Ljava/lang/String;\0").unwrap_or_default(),
+            file_name: call_string(env, element, STE, b"getFileName\0", b"()Ljava/lang/String;\0"),
+            line_number: call_int(env, element, STE, b"getLineNumber\0", b"()I\0").unwrap_or(-1),
+        });
+        delete_local(env, element);
+    }
+    delete_local(env, elements);
+    frames
+}
+
+/// errors which can be raised as java exceptions when returning from a native method, see [`crate::env::Jenv::unwrap_or_throw`]
+pub trait ThrowableError : Display {
+    /// internal name of the exception class that will be thrown, as [`Jenv::throw_new`] takes it
+    fn exception_class(&self) -> Cow<'_, str> {
+        "java/lang/RuntimeException".into()
+    }
+    fn throw_into(&self, env:&Jenv) {
+        let _ = env.throw_new(&self.exception_class(), &self.to_string());
+    }
+}
+
+impl ThrowableError for JniError {
+    fn exception_class(&self) -> Cow<'_, str> {
+        Cow::Borrowed(match self {
+            JniError::ClassNotFound(_) => "java/lang/NoClassDefFoundError",
+            JniError::MethodNotFound {..} => "java/lang/NoSuchMethodError",
+            JniError::FieldNotFound {..} => "java/lang/NoSuchFieldError",
+            JniError::NullReturn => "java/lang/NullPointerException",
+            JniError::IndexOutOfBounds {..} => "java/lang/IndexOutOfBoundsException",
+            JniError::InvalidUtf(_) => "java/lang/IllegalArgumentException",
+            _ => "java/lang/RuntimeException",
+        })
+    }
+    fn throw_into(&self, env:&Jenv) {
+        // the original exception keeps its type and stack trace
+        if let JniError::JavaException(throwable) = self {
+            if env.throw(throwable).is_ok() {
+                return;
+            }
+        }
+        let _ = env.throw_new(&self.exception_class(), &self.to_string());
+    }
+}
+impl ThrowableError for JThrowable {
+    /// the class the exception was caught as, `class_name` is the dotted java name
+    fn exception_class(&self) -> Cow<'_, str> {
+        self.class_name.replace('.', "/").into()
+    }
+    fn throw_into(&self, env:&Jenv) {
+        let _ = env.throw(self);
+    }
+}
+impl ThrowableError for String {}
+impl ThrowableError for &str {}
+
+/// the value a native method returns alongside a thrown exception, java discards it
+pub trait ExceptionDefault {
+    fn exception_default() -> Self;
+}
+
+macro_rules! exception_default {
+    ($($t:ty => $v:expr),*) => {
+        $(impl ExceptionDefault for $t {
+            fn exception_default() -> Self {
+                $v
+            }
+        })*
+    };
+}
+exception_default!(() => (), bool => false, char => '\0', u8 => 0, i8 => 0, u16 => 0, i16 => 0, i32 => 0, i64 => 0, f32 => 0.0, f64 => 0.0, jobject => ptr::null_mut());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throwable(class_name : &str, message : Option<&str>, cause : Option<JThrowable>) -> JThrowable {
+        JThrowable {
+            reference : None,
+            class_name : class_name.to_string(),
+            message : message.map(str::to_string),
+            cause : cause.map(Box::new),
+            stack_trace : vec![StackFrame { class_name : "a.B".into(), method_name : "run".into(), file_name : Some("B.java".into()), line_number : 3 }],
+        }
+    }
+
+    #[test]
+    fn errors_cross_threads() {
+        fn assert_send_sync<T : Send + Sync>() {}
+        assert_send_sync::<JThrowable>();
+        assert_send_sync::<JniError>();
+    }
+
+    #[test]
+    fn stack_trace_format() {
+        let t = throwable("java.lang.RuntimeException", Some("outer"), Some(throwable("java.io.IOException", None, None)));
+        assert_eq!(t.chain().count(), 2);
+        assert_eq!(t.format_stack_trace(), "java.lang.RuntimeException: outer\n\tat a.B.run(B.java:3)\nCaused by: java.io.IOException\n\tat a.B.run(B.java:3)\n");
+        let native = StackFrame { class_name : "a.B".into(), method_name : "n".into(), file_name : None, line_number : -2 };
+        assert_eq!(native.to_string(), "a.B.n(Native Method)");
+        let unknown = StackFrame { line_number : -1, ..native };
+        assert_eq!(unknown.to_string(), "a.B.n(Unknown Source)");
+    }
+
+    /// `Integer.parseInt(text)`
+    fn parse_int(env : &Jenv, text : &str) -> Result<i32,JniError> {
+        let text = crate::jstring::JString::new(text, env);
+        env.find_class("java/lang/Integer")?.call_static_int_method("parseInt", "(Ljava/lang/String;)I", &vec![crate::jvalue::JValue::from(&text.obj)])
+    }
+
+    #[test]
+    fn caught() {
+        let env = crate::vm::tests::attach();
+        assert_eq!(parse_int(&env, "12").unwrap(), 12);
+        let Err(JniError::JavaException(t)) = parse_int(&env, "x") else { panic!("parseInt(\"x\") did not throw") };
+        assert_eq!(t.class_name, "java.lang.NumberFormatException");
+        assert_eq!(t.message.as_deref(), Some("For input string: \"x\""));
+        assert!(t.cause.is_none() && t.reference.is_some());
+        assert!(t.stack_trace.iter().any(|f| f.class_name == "java.lang.Integer" && f.method_name == "parseInt"));
+        assert!(env.take_exception().is_none());
+    }
+
+    #[test]
+    fn exception_classes() {
+        assert_eq!(JniError::ClassNotFound("a/B".into()).exception_class(), "java/lang/NoClassDefFoundError");
+        assert_eq!(JniError::NullReturn.exception_class(), "java/lang/NullPointerException");
+        assert_eq!(JniError::IndexOutOfBounds { index : 1, length : 0 }.exception_class(), "java/lang/IndexOutOfBoundsException");
+        assert_eq!(JniError::Status(-1).exception_class(), "java/lang/RuntimeException");
+        assert_eq!("plain".exception_class(), "java/lang/RuntimeException");
+        assert_eq!(throwable("java.io.IOException", None, None).exception_class(), "java/io/IOException");
+    }
+
+    #[test]
+    fn thrown() {
+        let env = crate::vm::tests::attach();
+        assert_eq!(env.unwrap_or_throw::<i32, JniError>(Err(JniError::NullReturn)), 0);
+        let npe = env.take_exception().unwrap();
+        assert_eq!((npe.class_name.as_str(), npe.message.as_deref()), ("java.lang.NullPointerException", Some("jni call returned null")));
+        assert_eq!(env.unwrap_or_throw(Ok::<_, &str>(7)), 7);
+        assert!(env.take_exception().is_none());
+
+        // a caught exception goes back in as itself, stack trace and all
+        let Err(caught) = parse_int(&env, "x") else { panic!("parseInt(\"x\") did not throw") };
+        env.unwrap_or_throw::<(), _>(Err(caught.clone()));
+        let JniError::JavaException(caught) = caught else { panic!("{caught}") };
+        let rethrown = env.take_exception().unwrap();
+        assert_eq!(rethrown.format_stack_trace(), caught.format_stack_trace());
+
+        assert!(matches!(env.throw_new("does/not/Exist", "x"), Err(JniError::ClassNotFound(_))));
+        assert!(env.take_exception().is_none());
+    }
+}
@@ -0,0 +1,242 @@
+use std::{ffi::{c_char, c_uchar, CStr}, mem, panic::{self, AssertUnwindSafe}, ptr, slice, sync::{Arc, RwLock}};
+
+use jdk_sys::{jboolean, jclass, jint, jlocation, jmethodID, jobject, jthread, jvalue, jvmtiEnv, jvmtiEvent, jvmtiEventCallbacks, JNIEnv, JNI_FALSE};
+
+use crate::{env::Jenv, object::JObject, class::JClass, error::JniError};
+
+use super::{check, jnicall, JvmtiEnv, transform::ClassFileLoad};
+
+/// The events closures can be registered for. jvmti calls them on whatever thread caused them, so handlers must be `Send + Sync`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// the jvm has finished starting up, jni and all of jvmti can be used from here on
+    VmInit,
+    /// the jvm is shutting down, no more events follow
+    VmDeath,
+    /// a class file is about to be parsed, either for the first time or because it is being retransformed / redefined
+    ClassFileLoadHook,
+    /// a java method is entered, needs `can_generate_method_entry_events`
+    MethodEntry,
+    /// a java method returns or is popped by an exception, needs `can_generate_method_exit_events`
+    MethodExit,
+    /// a breakpoint set with [`JvmtiEnv::set_breakpoint`] is hit, needs `can_generate_breakpoint_events`
+    Breakpoint,
+}
+
+impl Event {
+    pub fn as_raw(self) -> jvmtiEvent {
+        match self {
+            Event::VmInit => jdk_sys::jvmtiEvent_JVMTI_EVENT_VM_INIT,
+            Event::VmDeath => jdk_sys::jvmtiEvent_JVMTI_EVENT_VM_DEATH,
+            Event::ClassFileLoadHook => jdk_sys::jvmtiEvent_JVMTI_EVENT_CLASS_FILE_LOAD_HOOK,
+            Event::MethodEntry => jdk_sys::jvmtiEvent_JVMTI_EVENT_METHOD_ENTRY,
+            Event::MethodExit => jdk_sys::jvmtiEvent_JVMTI_EVENT_METHOD_EXIT,
+            Event::Breakpoint => jdk_sys::jvmtiEvent_JVMTI_EVENT_BREAKPOINT,
+        }
+    }
+}
+
+/// called with the env and `java.lang.Thread` of the thread starting the jvm
+pub type VmInitHandler = dyn for<'e> Fn(&'e Jenv<'e>, JObject<'e>) + Send + Sync;
+pub type VmDeathHandler = dyn for<'e> Fn(&'e Jenv<'e>) + Send + Sync;
+/// called with the class and its current bytes, returns the bytes to use instead or `None` to leave the class alone
+pub type ClassFileLoadHandler = dyn for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync;
+/// called with the internal name before anything else is done for a class file, false skips the [`ClassFileLoadHandler`]
+pub(super) type ClassFileFilter = dyn Fn(&str) -> bool + Send + Sync;
+/// called with the thread and the method being entered. both are raw, this runs for every method the jvm enters
+/// and most handlers only look at a few of them (wrap the thread with [`JObject::new`] once it is needed)
+pub type MethodEntryHandler = dyn for<'e> Fn(&'e Jenv<'e>, jthread, jmethodID) + Send + Sync;
+/// called with the raw thread, the method, whether it was popped by an exception and the raw return value
+pub type MethodExitHandler = dyn for<'e> Fn(&'e Jenv<'e>, jthread, jmethodID, bool, jvalue) + Send + Sync;
+/// called with the thread, the method and the bytecode index of the breakpoint
+pub type BreakpointHandler = dyn for<'e> Fn(&'e Jenv<'e>, JObject<'e>, jmethodID, jlocation) + Send + Sync;
+
+/// the closures of one environment
+#[derive(Default, Clone)]
+struct Handlers {
+    vm_init : Option<Arc<VmInitHandler>>,
+    vm_death : Option<Arc<VmDeathHandler>>,
+    class_file_load : Option<Arc<ClassFileLoadHandler>>,
+    class_file_filter : Option<Arc<ClassFileFilter>>,
+    method_entry : Option<Arc<MethodEntryHandler>>,
+    method_exit : Option<Arc<MethodExitHandler>>,
+    breakpoint : Option<Arc<BreakpointHandler>>,
+}
+
+/// keyed by the jvmti env pointer, the callbacks only get that to go on
+static HANDLERS : RwLock<Vec<(usize, Handlers)>> = RwLock::new(Vec::new());
+
+impl JvmtiEnv {
+    /// `SetEventNotificationMode`. `thread` limits the event to one thread, not every event allows that (eg. [`Event::VmInit`])
+    pub fn set_event_enabled(&self, event : Event, enabled : bool, thread : Option<&JObject>) -> Result<(),JniError> {
+        let mode = if enabled { jdk_sys::jvmtiEventMode_JVMTI_ENABLE } else { jdk_sys::jvmtiEventMode_JVMTI_DISABLE };
+        let thread : jthread = thread.map_or(ptr::null_mut(), |t| t.ptr);
+        check(jvmti_call!(self.ptr,SetEventNotificationMode, mode, event.as_raw(), thread))
+    }
+    /// calls `f` once the jvm has started and enables [`Event::VmInit`]. replaces any earlier handler
+    pub fn on_vm_init(&self, f : impl for<'e> Fn(&'e Jenv<'e>, JObject<'e>) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| h.vm_init = Some(Arc::new(f)))?;
+        self.set_event_enabled(Event::VmInit, true, None)
+    }
+    /// calls `f` when the jvm shuts down and enables [`Event::VmDeath`]. replaces any earlier handler
+    pub fn on_vm_death(&self, f : impl for<'e> Fn(&'e Jenv<'e>) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| h.vm_death = Some(Arc::new(f)))?;
+        self.set_event_enabled(Event::VmDeath, true, None)
+    }
+
+    /// calls `f` for every class file the jvm parses and enables [`Event::ClassFileLoadHook`]. replaces any earlier handler,
+    /// [`JvmtiEnv::add_transformer`] builds a per class pipeline on top of this
+    pub fn on_class_file_load(&self, f : impl for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| {
+            h.class_file_load = Some(Arc::new(f));
+            h.class_file_filter = None;
+        })?;
+        self.set_event_enabled(Event::ClassFileLoadHook, true, None)
+    }
+    /// [`Self::on_class_file_load`] for the classes `filter` accepts, the others are skipped before their loader and class are wrapped
+    pub(super) fn on_class_file_load_where(&self, filter : impl Fn(&str) -> bool + Send + Sync + 'static, f : impl for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| {
+            h.class_file_load = Some(Arc::new(f));
+            h.class_file_filter = Some(Arc::new(filter));
+        })?;
+        self.set_event_enabled(Event::ClassFileLoadHook, true, None)
+    }
+    /// calls `f` for every method entered while [`Event::MethodEntry`] is enabled, which this does not do.
+    /// the event is global and slows the jvm down a lot, [`JvmtiEnv::hook_method`] enables it only while there are hooks
+    pub fn on_method_entry(&self, f : impl for<'e> Fn(&'e Jenv<'e>, jthread, jmethodID) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| h.method_entry = Some(Arc::new(f)))
+    }
+    /// calls `f` for every method exited while [`Event::MethodExit`] is enabled, which this does not do
+    pub fn on_method_exit(&self, f : impl for<'e> Fn(&'e Jenv<'e>, jthread, jmethodID, bool, jvalue) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| h.method_exit = Some(Arc::new(f)))
+    }
+    /// calls `f` whenever a breakpoint is hit and enables [`Event::Breakpoint`]
+    pub fn on_breakpoint(&self, f : impl for<'e> Fn(&'e Jenv<'e>, JObject<'e>, jmethodID, jlocation) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_breakpoint_handler(f)?;
+        self.set_event_enabled(Event::Breakpoint, true, None)
+    }
+    /// [`Self::on_breakpoint`] without enabling the event, which needs `can_generate_breakpoint_events`
+    pub(super) fn set_breakpoint_handler(&self, f : impl for<'e> Fn(&'e Jenv<'e>, JObject<'e>, jmethodID, jlocation) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.set_handler(|h| h.breakpoint = Some(Arc::new(f)))
+    }
+
+    fn set_handler(&self, set : impl FnOnce(&mut Handlers)) -> Result<(),JniError> {
+        {
+            let mut lock = HANDLERS.write().unwrap();
+            let key = self.ptr as usize;
+            match lock.iter_mut().find(|(env, _)| *env == key) {
+                Some((_, handlers)) => set(handlers),
+                None => {
+                    let mut handlers = Handlers::default();
+                    set(&mut handlers);
+                    lock.push((key, handlers));
+                }
+            }
+        }
+        self.install_callbacks()
+    }
+    /// `SetEventCallbacks` with every trampoline, the ones without a handler return straight away
+    fn install_callbacks(&self) -> Result<(),JniError> {
+        // every field is an `Option<fn>`, so all zero is "no callbacks"
+        let mut callbacks : jvmtiEventCallbacks = unsafe { mem::zeroed() };
+        unsafe {
+            callbacks.VMInit = jnicall(vm_init as *const ());
+            callbacks.VMDeath = jnicall(vm_death as *const ());
+            callbacks.ClassFileLoadHook = jnicall(class_file_load_hook as *const ());
+            callbacks.MethodEntry = jnicall(method_entry as *const ());
+            callbacks.MethodExit = jnicall(method_exit as *const ());
+            callbacks.Breakpoint = jnicall(breakpoint as *const ());
+        }
+        check(jvmti_call!(self.ptr,SetEventCallbacks, &callbacks, mem::size_of::<jvmtiEventCallbacks>() as jint))
+    }
+}
+
+/// drops the closures of an environment that is being disposed
+pub(super) fn forget(env : *mut jvmtiEnv) {
+    HANDLERS.write().unwrap().retain(|(key, _)| *key != env as usize);
+}
+
+/// clones the handler out so the lock is not held while it runs (it may well register other handlers)
+fn handler<T>(env : *mut jvmtiEnv, get : impl FnOnce(&Handlers) -> Option<T>) -> Option<T> {
+    let lock = HANDLERS.read().unwrap();
+    lock.iter().find(|(key, _)| *key == env as usize).and_then(|(_, handlers)| get(handlers))
+}
+
+/// panics must not unwind into the jvm, the panic hook has already reported it by the time we get here
+fn guard(f : impl FnOnce()) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(f));
+}
+
+unsafe extern "system" fn vm_init(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv, thread : jthread) {
+    if let Some(f) = handler(jvmti, |h| h.vm_init.clone()) {
+        let env = Jenv::from_raw(jni);
+        guard(|| f(&env, JObject::new(thread, &env)));
+    }
+}
+
+unsafe extern "system" fn vm_death(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv) {
+    if let Some(f) = handler(jvmti, |h| h.vm_death.clone()) {
+        let env = Jenv::from_raw(jni);
+        guard(|| f(&env));
+    }
+}
+
+unsafe extern "system" fn method_entry(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv, thread : jthread, method : jmethodID) {
+    if let Some(f) = handler(jvmti, |h| h.method_entry.clone()) {
+        let env = Jenv::from_raw(jni);
+        guard(|| f(&env, thread, method));
+    }
+}
+
+unsafe extern "system" fn method_exit(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv, thread : jthread, method : jmethodID, was_popped_by_exception : jboolean, return_value : jvalue) {
+    if let Some(f) = handler(jvmti, |h| h.method_exit.clone()) {
+        let env = Jenv::from_raw(jni);
+        guard(|| f(&env, thread, method, was_popped_by_exception != JNI_FALSE as jboolean, return_value));
+    }
+}
+
+unsafe extern "system" fn breakpoint(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv, thread : jthread, method : jmethodID, location : jlocation) {
+    if let Some(f) = handler(jvmti, |h| h.breakpoint.clone()) {
+        let env = Jenv::from_raw(jni);
+        guard(|| f(&env, JObject::new(thread, &env), method, location));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "system" fn class_file_load_hook(jvmti : *mut jvmtiEnv, jni : *mut JNIEnv, class_being_redefined : jclass, loader : jobject, name : *const c_char,
+    protection_domain : jobject, class_data_len : jint, class_data : *const c_uchar, new_class_data_len : *mut jint, new_class_data : *mut *mut c_uchar)
+{
+    // hidden / anonymous classes have no name, there is nothing to key a transformer on
+    if name.is_null() || class_data.is_null() {
+        return;
+    }
+    let Some((f, filter)) = handler(jvmti, |h| Some((h.class_file_load.clone()?, h.class_file_filter.clone()))) else {
+        return;
+    };
+    // every class the jvm parses comes through here, most are of no interest
+    let name = CStr::from_ptr(name).to_string_lossy();
+    if filter.is_some_and(|wants| !wants(&name)) {
+        return;
+    }
+    let env = Jenv::from_raw(jni);
+    let object = |ptr : jobject| if ptr.is_null() { JObject::null(&env) } else { JObject::new(ptr, &env) };
+    let class = ClassFileLoad {
+        name : &name,
+        loader : object(loader),
+        protection_domain : object(protection_domain),
+        class_being_redefined : (!class_being_redefined.is_null()).then(|| JClass::new(class_being_redefined, &env)),
+    };
+    let bytes = slice::from_raw_parts(class_data, class_data_len.max(0) as usize);
+    let mut out = None;
+    guard(|| out = f(&env, &class, bytes));
+    let Some(out) = out else {
+        return;
+    };
+    // the new bytes have to come from jvmti, the jvm frees them
+    let jvmti = JvmtiEnv { ptr : jvmti };
+    if let Ok(mem) = jvmti.allocate(out.len()) {
+        ptr::copy_nonoverlapping(out.as_ptr(), mem, out.len());
+        *new_class_data = mem;
+        *new_class_data_len = out.len() as jint;
+    }
+}
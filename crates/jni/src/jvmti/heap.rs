@@ -0,0 +1,267 @@
+use std::{collections::HashMap, ffi::c_void, fmt, mem, ptr, sync::atomic::{AtomicI64, Ordering}};
+
+use jdk_sys::{jint, jlong, jobject, jvmtiHeapCallbacks, jvmtiIterationControl, jvmtiHeapObjectFilter_JVMTI_HEAP_OBJECT_EITHER, jvmtiIterationControl_JVMTI_ITERATION_CONTINUE};
+
+use crate::{env::Jenv, object::JObject, class::JClass, error::JniError};
+
+use super::{check, hooks::Mapped, jnicall, JvmtiEnv};
+
+/// tags handed out to [`JvmtiEnv::instances`], negative so they stay clear of tags users pick themselves
+static NEXT_TAG : AtomicI64 = AtomicI64::new(i64::MIN);
+/// how many of them each call takes, one per instance
+const TAG_BLOCK : i64 = 1 << 32;
+
+/// A mapped class, the generated bindings list every one of them in `MAPPED_CLASSES` to remap names with, see [`HeapHistogram::remap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedClass {
+    /// the yarn name, eg. `net/minecraft/client/MinecraftClient`
+    pub yarn : &'static str,
+    /// the obfuscated internal name the jvm knows the class by
+    pub name : &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramEntry {
+    /// internal name, array classes keep their descriptor (`[Ljava/lang/String;`)
+    pub class : String,
+    pub instances : u64,
+    pub bytes : u64,
+}
+
+/// What the heap is made of per class, biggest first. see [`JvmtiEnv::heap_histogram`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapHistogram {
+    pub entries : Vec<HistogramEntry>,
+}
+
+impl HeapHistogram {
+    pub fn total_instances(&self) -> u64 {
+        self.entries.iter().map(|e| e.instances).sum()
+    }
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|e| e.bytes).sum()
+    }
+    /// renames obfuscated classes (and arrays of them) to their yarn names, classes not in `classes` keep their name
+    pub fn remap(&mut self, classes : &[MappedClass]) {
+        let names = classes.iter().map(|c| (c.name, c.yarn)).collect::<HashMap<_, _>>();
+        for entry in &mut self.entries {
+            let dims = entry.class.bytes().take_while(|b| *b == b'[').count();
+            let remapped = if dims == 0 {
+                names.get(entry.class.as_str()).map(|yarn| yarn.to_string())
+            } else {
+                entry.class[dims..].strip_prefix('L').and_then(|c| c.strip_suffix(';'))
+                    .and_then(|c| names.get(c))
+                    .map(|yarn| format!("{}L{};", &entry.class[..dims], yarn))
+            };
+            if let Some(remapped) = remapped {
+                entry.class = remapped;
+            }
+        }
+    }
+}
+
+/// laid out like `jmap -histo`
+impl fmt::Display for HeapHistogram {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, " num     #instances         #bytes  class name")?;
+        writeln!(f, "----------------------------------------------")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            writeln!(f, "{:>4}: {:>14} {:>14}  {}", i + 1, entry.instances, entry.bytes, entry.class)?;
+        }
+        write!(f, "Total {:>14} {:>14}", self.total_instances(), self.total_bytes())
+    }
+}
+
+impl JvmtiEnv {
+    /// `GetTag`, 0 when the object is untagged. tags are per environment, needs `can_tag_objects`
+    pub fn tag(&self, object : &JObject) -> Result<jlong,JniError> {
+        let mut tag : jlong = 0;
+        check(jvmti_call!(self.ptr,GetTag, object.ptr, &mut tag))?;
+        Ok(tag)
+    }
+    /// `SetTag`, 0 removes the tag
+    pub fn set_tag(&self, object : &JObject, tag : jlong) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,SetTag, object.ptr, tag))
+    }
+    /// `GetObjectsWithTags`, local references to every object tagged with one of `tags`
+    pub fn objects_with_tags<'a>(&self, env : &'a Jenv<'a>, tags : &[jlong]) -> Result<Vec<JObject<'a>>,JniError> {
+        let mut count : jint = 0;
+        let mut objects : *mut jobject = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetObjectsWithTags, tags.len() as jint, tags.as_ptr(), &mut count, &mut objects, ptr::null_mut()))?;
+        let objects = unsafe { self.take_array(objects, count) }?;
+        Ok(objects.into_iter().map(|o| JObject::new(o, env)).collect())
+    }
+    /// [`Self::objects_with_tags`] along with the tag of each object
+    fn tagged_objects<'a>(&self, env : &'a Jenv<'a>, tags : &[jlong]) -> Result<Vec<(JObject<'a>, jlong)>,JniError> {
+        let mut count : jint = 0;
+        let mut objects : *mut jobject = ptr::null_mut();
+        let mut object_tags : *mut jlong = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetObjectsWithTags, tags.len() as jint, tags.as_ptr(), &mut count, &mut objects, &mut object_tags))?;
+        let objects = unsafe { self.take_array(objects, count) }?;
+        let object_tags = unsafe { self.take_array(object_tags, count) }?;
+        Ok(objects.into_iter().map(|o| JObject::new(o, env)).zip(object_tags).collect())
+    }
+
+    /// every object on the heap that is an instance of `class` or one of its subclasses, through `IterateOverInstancesOfClass`.
+    /// garbage that has not been collected yet is included too.
+    /// each instance is tagged for a moment, the tags they had are put back afterwards. needs `can_tag_objects`
+    pub fn instances<'a>(&self, env : &'a Jenv<'a>, class : &JClass) -> Result<Vec<JObject<'a>>,JniError> {
+        /// the instance tagged `base + i` had the tag `old[i]`
+        struct Tagging {
+            base : jlong,
+            old : Vec<jlong>,
+        }
+        unsafe extern "system" fn tag_instance(_class_tag : jlong, _size : jlong, tag_ptr : *mut jlong, user_data : *mut c_void) -> jvmtiIterationControl {
+            let tagging = &mut *(user_data as *mut Tagging);
+            let old = mem::replace(&mut *tag_ptr, tagging.base + tagging.old.len() as jlong);
+            tagging.old.push(old);
+            jvmtiIterationControl_JVMTI_ITERATION_CONTINUE
+        }
+        let mut tagging = Tagging { base : NEXT_TAG.fetch_add(TAG_BLOCK, Ordering::Relaxed), old : vec![] };
+        let callback = unsafe { jnicall(tag_instance as *const ()) };
+        check(jvmti_call!(self.ptr,IterateOverInstancesOfClass, class.ptr, jvmtiHeapObjectFilter_JVMTI_HEAP_OBJECT_EITHER, callback, &mut tagging as *mut Tagging as *const c_void))?;
+        if tagging.old.is_empty() {
+            return Ok(vec![]);
+        }
+        let tags = (0..tagging.old.len() as jlong).map(|i| tagging.base + i).collect::<Vec<_>>();
+        let instances = self.tagged_objects(env, &tags)?;
+        for (instance, tag) in &instances {
+            self.set_tag(instance, tagging.old[(tag - tagging.base) as usize])?;
+        }
+        Ok(instances.into_iter().map(|(instance, _)| instance).collect())
+    }
+    /// the live instances of a generated binding, eg. `jvmti.instances_of::<ClientWorld>(env)`.
+    /// empty when the class has not been loaded yet
+    pub fn instances_of<'a, T : Mapped + From<JObject<'a>>>(&self, env : &'a Jenv<'a>) -> Result<Vec<T>,JniError> {
+        let class = match self.loaded_class(env, T::CLASS) {
+            Ok(class) => class,
+            Err(JniError::ClassNotFound(_)) => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        Ok(self.instances(env, &class)?.into_iter().map(T::from).collect())
+    }
+
+    /// counts the objects and bytes on the heap per class through `IterateThroughHeap`.
+    /// every loaded class is tagged while the heap is walked, the tags they had are put back afterwards. needs `can_tag_objects`
+    pub fn heap_histogram(&self, env : &Jenv) -> Result<HeapHistogram,JniError> {
+        unsafe extern "system" fn count_object(class_tag : jlong, size : jlong, _tag_ptr : *mut jlong, _length : jint, user_data : *mut c_void) -> jint {
+            let counts = &mut *(user_data as *mut Vec<(u64, u64)>);
+            // slot 0 for classes that were loaded after the tagging
+            let index = if class_tag > 0 && (class_tag as usize) < counts.len() { class_tag as usize } else { 0 };
+            let slot = &mut counts[index];
+            slot.0 += 1;
+            slot.1 += size as u64;
+            0
+        }
+        // every class (and the objects wrapping them for the tags) is a local reference, the frame frees them all at once
+        let mut entries = env.with_local_frame(0, |env| {
+            let classes = self.loaded_classes(env)?;
+            let mut old_tags = Vec::with_capacity(classes.len());
+            for (i, class) in classes.iter().enumerate() {
+                let object = JObject::new(class.ptr, env);
+                old_tags.push(self.tag(&object)?);
+                self.set_tag(&object, i as jlong + 1)?;
+            }
+            let mut counts = vec![(0u64, 0u64); classes.len() + 1];
+            let mut callbacks : jvmtiHeapCallbacks = unsafe { mem::zeroed() };
+            callbacks.heap_iteration_callback = unsafe { jnicall(count_object as *const ()) };
+            let walked = check(jvmti_call!(self.ptr,IterateThroughHeap, 0, ptr::null_mut(), &callbacks, &mut counts as *mut Vec<(u64, u64)> as *const c_void));
+            for (class, tag) in classes.iter().zip(old_tags) {
+                self.set_tag(&JObject::new(class.ptr, env), tag)?;
+            }
+            walked?;
+            let untagged = counts[0];
+            let mut entries = vec![];
+            for (class, (instances, bytes)) in classes.iter().zip(counts.into_iter().skip(1)) {
+                if instances > 0 {
+                    let signature = self.class_signature(class)?;
+                    entries.push(HistogramEntry { class : signature.internal_name().to_string(), instances, bytes });
+                }
+            }
+            if untagged.0 > 0 {
+                entries.push(HistogramEntry { class : "<unknown>".to_string(), instances : untagged.0, bytes : untagged.1 });
+            }
+            Ok(entries)
+        })?;
+        entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.instances.cmp(&a.instances)));
+        Ok(HeapHistogram { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{unchecked_jnic, jarray::JArray, jvalue::JValue, jvmti::{hooks::MappedMethod, Capabilities}, vm::tests::{attach, vm}};
+
+    /// a binding the way map-gen generates them
+    struct Thread<'a>(JObject<'a>);
+    impl<'a> From<JObject<'a>> for Thread<'a> {
+        fn from(obj : JObject<'a>) -> Self {
+            Thread(obj)
+        }
+    }
+    impl Mapped for Thread<'_> {
+        const CLASS : &'static str = "java/lang/Thread";
+        const METHODS : &'static [MappedMethod] = &[];
+    }
+    struct Missing;
+    impl From<JObject<'_>> for Missing {
+        fn from(_ : JObject<'_>) -> Self {
+            Missing
+        }
+    }
+    impl Mapped for Missing {
+        const CLASS : &'static str = "mci/Missing";
+        const METHODS : &'static [MappedMethod] = &[];
+    }
+
+    fn same(env : &Jenv, a : &JObject, b : &JObject) -> bool {
+        unchecked_jnic!(env.ptr,IsSameObject, a.ptr, b.ptr) == jdk_sys::JNI_TRUE as u8
+    }
+
+    #[test]
+    fn remap() {
+        let entry = |class : &str| HistogramEntry { class : class.to_string(), instances : 1, bytes : 16 };
+        let mut histogram = HeapHistogram { entries : vec![entry("eev"), entry("[[Leev;"), entry("[I"), entry("java/lang/String")] };
+        histogram.remap(&[MappedClass { yarn : "net/minecraft/client/MinecraftClient", name : "eev" }]);
+        let classes = histogram.entries.iter().map(|e| e.class.as_str()).collect::<Vec<_>>();
+        assert_eq!(classes, ["net/minecraft/client/MinecraftClient", "[[Lnet/minecraft/client/MinecraftClient;", "[I", "java/lang/String"]);
+        assert_eq!((histogram.total_instances(), histogram.total_bytes()), (4, 64));
+    }
+
+    #[test]
+    fn tagged_instances() {
+        let env = attach();
+        let jvmti = JvmtiEnv::from_vm(&vm()).unwrap();
+        jvmti.add_capabilities(&Capabilities { can_tag_objects : true, ..Default::default() }).unwrap();
+        // a class of its own in a fresh loader, so nothing else has instances of it
+        let urls = JArray::<JObject>::create_object(&env, 0, &env.find_class("java/net/URL").unwrap(), None).unwrap();
+        let loader : JObject = env.find_class("java/net/URLClassLoader").unwrap().new_object("<init>", "([Ljava/net/URL;)V", &vec![JValue::from(&urls.ptr)]).unwrap();
+        let class = env.define_class("mci/RustProxyHandler", Some(&loader), include_bytes!("../../java/mci/RustProxyHandler.class")).unwrap();
+        // without running the constructor, which would register a release with the cleaner
+        let alloc = || JObject::new(unchecked_jnic!(env.ptr,AllocObject, class.ptr), &env);
+        let (tagged, untagged) = (alloc(), alloc());
+
+        jvmti.set_tag(&tagged, 42).unwrap();
+        assert_eq!((jvmti.tag(&tagged).unwrap(), jvmti.tag(&untagged).unwrap()), (42, 0));
+        let instances = jvmti.instances(&env, &class).unwrap();
+        assert_eq!(instances.len(), 2);
+        assert!(instances.iter().any(|i| same(&env, i, &tagged)) && instances.iter().any(|i| same(&env, i, &untagged)));
+        // the tags they had are put back
+        assert_eq!((jvmti.tag(&tagged).unwrap(), jvmti.tag(&untagged).unwrap()), (42, 0));
+        let with_tag = jvmti.objects_with_tags(&env, &[42]).unwrap();
+        assert!(with_tag.len() == 1 && same(&env, &with_tag[0], &tagged));
+
+        assert!(!jvmti.instances_of::<Thread>(&env).unwrap().is_empty());
+        assert!(jvmti.instances_of::<Missing>(&env).unwrap().is_empty());
+
+        let class_object = JObject::new(class.ptr, &env);
+        jvmti.set_tag(&class_object, 7).unwrap();
+        let histogram = jvmti.heap_histogram(&env).unwrap();
+        assert_eq!(jvmti.tag(&class_object).unwrap(), 7);
+        assert!(histogram.entries.iter().any(|e| e.class == "mci/RustProxyHandler" && e.instances >= 2));
+        assert!(histogram.entries.iter().any(|e| e.class == "java/lang/String" && e.bytes > 0));
+        assert!(histogram.entries.windows(2).all(|w| w[0].bytes >= w[1].bytes));
+        jvmti.dispose().unwrap();
+    }
+}
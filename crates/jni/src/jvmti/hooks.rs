@@ -0,0 +1,428 @@
+use std::sync::{Arc, RwLock};
+
+use jdk_sys::{jdouble, jfloat, jint, jlocation, jlong, jmethodID, jobject, jthread, jvalue};
+
+use crate::{unchecked_jnic, env::Jenv, object::JObject, jvalue::JValue, error::JniError};
+
+use super::{check, Event, JvmtiEnv};
+
+const ACC_STATIC : i32 = 0x0008;
+
+/// A method of a mapped class, the generated bindings list these in [`Mapped::METHODS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedMethod {
+    /// the yarn name, eg. `getInstance`
+    pub yarn : &'static str,
+    /// the obfuscated name the jvm knows the method by
+    pub name : &'static str,
+    /// the obfuscated descriptor
+    pub sig : &'static str,
+}
+
+/// Implemented by the generated bindings for every class that has mappings, see [`JvmtiEnv::hook_mapped`]
+pub trait Mapped {
+    /// the obfuscated internal name of the class
+    const CLASS : &'static str;
+    const METHODS : &'static [MappedMethod];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    /// `MethodEntry`, slow since the jvm posts it for every method once any method is hooked
+    Entry,
+    /// `MethodExit`, same cost as [`HookKind::Entry`]
+    Exit,
+    /// a breakpoint on the first instruction, only the hooked methods pay for it
+    Breakpoint,
+}
+
+/// A call of a hooked method
+pub struct MethodEvent<'a> {
+    pub kind : HookKind,
+    pub thread : JObject<'a>,
+    pub method : jmethodID,
+    /// `None` for static methods
+    pub this : Option<JObject<'a>>,
+    /// read from the frame's locals, so on exit they are whatever the method left in them.
+    /// empty for native methods and when the environment lacks `can_access_local_variables`
+    pub args : Vec<JValue<'a>>,
+    /// the returned value, only on exits of non `void` methods which were not popped by an exception
+    pub ret : Option<JValue<'a>>,
+    pub popped_by_exception : bool,
+}
+
+pub type MethodHook = dyn for<'e> Fn(&'e Jenv<'e>, &MethodEvent<'e>) + Send + Sync;
+
+/// a hooked method, with what is needed to read its frame worked out up front
+struct Hook {
+    method : usize,
+    kind : HookKind,
+    is_static : bool,
+    /// first char of each parameter descriptor, `L` for arrays too
+    params : Vec<u8>,
+    ret : u8,
+    f : Arc<MethodHook>,
+}
+
+/// the hooks of every environment
+static HOOKS : RwLock<Vec<(usize, Vec<Arc<Hook>>)>> = RwLock::new(Vec::new());
+
+impl JvmtiEnv {
+    /// `SetBreakpoint`, needs `can_generate_breakpoint_events`
+    pub fn set_breakpoint(&self, method : jmethodID, location : jlocation) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,SetBreakpoint, method, location))
+    }
+    /// `ClearBreakpoint`
+    pub fn clear_breakpoint(&self, method : jmethodID, location : jlocation) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,ClearBreakpoint, method, location))
+    }
+
+    /// calls `f` whenever `method` is entered, exited or reaches its first instruction (depending on `kind`).
+    /// needs the matching `can_generate_*_events` capability, and `can_access_local_variables` for `this` and the arguments
+    pub fn hook_method(&self, method : jmethodID, kind : HookKind, f : impl for<'e> Fn(&'e Jenv<'e>, &MethodEvent<'e>) + Send + Sync + 'static) -> Result<(),JniError> {
+        self.hook_arc(method, kind, Arc::new(f))
+    }
+    /// hooks every method called `name` (and with the descriptor `sig` if it is given) declared by the loaded class `class`, returns how many there were.
+    /// nothing stays hooked when this fails
+    pub fn hook_class_method(&self, env : &Jenv, class : &str, name : &str, sig : Option<&str>, kind : HookKind, f : impl for<'e> Fn(&'e Jenv<'e>, &MethodEvent<'e>) + Send + Sync + 'static) -> Result<usize,JniError> {
+        let methods = self.methods_by_name(env, class, name, sig)?;
+        self.hook_all(&methods, kind, Arc::new(f))?;
+        Ok(methods.len())
+    }
+    /// hooks every overload of the method with the yarn name `yarn` of the generated class `T`, eg.
+    /// `jvmti.hook_mapped::<MinecraftClient>(&env, "tick", HookKind::Breakpoint, |env, event| ..)`.
+    /// the class has to be loaded already. every overload is looked up before any is hooked, and nothing stays hooked when this fails
+    pub fn hook_mapped<T : Mapped>(&self, env : &Jenv, yarn : &str, kind : HookKind, f : impl for<'e> Fn(&'e Jenv<'e>, &MethodEvent<'e>) + Send + Sync + 'static) -> Result<usize,JniError> {
+        let mut methods = vec![];
+        for method in T::METHODS.iter().filter(|m| m.yarn == yarn) {
+            methods.extend(self.methods_by_name(env, T::CLASS, method.name, Some(method.sig))?);
+        }
+        if methods.is_empty() {
+            return Err(JniError::MethodNotFound { name : yarn.to_string(), sig : String::new() });
+        }
+        self.hook_all(&methods, kind, Arc::new(f))?;
+        Ok(methods.len())
+    }
+    /// removes every hook of `method`
+    pub fn unhook_method(&self, method : jmethodID) -> Result<(),JniError> {
+        self.unhook_where(method, |_| true)
+    }
+
+    /// removes the hooks of `method` matching `remove`
+    fn unhook_where(&self, method : jmethodID, remove : impl Fn(&Hook) -> bool) -> Result<(),JniError> {
+        let (removed, remaining) = {
+            let mut lock = HOOKS.write().unwrap();
+            let Some((_, hooks)) = lock.iter_mut().find(|(env, _)| *env == self.ptr as usize) else {
+                return Ok(());
+            };
+            let (removed, kept) = hooks.drain(..).partition::<Vec<_>, _>(|h| h.method == method as usize && remove(h));
+            *hooks = kept;
+            (removed, hooks.iter().map(|h| (h.method, h.kind)).collect::<Vec<_>>())
+        };
+        // other hooks on the same method share the breakpoint
+        if removed.iter().any(|h| h.kind == HookKind::Breakpoint) && !remaining.contains(&(method as usize, HookKind::Breakpoint)) {
+            self.clear_breakpoint(method, 0)?;
+        }
+        // entry and exit events are global, turn them off again once nothing needs them
+        for (kind, event) in [(HookKind::Entry, Event::MethodEntry), (HookKind::Exit, Event::MethodExit)] {
+            if removed.iter().any(|h| h.kind == kind) && !remaining.iter().any(|(_, k)| *k == kind) {
+                self.set_event_enabled(event, false, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// undoes the hooks of `f` on `hooked` after `error`, which is passed on
+    fn unhook_all(&self, hooked : &[jmethodID], f : &Arc<MethodHook>, error : JniError) -> JniError {
+        for method in hooked {
+            let _ = self.unhook_where(*method, |h| Arc::ptr_eq(&h.f, f));
+        }
+        error
+    }
+    /// hooks every one of `methods` or, when one fails, none of them
+    fn hook_all(&self, methods : &[jmethodID], kind : HookKind, f : Arc<MethodHook>) -> Result<(),JniError> {
+        for (idx, method) in methods.iter().enumerate() {
+            self.hook_arc(*method, kind, f.clone()).map_err(|e| self.unhook_all(&methods[..idx], &f, e))?;
+        }
+        Ok(())
+    }
+    /// the methods called `name` (with the descriptor `sig` if it is given) declared by the loaded class `class`, at least one
+    fn methods_by_name(&self, env : &Jenv, class : &str, name : &str, sig : Option<&str>) -> Result<Vec<jmethodID>,JniError> {
+        let class = self.loaded_class(env, class)?;
+        // method ids outlive the class reference
+        let methods = self.class_methods(&class);
+        unchecked_jnic!(env.ptr,DeleteLocalRef, class.ptr);
+        let mut found = vec![];
+        for method in methods? {
+            let member = self.method_name(method)?;
+            if member.name == name && sig.is_none_or(|sig| member.signature == sig) {
+                found.push(method);
+            }
+        }
+        if found.is_empty() {
+            return Err(JniError::MethodNotFound { name : name.to_string(), sig : sig.unwrap_or_default().to_string() });
+        }
+        Ok(found)
+    }
+    fn hook_arc(&self, method : jmethodID, kind : HookKind, f : Arc<MethodHook>) -> Result<(),JniError> {
+        let signature = self.method_name(method)?.signature;
+        let (params, ret) = parse_descriptor(&signature).ok_or_else(|| JniError::InvalidUtf(format!("bad method descriptor {signature}")))?;
+        let hook = Arc::new(Hook {
+            method : method as usize,
+            kind,
+            is_static : self.method_modifiers(method)? & ACC_STATIC != 0,
+            params,
+            ret,
+            f : f.clone(),
+        });
+        let first = {
+            let mut lock = HOOKS.write().unwrap();
+            let key = self.ptr as usize;
+            match lock.iter_mut().find(|(env, _)| *env == key) {
+                Some((_, hooks)) => {
+                    hooks.push(hook);
+                    // empty again after everything was unhooked, or after the first hook failed
+                    hooks.len() == 1
+                }
+                None => {
+                    lock.push((key, vec![hook]));
+                    true
+                }
+            }
+        };
+        let enabled = if first { self.install_dispatch() } else { Ok(()) }.and_then(|_| match kind {
+            HookKind::Entry => self.set_event_enabled(Event::MethodEntry, true, None),
+            HookKind::Exit => self.set_event_enabled(Event::MethodExit, true, None),
+            HookKind::Breakpoint => self.set_event_enabled(Event::Breakpoint, true, None).and_then(|_| match self.set_breakpoint(method, 0) {
+                // a second hook on the same method shares the breakpoint
+                Err(JniError::Jvmti(jdk_sys::jvmtiError_JVMTI_ERROR_DUPLICATE)) => Ok(()),
+                result => result,
+            }),
+        });
+        enabled.map_err(|e| self.unhook_all(&[method], &f, e))
+    }
+    /// points the method entry, exit and breakpoint handlers of the environment at [`dispatch`].
+    /// none of the events are enabled here, so this works whichever of the capabilities the environment has
+    fn install_dispatch(&self) -> Result<(),JniError> {
+        let key = self.ptr as usize;
+        self.on_method_entry(move |env, thread, method| dispatch(key, env, HookKind::Entry, thread, method, None))?;
+        self.on_method_exit(move |env, thread, method, popped, value| dispatch(key, env, HookKind::Exit, thread, method, Some((popped, value))))?;
+        self.set_breakpoint_handler(move |env, thread, method, location| {
+            if location == 0 {
+                dispatch(key, env, HookKind::Breakpoint, thread.ptr, method, None)
+            }
+        })
+    }
+
+    /// `GetLocalInstance`, `this` of the frame `depth` calls up from the top of `thread`'s stack
+    pub fn local_instance<'a>(&self, env : &'a Jenv<'a>, thread : &JObject, depth : i32) -> Result<JObject<'a>,JniError> {
+        let mut value : jobject = std::ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetLocalInstance, thread.ptr, depth, &mut value))?;
+        Ok(object(env, value))
+    }
+    /// `GetLocalObject`
+    pub fn local_object<'a>(&self, env : &'a Jenv<'a>, thread : &JObject, depth : i32, slot : i32) -> Result<JObject<'a>,JniError> {
+        let mut value : jobject = std::ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetLocalObject, thread.ptr, depth, slot, &mut value))?;
+        Ok(object(env, value))
+    }
+    /// `GetLocalInt`, also used for `boolean`, `byte`, `char` and `short` locals
+    pub fn local_int(&self, thread : &JObject, depth : i32, slot : i32) -> Result<i32,JniError> {
+        let mut value : jint = 0;
+        check(jvmti_call!(self.ptr,GetLocalInt, thread.ptr, depth, slot, &mut value))?;
+        Ok(value)
+    }
+    /// `GetLocalLong`
+    pub fn local_long(&self, thread : &JObject, depth : i32, slot : i32) -> Result<i64,JniError> {
+        let mut value : jlong = 0;
+        check(jvmti_call!(self.ptr,GetLocalLong, thread.ptr, depth, slot, &mut value))?;
+        Ok(value)
+    }
+    /// `GetLocalFloat`
+    pub fn local_float(&self, thread : &JObject, depth : i32, slot : i32) -> Result<f32,JniError> {
+        let mut value : jfloat = 0.0;
+        check(jvmti_call!(self.ptr,GetLocalFloat, thread.ptr, depth, slot, &mut value))?;
+        Ok(value)
+    }
+    /// `GetLocalDouble`
+    pub fn local_double(&self, thread : &JObject, depth : i32, slot : i32) -> Result<f64,JniError> {
+        let mut value : jdouble = 0.0;
+        check(jvmti_call!(self.ptr,GetLocalDouble, thread.ptr, depth, slot, &mut value))?;
+        Ok(value)
+    }
+
+    /// the arguments in the locals of the top frame, slot 0 is `this` for instance methods and `long` / `double` take two slots
+    fn frame_args<'a>(&self, env : &'a Jenv<'a>, thread : &JObject, hook : &Hook) -> Result<Vec<JValue<'a>>,JniError> {
+        let mut slot = if hook.is_static { 0 } else { 1 };
+        let mut args = Vec::with_capacity(hook.params.len());
+        for param in &hook.params {
+            args.push(match param {
+                b'J' => JValue::JLong(self.local_long(thread, 0, slot)?),
+                b'F' => JValue::JFloat(self.local_float(thread, 0, slot)?),
+                b'D' => JValue::JDouble(self.local_double(thread, 0, slot)?),
+                b'L' => JValue::JObject(self.local_object(env, thread, 0, slot)?),
+                c => int_value(*c, self.local_int(thread, 0, slot)?),
+            });
+            slot += if matches!(param, b'J' | b'D') { 2 } else { 1 };
+        }
+        Ok(args)
+    }
+}
+
+/// drops the hooks of an environment that is being disposed
+pub(super) fn forget(env : *mut jdk_sys::jvmtiEnv) {
+    HOOKS.write().unwrap().retain(|(key, _)| *key != env as usize);
+}
+
+/// runs the hooks of `method`, the thread is only wrapped once there is one
+fn dispatch<'e>(key : usize, env : &'e Jenv<'e>, kind : HookKind, thread : jthread, method : jmethodID, exit : Option<(bool, jvalue)>) {
+    let hooks = {
+        let lock = HOOKS.read().unwrap();
+        let Some((_, hooks)) = lock.iter().find(|(env, _)| *env == key) else {
+            return;
+        };
+        hooks.iter().filter(|h| h.method == method as usize && h.kind == kind).cloned().collect::<Vec<_>>()
+    };
+    let Some(hook) = hooks.first() else {
+        return;
+    };
+    let thread = JObject::new(thread, env);
+    let jvmti = JvmtiEnv { ptr : key as *mut jdk_sys::jvmtiEnv };
+    let this = if hook.is_static { None } else { jvmti.local_instance(env, &thread, 0).ok() };
+    let args = jvmti.frame_args(env, &thread, hook).unwrap_or_default();
+    let popped_by_exception = exit.is_some_and(|(popped, _)| popped);
+    let ret = match exit {
+        Some((false, value)) => return_value(env, hook.ret, value),
+        _ => None,
+    };
+    let event = MethodEvent { kind, thread, method, this, args, ret, popped_by_exception };
+    for hook in &hooks {
+        (hook.f)(env, &event);
+    }
+}
+
+fn object<'a>(env : &'a Jenv<'a>, ptr : jobject) -> JObject<'a> {
+    if ptr.is_null() { JObject::null(env) } else { JObject::new(ptr, env) }
+}
+
+fn int_value<'a>(kind : u8, value : i32) -> JValue<'a> {
+    match kind {
+        b'Z' => JValue::JBoolean(value != 0),
+        b'B' => JValue::JByte(value as i8),
+        b'C' => JValue::JChar(char::from_u32(value as u32 & 0xFFFF).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        b'S' => JValue::JShort(value as i16),
+        _ => JValue::JInt(value),
+    }
+}
+
+fn return_value<'a>(env : &'a Jenv<'a>, kind : u8, value : jvalue) -> Option<JValue<'a>> {
+    unsafe {
+        Some(match kind {
+            b'V' => return None,
+            b'J' => JValue::JLong(value.j),
+            b'F' => JValue::JFloat(value.f),
+            b'D' => JValue::JDouble(value.d),
+            b'L' => JValue::JObject(object(env, value.l)),
+            b'Z' => JValue::JBoolean(value.z != 0),
+            c => int_value(c, value.i),
+        })
+    }
+}
+
+/// the kinds of the parameters and of the return type of a method descriptor, arrays count as objects
+fn parse_descriptor(sig : &str) -> Option<(Vec<u8>, u8)> {
+    let bytes = sig.as_bytes();
+    if bytes.first() != Some(&b'(') {
+        return None;
+    }
+    let mut idx = 1;
+    let kind = |idx : &mut usize| -> Option<u8> {
+        let start = *idx;
+        while *bytes.get(*idx)? == b'[' {
+            *idx += 1;
+        }
+        let c = *bytes.get(*idx)?;
+        if c == b'L' {
+            *idx += bytes[*idx..].iter().position(|b| *b == b';')?;
+        }
+        *idx += 1;
+        Some(if *idx - start > 1 { b'L' } else { c })
+    };
+    let mut params = vec![];
+    while *bytes.get(idx)? != b')' {
+        params.push(kind(&mut idx)?);
+    }
+    idx += 1;
+    let ret = kind(&mut idx)?;
+    (idx == bytes.len()).then_some((params, ret))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{jvmti::Capabilities, vm::tests::{attach, vm}};
+
+    #[test]
+    fn descriptors() {
+        assert_eq!(parse_descriptor("()V"), Some((vec![], b'V')));
+        assert_eq!(parse_descriptor("(IJ[I[[Ljava/lang/String;Lefl;D)Z"), Some((vec![b'I', b'J', b'L', b'L', b'L', b'D'], b'Z')));
+        assert_eq!(parse_descriptor("(I)[Lefl;"), Some((vec![b'I'], b'L')));
+        for bad in ["", "I", "(I", "(Lefl)V", "(I)", "(I)VV"] {
+            assert_eq!(parse_descriptor(bad), None, "{bad}");
+        }
+        assert!(matches!(int_value(b'Z', 2), JValue::JBoolean(true)));
+        assert!(matches!(int_value(b'B', 0x1ff), JValue::JByte(-1)));
+        assert!(matches!(int_value(b'C', 0x10041), JValue::JChar('A')));
+        assert!(matches!(int_value(b'S', -1), JValue::JShort(-1)));
+        assert!(matches!(int_value(b'I', -1), JValue::JInt(-1)));
+    }
+
+    #[test]
+    fn rolled_back() {
+        let env = attach();
+        let jvmti = JvmtiEnv::from_vm(&vm()).unwrap();
+        let key = jvmti.ptr as usize;
+        assert_eq!(jvmti.methods_by_name(&env, "java/lang/Integer", "max", None).unwrap().len(), 1);
+        assert_eq!(jvmti.methods_by_name(&env, "java/lang/Math", "max", None).unwrap().len(), 4);
+        assert!(matches!(jvmti.methods_by_name(&env, "java/lang/Math", "max", Some("(SS)S")), Err(JniError::MethodNotFound { name, sig }) if name == "max" && sig == "(SS)S"));
+        // the test jvm's debug agent holds breakpoints, so the first overload fails and none stay hooked
+        assert!(!jvmti.capabilities().unwrap().can_generate_breakpoint_events);
+        let hooked = jvmti.hook_class_method(&env, "java/lang/Math", "max", None, HookKind::Breakpoint, |_, _| {});
+        assert!(matches!(hooked, Err(JniError::Jvmti(jdk_sys::jvmtiError_JVMTI_ERROR_MUST_POSSESS_CAPABILITY))));
+        assert!(HOOKS.read().unwrap().iter().all(|(env, hooks)| *env != key || hooks.is_empty()));
+        jvmti.dispose().unwrap();
+        assert!(HOOKS.read().unwrap().iter().all(|(env, _)| *env != key));
+    }
+
+    #[test]
+    fn fired() {
+        // the hooks run on the calling thread, calls from tests running next to this one are not recorded
+        type Recorded = (HookKind, Vec<i32>, Option<i32>);
+        thread_local!(static EVENTS : RefCell<Vec<Recorded>> = const { RefCell::new(vec![]) });
+        fn record(_ : &Jenv, event : &MethodEvent) {
+            let int = |value : &JValue| match value { JValue::JInt(i) => *i, _ => panic!("not an int") };
+            assert!(event.this.is_none() && !event.popped_by_exception);
+            EVENTS.with(|e| e.borrow_mut().push((event.kind, event.args.iter().map(int).collect(), event.ret.as_ref().map(int))));
+        }
+        let env = attach();
+        let jvmti = JvmtiEnv::from_vm(&vm()).unwrap();
+        jvmti.add_capabilities(&Capabilities { can_generate_method_entry_events : true, can_generate_method_exit_events : true, can_access_local_variables : true, ..Default::default() }).unwrap();
+        assert!(!jvmti.capabilities().unwrap().can_generate_breakpoint_events);
+        assert_eq!(jvmti.hook_class_method(&env, "java/lang/Integer", "max", None, HookKind::Entry, record).unwrap(), 1);
+        assert_eq!(jvmti.hook_class_method(&env, "java/lang/Integer", "max", None, HookKind::Exit, record).unwrap(), 1);
+
+        let integer = env.find_class("java/lang/Integer").unwrap();
+        let max = |a, b| integer.call_static_int_method("max", "(II)I", &vec![JValue::JInt(a), JValue::JInt(b)]).unwrap();
+        assert_eq!(max(3, 7), 7);
+        // max leaves its locals alone, so the exit sees the arguments too
+        assert_eq!(EVENTS.take(), [(HookKind::Entry, vec![3, 7], None), (HookKind::Exit, vec![3, 7], Some(7))]);
+
+        let method = jvmti.methods_by_name(&env, "java/lang/Integer", "max", None).unwrap()[0];
+        jvmti.unhook_method(method).unwrap();
+        assert_eq!(max(3, 7), 7);
+        assert!(EVENTS.take().is_empty());
+        jvmti.dispose().unwrap();
+    }
+}
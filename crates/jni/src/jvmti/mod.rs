@@ -0,0 +1,445 @@
+//! a safe layer over the jvm tool interface (jvmti), the api agents use to inspect and instrument the jvm.
+use std::{ffi::{c_char, c_uchar, CStr}, ptr};
+
+pub use jdk_sys::{jmethodID, jfieldID};
+
+use jdk_sys::{jclass, jint, jlong, jvmtiCapabilities, jvmtiEnv, jvmtiError, JNI_OK, JVMTI_VERSION_1_2};
+
+use crate::{unchecked_jnic, env::Jenv, class::JClass, error::JniError, mutf8, vm::JavaVm};
+
+macro_rules! jvmti_call {
+    ($env:expr,$fna:tt$(, $a:expr)*) => {
+        unsafe {(*(*$env)).$fna.unwrap_unchecked()($env$(, $a)*)}
+    };
+}
+
+pub mod events;
+pub mod transform;
+pub mod hooks;
+pub mod heap;
+
+pub use events::Event;
+pub use transform::ClassFileLoad;
+pub use hooks::{HookKind, MethodEvent, Mapped, MappedMethod};
+pub use heap::{HeapHistogram, HistogramEntry, MappedClass};
+
+/// A jvmti environment. like [`JavaVm`] it is only a pointer, it can be copied and used from any thread attached to the jvm.
+/// every environment has its own capabilities (and later, event callbacks), agents usually make one and keep it around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JvmtiEnv {
+    ptr : *mut jvmtiEnv,
+}
+
+unsafe impl Send for JvmtiEnv {}
+unsafe impl Sync for JvmtiEnv {}
+
+/// the name and descriptors of a method or field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberName {
+    pub name : String,
+    /// jni descriptor, eg. `(I)V` or `Ljava/lang/String;`
+    pub signature : String,
+    /// the generic signature, only present when the member uses generics
+    pub generic : Option<String>,
+}
+
+/// the descriptor of a class, eg. `Ljava/lang/String;`, and its generic signature if it has one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature {
+    pub signature : String,
+    pub generic : Option<String>,
+}
+
+impl ClassSignature {
+    /// the internal name (`java/lang/String`) for plain classes, array and primitive descriptors are returned as is
+    pub fn internal_name(&self) -> &str {
+        self.signature.strip_prefix('L')
+            .and_then(|s| s.strip_suffix(';'))
+            .unwrap_or(&self.signature)
+    }
+}
+
+fn check(err : jvmtiError) -> Result<(),JniError> {
+    if err != jdk_sys::jvmtiError_JVMTI_ERROR_NONE {
+        return Err(JniError::Jvmti(err));
+    }
+    Ok(())
+}
+
+/// a JNICALL (`extern "system"`) callback as the function pointer type bindgen generated for it.
+/// bindgen spells JNICALL `extern "C"` wherever the two are the same abi, on 32 bit windows both are stdcall
+/// # Safety
+/// `f` must be an `extern "system"` fn with the signature jvmti.h gives `F`
+unsafe fn jnicall<F : Copy>(f : *const ()) -> Option<F> {
+    assert_eq!(std::mem::size_of::<F>(), std::mem::size_of::<*const ()>());
+    Some(std::mem::transmute_copy(&f))
+}
+
+impl JvmtiEnv {
+    /// `GetEnv` with `JVMTI_VERSION_1_2`, makes a new environment on `vm`. works from `Agent_OnLoad` onwards
+    pub fn from_vm(vm : &JavaVm) -> Result<Self,JniError> {
+        let mut ptr : *mut jvmtiEnv = ptr::null_mut();
+        let raw = vm.as_raw();
+        let status = unsafe { (*(*raw)).GetEnv.unwrap_unchecked()(raw, &mut ptr as *mut *mut jvmtiEnv as _, JVMTI_VERSION_1_2 as i32) };
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        unsafe { Self::from_raw(ptr) }
+    }
+    /// # Safety
+    /// `ptr` must point to a live jvmti environment
+    pub unsafe fn from_raw(ptr : *mut jvmtiEnv) -> Result<Self,JniError> {
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self { ptr })
+    }
+    pub fn as_raw(&self) -> *mut jvmtiEnv {
+        self.ptr
+    }
+    /// `DisposeEnvironment`, relinquishes the capabilities of this environment and clears its callbacks
+    pub fn dispose(self) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,DisposeEnvironment))?;
+        events::forget(self.ptr);
+        transform::forget(self.ptr);
+        hooks::forget(self.ptr);
+        Ok(())
+    }
+
+    /// `GetPotentialCapabilities`, what could still be added to this environment in the current phase
+    pub fn potential_capabilities(&self) -> Result<Capabilities,JniError> {
+        let mut raw = Capabilities::zeroed();
+        check(jvmti_call!(self.ptr,GetPotentialCapabilities, &mut raw))?;
+        Ok(Capabilities::from_raw(&raw))
+    }
+    /// `GetCapabilities`, what this environment currently has
+    pub fn capabilities(&self) -> Result<Capabilities,JniError> {
+        let mut raw = Capabilities::zeroed();
+        check(jvmti_call!(self.ptr,GetCapabilities, &mut raw))?;
+        Ok(Capabilities::from_raw(&raw))
+    }
+    /// `AddCapabilities`, fails with `JVMTI_ERROR_NOT_AVAILABLE` if any of them can not be had.
+    /// some (eg. `can_generate_all_class_hook_events`) are only available during `Agent_OnLoad`
+    pub fn add_capabilities(&self, capabilities : &Capabilities) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,AddCapabilities, &capabilities.to_raw()))
+    }
+    /// adds whatever part of `wanted` is potentially available and returns what was added
+    pub fn add_available_capabilities(&self, wanted : &Capabilities) -> Result<Capabilities,JniError> {
+        let available = wanted.intersection(&self.potential_capabilities()?);
+        self.add_capabilities(&available)?;
+        Ok(available)
+    }
+    /// `RelinquishCapabilities`
+    pub fn relinquish_capabilities(&self, capabilities : &Capabilities) -> Result<(),JniError> {
+        check(jvmti_call!(self.ptr,RelinquishCapabilities, &capabilities.to_raw()))
+    }
+
+    /// `GetLoadedClasses`, every class loaded by the jvm (including arrays and primitives) as local refs of `env`.
+    /// that is one local reference per class (tens of thousands in a modded game), call it inside [`Jenv::with_local_frame`]
+    pub fn loaded_classes<'a>(&self, env : &'a Jenv<'a>) -> Result<Vec<JClass<'a>>,JniError> {
+        Ok(self.raw_loaded_classes()?.into_iter().map(|c| JClass::new(c, env)).collect())
+    }
+    fn raw_loaded_classes(&self) -> Result<Vec<jclass>,JniError> {
+        let mut count : jint = 0;
+        let mut classes : *mut jclass = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetLoadedClasses, &mut count, &mut classes))?;
+        unsafe { self.take_array(classes, count) }
+    }
+    /// the loaded class with the internal name `name` (eg. `java/lang/String`), from any class loader.
+    /// unlike [`Jenv::find_class`] this never loads anything. every loaded class is looked at, the references to the ones
+    /// that don't match are deleted straight away so only the returned one is left in `env`
+    pub fn loaded_class<'a>(&self, env : &'a Jenv<'a>, name : &str) -> Result<JClass<'a>,JniError> {
+        let name = name.replace('.', "/");
+        let mut found = None;
+        for class in self.raw_loaded_classes()? {
+            if found.is_none() && self.class_signature(&JClass::new(class, env)).is_ok_and(|s| s.internal_name() == name) {
+                found = Some(class);
+                continue;
+            }
+            unchecked_jnic!(env.ptr,DeleteLocalRef, class);
+        }
+        found.map(|class| JClass::new(class, env)).ok_or(JniError::ClassNotFound(name))
+    }
+    /// `GetClassSignature`
+    pub fn class_signature(&self, class : &JClass) -> Result<ClassSignature,JniError> {
+        let mut signature : *mut c_char = ptr::null_mut();
+        let mut generic : *mut c_char = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetClassSignature, class.ptr, &mut signature, &mut generic))?;
+        let (signature, generic) = unsafe { (self.take_string(signature), self.take_string(generic)) };
+        Ok(ClassSignature {
+            signature : signature?.ok_or(JniError::NullReturn)?,
+            generic : generic?,
+        })
+    }
+    /// `GetClassModifiers`, the `ACC_*` flags of the class
+    pub fn class_modifiers(&self, class : &JClass) -> Result<i32,JniError> {
+        let mut modifiers : jint = 0;
+        check(jvmti_call!(self.ptr,GetClassModifiers, class.ptr, &mut modifiers))?;
+        Ok(modifiers)
+    }
+    /// `GetClassMethods`, the methods declared by the class itself (constructors and `<clinit>` included, inherited ones not).
+    /// fails with `JVMTI_ERROR_CLASS_NOT_PREPARED` for classes that are not linked yet
+    pub fn class_methods(&self, class : &JClass) -> Result<Vec<jmethodID>,JniError> {
+        let mut count : jint = 0;
+        let mut methods : *mut jmethodID = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetClassMethods, class.ptr, &mut count, &mut methods))?;
+        unsafe { self.take_array(methods, count) }
+    }
+    /// `GetClassFields`, the fields declared by the class itself
+    pub fn class_fields(&self, class : &JClass) -> Result<Vec<jfieldID>,JniError> {
+        let mut count : jint = 0;
+        let mut fields : *mut jfieldID = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetClassFields, class.ptr, &mut count, &mut fields))?;
+        unsafe { self.take_array(fields, count) }
+    }
+
+    /// `GetMethodName`
+    pub fn method_name(&self, method : jmethodID) -> Result<MemberName,JniError> {
+        let mut name : *mut c_char = ptr::null_mut();
+        let mut signature : *mut c_char = ptr::null_mut();
+        let mut generic : *mut c_char = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetMethodName, method, &mut name, &mut signature, &mut generic))?;
+        unsafe { self.take_member_name(name, signature, generic) }
+    }
+    /// `GetMethodDeclaringClass`
+    pub fn method_declaring_class<'a>(&self, env : &'a Jenv<'a>, method : jmethodID) -> Result<JClass<'a>,JniError> {
+        let mut class : jclass = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetMethodDeclaringClass, method, &mut class))?;
+        Ok(JClass::new(class, env))
+    }
+    /// `GetMethodModifiers`, the `ACC_*` flags of the method
+    pub fn method_modifiers(&self, method : jmethodID) -> Result<i32,JniError> {
+        let mut modifiers : jint = 0;
+        check(jvmti_call!(self.ptr,GetMethodModifiers, method, &mut modifiers))?;
+        Ok(modifiers)
+    }
+    /// `GetFieldName`
+    pub fn field_name(&self, class : &JClass, field : jfieldID) -> Result<MemberName,JniError> {
+        let mut name : *mut c_char = ptr::null_mut();
+        let mut signature : *mut c_char = ptr::null_mut();
+        let mut generic : *mut c_char = ptr::null_mut();
+        check(jvmti_call!(self.ptr,GetFieldName, class.ptr, field, &mut name, &mut signature, &mut generic))?;
+        unsafe { self.take_member_name(name, signature, generic) }
+    }
+    /// `GetFieldModifiers`, the `ACC_*` flags of the field
+    pub fn field_modifiers(&self, class : &JClass, field : jfieldID) -> Result<i32,JniError> {
+        let mut modifiers : jint = 0;
+        check(jvmti_call!(self.ptr,GetFieldModifiers, class.ptr, field, &mut modifiers))?;
+        Ok(modifiers)
+    }
+
+    /// `Allocate`, memory jvmti (or the jvm) will free, eg. transformed class bytes
+    pub fn allocate(&self, size : usize) -> Result<*mut u8,JniError> {
+        let mut mem : *mut c_uchar = ptr::null_mut();
+        check(jvmti_call!(self.ptr,Allocate, size as jlong, &mut mem))?;
+        Ok(mem)
+    }
+    /// `Deallocate`, frees memory handed out by jvmti
+    /// # Safety
+    /// `mem` must have come from this environment and not be used afterwards
+    pub unsafe fn deallocate<T>(&self, mem : *mut T) -> Result<(),JniError> {
+        if mem.is_null() {
+            return Ok(());
+        }
+        check(jvmti_call!(self.ptr,Deallocate, mem as *mut c_uchar))
+    }
+    /// copies out and frees a jvmti allocated array
+    unsafe fn take_array<T : Copy>(&self, ptr : *mut T, count : jint) -> Result<Vec<T>,JniError> {
+        if ptr.is_null() {
+            return Ok(vec![]);
+        }
+        let out = std::slice::from_raw_parts(ptr, count.max(0) as usize).to_vec();
+        self.deallocate(ptr)?;
+        Ok(out)
+    }
+    /// copies out and frees a jvmti allocated (modified utf-8) string
+    unsafe fn take_string(&self, ptr : *mut c_char) -> Result<Option<String>,JniError> {
+        if ptr.is_null() {
+            return Ok(None);
+        }
+        let string = mutf8::decode_lossy(CStr::from_ptr(ptr).to_bytes()).into_owned();
+        self.deallocate(ptr)?;
+        Ok(Some(string))
+    }
+    unsafe fn take_member_name(&self, name : *mut c_char, signature : *mut c_char, generic : *mut c_char) -> Result<MemberName,JniError> {
+        // take all three before bailing so none of them leak
+        let (name, signature, generic) = (self.take_string(name), self.take_string(signature), self.take_string(generic));
+        Ok(MemberName {
+            name : name?.ok_or(JniError::NullReturn)?,
+            signature : signature?.ok_or(JniError::NullReturn)?,
+            generic : generic?,
+        })
+    }
+}
+
+/// the readable name of a `jvmtiError`, see [`JniError::Jvmti`]
+pub fn error_name(err : jvmtiError) -> &'static str {
+    use jdk_sys::*;
+    #[allow(non_upper_case_globals)]
+    match err {
+        jvmtiError_JVMTI_ERROR_NONE => "JVMTI_ERROR_NONE",
+        jvmtiError_JVMTI_ERROR_INVALID_THREAD => "JVMTI_ERROR_INVALID_THREAD",
+        jvmtiError_JVMTI_ERROR_INVALID_OBJECT => "JVMTI_ERROR_INVALID_OBJECT",
+        jvmtiError_JVMTI_ERROR_INVALID_CLASS => "JVMTI_ERROR_INVALID_CLASS",
+        jvmtiError_JVMTI_ERROR_CLASS_NOT_PREPARED => "JVMTI_ERROR_CLASS_NOT_PREPARED",
+        jvmtiError_JVMTI_ERROR_INVALID_METHODID => "JVMTI_ERROR_INVALID_METHODID",
+        jvmtiError_JVMTI_ERROR_INVALID_LOCATION => "JVMTI_ERROR_INVALID_LOCATION",
+        jvmtiError_JVMTI_ERROR_INVALID_FIELDID => "JVMTI_ERROR_INVALID_FIELDID",
+        jvmtiError_JVMTI_ERROR_NO_MORE_FRAMES => "JVMTI_ERROR_NO_MORE_FRAMES",
+        jvmtiError_JVMTI_ERROR_OPAQUE_FRAME => "JVMTI_ERROR_OPAQUE_FRAME",
+        jvmtiError_JVMTI_ERROR_DUPLICATE => "JVMTI_ERROR_DUPLICATE",
+        jvmtiError_JVMTI_ERROR_NOT_FOUND => "JVMTI_ERROR_NOT_FOUND",
+        jvmtiError_JVMTI_ERROR_UNMODIFIABLE_CLASS => "JVMTI_ERROR_UNMODIFIABLE_CLASS",
+        jvmtiError_JVMTI_ERROR_INVALID_CLASS_FORMAT => "JVMTI_ERROR_INVALID_CLASS_FORMAT",
+        jvmtiError_JVMTI_ERROR_FAILS_VERIFICATION => "JVMTI_ERROR_FAILS_VERIFICATION",
+        jvmtiError_JVMTI_ERROR_NOT_AVAILABLE => "JVMTI_ERROR_NOT_AVAILABLE",
+        jvmtiError_JVMTI_ERROR_MUST_POSSESS_CAPABILITY => "JVMTI_ERROR_MUST_POSSESS_CAPABILITY",
+        jvmtiError_JVMTI_ERROR_NULL_POINTER => "JVMTI_ERROR_NULL_POINTER",
+        jvmtiError_JVMTI_ERROR_ABSENT_INFORMATION => "JVMTI_ERROR_ABSENT_INFORMATION",
+        jvmtiError_JVMTI_ERROR_INVALID_EVENT_TYPE => "JVMTI_ERROR_INVALID_EVENT_TYPE",
+        jvmtiError_JVMTI_ERROR_ILLEGAL_ARGUMENT => "JVMTI_ERROR_ILLEGAL_ARGUMENT",
+        jvmtiError_JVMTI_ERROR_NATIVE_METHOD => "JVMTI_ERROR_NATIVE_METHOD",
+        jvmtiError_JVMTI_ERROR_OUT_OF_MEMORY => "JVMTI_ERROR_OUT_OF_MEMORY",
+        jvmtiError_JVMTI_ERROR_ACCESS_DENIED => "JVMTI_ERROR_ACCESS_DENIED",
+        jvmtiError_JVMTI_ERROR_WRONG_PHASE => "JVMTI_ERROR_WRONG_PHASE",
+        jvmtiError_JVMTI_ERROR_INTERNAL => "JVMTI_ERROR_INTERNAL",
+        jvmtiError_JVMTI_ERROR_UNATTACHED_THREAD => "JVMTI_ERROR_UNATTACHED_THREAD",
+        jvmtiError_JVMTI_ERROR_INVALID_ENVIRONMENT => "JVMTI_ERROR_INVALID_ENVIRONMENT",
+        _ => "unknown jvmti error",
+    }
+}
+
+macro_rules! capabilities {
+    ($($name:ident => $set:ident),* $(,)?) => {
+        /// The optional features of a [`JvmtiEnv`], each field is one of the `can_*` bits of `jvmtiCapabilities`
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct Capabilities {
+            $(pub $name : bool,)*
+        }
+
+        impl Capabilities {
+            pub fn new() -> Self {
+                Self::default()
+            }
+            /// every capability turned on, pass to [`JvmtiEnv::add_available_capabilities`] to get all that can be had
+            pub fn all() -> Self {
+                Self { $($name : true,)* }
+            }
+            /// the capabilities set in both
+            pub fn intersection(&self, other : &Self) -> Self {
+                Self { $($name : self.$name && other.$name,)* }
+            }
+            /// the capabilities set in either
+            pub fn union(&self, other : &Self) -> Self {
+                Self { $($name : self.$name || other.$name,)* }
+            }
+            /// true when every capability set in `other` is also set here
+            pub fn contains(&self, other : &Self) -> bool {
+                self.union(other) == *self
+            }
+            fn zeroed() -> jvmtiCapabilities {
+                // a plain bitfield, all zero is "no capabilities"
+                unsafe { std::mem::zeroed() }
+            }
+            pub fn from_raw(raw : &jvmtiCapabilities) -> Self {
+                Self { $($name : raw.$name() != 0,)* }
+            }
+            pub fn to_raw(&self) -> jvmtiCapabilities {
+                let mut raw = Self::zeroed();
+                $(raw.$set(self.$name as _);)*
+                raw
+            }
+        }
+    };
+}
+
+capabilities! {
+    can_tag_objects => set_can_tag_objects,
+    can_generate_field_modification_events => set_can_generate_field_modification_events,
+    can_generate_field_access_events => set_can_generate_field_access_events,
+    can_get_bytecodes => set_can_get_bytecodes,
+    can_get_synthetic_attribute => set_can_get_synthetic_attribute,
+    can_get_owned_monitor_info => set_can_get_owned_monitor_info,
+    can_get_current_contended_monitor => set_can_get_current_contended_monitor,
+    can_get_monitor_info => set_can_get_monitor_info,
+    can_pop_frame => set_can_pop_frame,
+    can_redefine_classes => set_can_redefine_classes,
+    can_signal_thread => set_can_signal_thread,
+    can_get_source_file_name => set_can_get_source_file_name,
+    can_get_line_numbers => set_can_get_line_numbers,
+    can_get_source_debug_extension => set_can_get_source_debug_extension,
+    can_access_local_variables => set_can_access_local_variables,
+    can_maintain_original_method_order => set_can_maintain_original_method_order,
+    can_generate_single_step_events => set_can_generate_single_step_events,
+    can_generate_exception_events => set_can_generate_exception_events,
+    can_generate_frame_pop_events => set_can_generate_frame_pop_events,
+    can_generate_breakpoint_events => set_can_generate_breakpoint_events,
+    can_suspend => set_can_suspend,
+    can_redefine_any_class => set_can_redefine_any_class,
+    can_get_current_thread_cpu_time => set_can_get_current_thread_cpu_time,
+    can_get_thread_cpu_time => set_can_get_thread_cpu_time,
+    can_generate_method_entry_events => set_can_generate_method_entry_events,
+    can_generate_method_exit_events => set_can_generate_method_exit_events,
+    can_generate_all_class_hook_events => set_can_generate_all_class_hook_events,
+    can_generate_compiled_method_load_events => set_can_generate_compiled_method_load_events,
+    can_generate_monitor_events => set_can_generate_monitor_events,
+    can_generate_vm_object_alloc_events => set_can_generate_vm_object_alloc_events,
+    can_generate_native_method_bind_events => set_can_generate_native_method_bind_events,
+    can_generate_garbage_collection_events => set_can_generate_garbage_collection_events,
+    can_generate_object_free_events => set_can_generate_object_free_events,
+    can_force_early_return => set_can_force_early_return,
+    can_get_owned_monitor_stack_depth_info => set_can_get_owned_monitor_stack_depth_info,
+    can_get_constant_pool => set_can_get_constant_pool,
+    can_set_native_method_prefix => set_can_set_native_method_prefix,
+    can_retransform_classes => set_can_retransform_classes,
+    can_retransform_any_class => set_can_retransform_any_class,
+    can_generate_resource_exhaustion_heap_events => set_can_generate_resource_exhaustion_heap_events,
+    can_generate_resource_exhaustion_threads_events => set_can_generate_resource_exhaustion_threads_events,
+    can_generate_early_vmstart => set_can_generate_early_vmstart,
+    can_generate_early_class_hook_events => set_can_generate_early_class_hook_events,
+    can_generate_sampled_object_alloc_events => set_can_generate_sampled_object_alloc_events,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::tests::{attach, vm};
+
+    #[test]
+    fn capability_sets() {
+        let tags = Capabilities { can_tag_objects : true, ..Default::default() };
+        let both = tags.union(&Capabilities { can_suspend : true, ..Default::default() });
+        assert!(both.contains(&tags) && !tags.contains(&both));
+        assert_eq!(both.intersection(&tags), tags);
+        assert_eq!(Capabilities::from_raw(&both.to_raw()), both);
+        assert_eq!(Capabilities::from_raw(&Capabilities::all().to_raw()), Capabilities::all());
+        assert_eq!(JniError::Jvmti(jdk_sys::jvmtiError_JVMTI_ERROR_NOT_FOUND).to_string(), "jvmti call failed with JVMTI_ERROR_NOT_FOUND (41)");
+        assert_eq!(error_name(1234), "unknown jvmti error");
+        let signature = |s : &str| ClassSignature { signature : s.to_string(), generic : None };
+        assert_eq!(signature("Ljava/lang/String;").internal_name(), "java/lang/String");
+        assert_eq!(signature("[Ljava/lang/String;").internal_name(), "[Ljava/lang/String;");
+    }
+
+    #[test]
+    fn introspection() {
+        let env = attach();
+        let jvmti = JvmtiEnv::from_vm(&vm()).unwrap();
+        let count = env.with_local_frame(16, |env| Ok(jvmti.loaded_classes(env)?.len())).unwrap();
+        assert!(count > 100);
+
+        let list = jvmti.loaded_class(&env, "java.util.List").unwrap();
+        let signature = jvmti.class_signature(&list).unwrap();
+        assert_eq!((signature.internal_name(), signature.generic.as_deref()), ("java/util/List", Some("<E:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/Collection<TE;>;")));
+        assert!(matches!(jvmti.loaded_class(&env, "does/not/Exist"), Err(JniError::ClassNotFound(name)) if name == "does/not/Exist"));
+
+        let string = env.find_class("java/lang/String").unwrap();
+        let length = jvmti.class_methods(&string).unwrap().into_iter().find(|m| jvmti.method_name(*m).unwrap().name == "length").unwrap();
+        assert_eq!(jvmti.method_name(length).unwrap(), MemberName { name : "length".into(), signature : "()I".into(), generic : None });
+        assert_eq!(jvmti.method_modifiers(length).unwrap() & 0x0001, 0x0001);
+        let declaring = jvmti.method_declaring_class(&env, length).unwrap();
+        assert_eq!(unchecked_jnic!(env.ptr,IsSameObject, declaring.ptr, string.ptr), jdk_sys::JNI_TRUE as u8);
+        let fields = jvmti.class_fields(&string).unwrap().into_iter().map(|f| jvmti.field_name(&string, f).unwrap().name).collect::<Vec<_>>();
+        assert!(fields.iter().any(|f| f == "hash"));
+        jvmti.dispose().unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, sync::{Arc, RwLock}};
+
+use jdk_sys::{jboolean, jclass, jint, JNI_FALSE};
+
+use crate::{env::Jenv, object::JObject, class::JClass, error::JniError};
+
+use super::{check, JvmtiEnv};
+
+/// A class file the jvm is about to parse, see [`JvmtiEnv::add_transformer`]
+pub struct ClassFileLoad<'a> {
+    /// internal name as the jvm sees it, eg. `java/lang/String` or the obfuscated `dyr`
+    pub name : &'a str,
+    /// null for the bootstrap loader
+    pub loader : JObject<'a>,
+    pub protection_domain : JObject<'a>,
+    /// set when the class is being retransformed (or redefined), `None` when it is loaded for the first time
+    pub class_being_redefined : Option<JClass<'a>>,
+}
+
+/// gets the current bytes of a class, returns the new ones or `None` to pass them on untouched
+pub type Transformer = dyn for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync;
+
+/// class name -> transformers, in the order they were added
+type Transformers = BTreeMap<String, Vec<Arc<Transformer>>>;
+
+/// the transformers of every environment
+static TRANSFORMERS : RwLock<Vec<(usize, Transformers)>> = RwLock::new(Vec::new());
+
+impl JvmtiEnv {
+    /// registers `f` for the class with the internal name `class` (as the jvm sees it, so the obfuscated name for minecraft classes).
+    /// transformers of the same class run in the order they were added, each getting the bytes the previous one returned.
+    /// classes that are already loaded are only affected once they are retransformed, see [`Self::retransform_transformed`].
+    /// this takes over the [`Self::on_class_file_load`] handler of the environment
+    pub fn add_transformer(&self, class : &str, f : impl for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static) -> Result<(),JniError> {
+        let first = {
+            let mut lock = TRANSFORMERS.write().unwrap();
+            let key = self.ptr as usize;
+            let index = match lock.iter().position(|(env, _)| *env == key) {
+                Some(index) => index,
+                None => {
+                    lock.push((key, BTreeMap::new()));
+                    lock.len() - 1
+                }
+            };
+            let transformers = &mut lock[index].1;
+            let first = transformers.is_empty();
+            transformers.entry(class.replace('.', "/")).or_default().push(Arc::new(f));
+            first
+        };
+        if first {
+            let key = self.ptr as usize;
+            self.on_class_file_load_where(move |name| wanted(key, name), move |env, class, bytes| run(key, env, class, bytes))?;
+        }
+        Ok(())
+    }
+    /// drops every transformer of `class`, already transformed classes keep their bytes until they are retransformed
+    pub fn remove_transformers(&self, class : &str) {
+        let mut lock = TRANSFORMERS.write().unwrap();
+        if let Some((_, transformers)) = lock.iter_mut().find(|(env, _)| *env == self.ptr as usize) {
+            transformers.remove(&class.replace('.', "/"));
+        }
+    }
+    /// true when at least one transformer is registered for `class`
+    pub fn has_transformers(&self, class : &str) -> bool {
+        let lock = TRANSFORMERS.read().unwrap();
+        lock.iter().any(|(env, transformers)| *env == self.ptr as usize && transformers.contains_key(&class.replace('.', "/")))
+    }
+
+    /// `IsModifiableClass`, false for primitives, arrays and some of the jdk's own classes
+    pub fn is_modifiable_class(&self, class : &JClass) -> Result<bool,JniError> {
+        let mut modifiable : jboolean = 0;
+        check(jvmti_call!(self.ptr,IsModifiableClass, class.ptr, &mut modifiable))?;
+        Ok(modifiable != JNI_FALSE as jboolean)
+    }
+    /// `RetransformClasses`, runs the class file load hook (and so the transformers) again on classes that are already loaded.
+    /// the hook gets the original class bytes, so dropping a transformer and retransforming undoes it.
+    /// needs `can_retransform_classes`, without it the environment does not see retransforms at all
+    pub fn retransform_classes(&self, classes : &[JClass]) -> Result<(),JniError> {
+        if classes.is_empty() {
+            return Ok(());
+        }
+        let raw = classes.iter().map(|c| c.ptr).collect::<Vec<jclass>>();
+        check(jvmti_call!(self.ptr,RetransformClasses, raw.len() as jint, raw.as_ptr()))
+    }
+    /// retransforms every loaded class that has a transformer registered, returns how many there were
+    pub fn retransform_transformed(&self, env : &Jenv) -> Result<usize,JniError> {
+        // one local reference per loaded class, freed with the frame
+        env.with_local_frame(0, |env| {
+            let mut classes = vec![];
+            for class in self.loaded_classes(env)? {
+                let signature = self.class_signature(&class)?;
+                if self.has_transformers(signature.internal_name()) && self.is_modifiable_class(&class)? {
+                    classes.push(class);
+                }
+            }
+            self.retransform_classes(&classes)?;
+            Ok(classes.len())
+        })
+    }
+}
+
+/// drops the transformers of an environment that is being disposed
+pub(super) fn forget(env : *mut jdk_sys::jvmtiEnv) {
+    TRANSFORMERS.write().unwrap().retain(|(key, _)| *key != env as usize);
+}
+
+/// the class file load filter installed by [`JvmtiEnv::add_transformer`]
+fn wanted(key : usize, name : &str) -> bool {
+    let lock = TRANSFORMERS.read().unwrap();
+    lock.iter().any(|(env, transformers)| *env == key && transformers.contains_key(name))
+}
+
+/// the class file load handler installed by [`JvmtiEnv::add_transformer`]
+fn run<'e>(key : usize, env : &'e Jenv<'e>, class : &ClassFileLoad<'e>, bytes : &[u8]) -> Option<Vec<u8>> {
+    let transformers = {
+        let lock = TRANSFORMERS.read().unwrap();
+        let (_, transformers) = lock.iter().find(|(env, _)| *env == key)?;
+        transformers.get(class.name)?.clone()
+    };
+    let mut current : Option<Vec<u8>> = None;
+    for transformer in transformers {
+        if let Some(out) = transformer(env, class, current.as_deref().unwrap_or(bytes)) {
+            current = Some(out);
+        }
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{jarray::JArray, jvalue::JValue, vm::tests::{attach, vm}};
+
+    #[test]
+    fn transformers() {
+        const CLASS : &[u8] = include_bytes!("../../java/mci/RustProxyHandler.class");
+        // the hook runs on the defining thread, so loads by tests running next to this one are not counted
+        thread_local!(static CALLS : Cell<usize> = const { Cell::new(0) });
+        let env = attach();
+        let jvmti = JvmtiEnv::from_vm(&vm()).unwrap();
+        let key = jvmti.ptr as usize;
+        jvmti.add_transformer("mci/RustProxyHandler", |_, class, bytes| {
+            // a first load into a class loader, the proxy handler is also defined into the bootstrap loader by other tests
+            if class.class_being_redefined.is_none() && !class.loader.ptr.is_null() {
+                CALLS.with(|c| c.set(c.get() + 1));
+            }
+            // an identical copy, the next transformer gets it instead of the original
+            Some(bytes.to_vec())
+        }).unwrap();
+        jvmti.add_transformer("mci.RustProxyHandler", |_, _, bytes| {
+            assert_eq!(bytes, CLASS);
+            CALLS.with(|c| c.set(c.get() + 10));
+            None
+        }).unwrap();
+        assert!(jvmti.has_transformers("mci.RustProxyHandler") && wanted(key, "mci/RustProxyHandler"));
+        assert!(!jvmti.has_transformers("mci/Missing") && !wanted(key, "mci/Missing"));
+
+        let urls = JArray::<JObject>::create_object(&env, 0, &env.find_class("java/net/URL").unwrap(), None).unwrap();
+        let loader : JObject = env.find_class("java/net/URLClassLoader").unwrap().new_object("<init>", "([Ljava/net/URL;)V", &vec![JValue::from(&urls.ptr)]).unwrap();
+        let defined = env.define_class("mci/RustProxyHandler", Some(&loader), CLASS).unwrap();
+        assert_eq!(CALLS.with(Cell::get), 11);
+        assert!(jvmti.is_modifiable_class(&defined).unwrap());
+
+        jvmti.remove_transformers("mci/RustProxyHandler");
+        assert!(!jvmti.has_transformers("mci/RustProxyHandler"));
+        jvmti.add_transformer("mci/RustProxyHandler", |_, _, _| None).unwrap();
+        jvmti.dispose().unwrap();
+        // disposing forgets the transformers of the environment
+        assert!(!wanted(key, "mci/RustProxyHandler"));
+    }
+}
@@ -1,4 +1,6 @@
 #![feature(default_free_fn)]
+// lets `#[jni_native]` (which expands to `::jni::..` paths) be used inside this crate
+extern crate self as jni;
 pub mod jvalue;
 pub mod jarray;
 pub mod jstring;
@@ -6,14 +8,29 @@ pub mod object;
 pub mod class;
 pub mod env;
 pub mod macros;
+pub mod error;
+pub mod jthrowable;
+pub mod refs;
+pub mod cache;
+pub mod mutf8;
+pub mod vm;
+pub mod native;
+pub mod proxy;
+pub mod jvmti;
 
 
 pub mod prelude {
     pub use crate::env::Jenv;
     pub use crate::object::JObject;
     pub use crate::jvalue::JValue;
-    pub use crate::jarray::JArray;
+    pub use crate::jarray::{JArray, ArrayElement, JPrimitive};
     pub use crate::jstring::JString;
     pub use crate::class::JClass;
     pub use crate::object::JClassInstance;
+    pub use crate::error::JniError;
+    pub use crate::jthrowable::{JThrowable, ThrowableError};
+    pub use crate::refs::{GlobalRef, WeakRef, LocalRef, JRefTarget};
+    pub use crate::vm::{JavaVm, JavaVmOptions, AttachGuard};
+    pub use crate::native::{NativeMethod, jni_native};
+    pub use crate::jvmti::{JvmtiEnv, Capabilities};
 }
\ No newline at end of file
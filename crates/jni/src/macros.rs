@@ -9,10 +9,8 @@ macro_rules! unchecked_jnice {
     ($fr:expr,$fna:tt$(, $a:expr)*) => {
         (|| unsafe {
             let q = (*(*$fr)).$fna.unwrap_unchecked()($fr$(, $a)*);
-            if (*(*$fr)).ExceptionCheck.unwrap_unchecked()($fr) == jdk_sys::JNI_TRUE as u8 {
-                (*(*$fr)).ExceptionDescribe.unwrap_unchecked()($fr);
-                println!("{}:{} caused exception",std::file!(),std::line!());
-                return Err(());
+            if let Some(throwable) = $crate::jthrowable::JThrowable::catch($fr) {
+                return Err($crate::error::JniError::JavaException(throwable));
             }
             Ok(q)
         })()
@@ -0,0 +1,114 @@
+//! java's "modified utf-8", what the `*UTF*` jni functions speak.
+//! it differs from utf-8 in two ways, `\0` is written as `C0 80` and characters outside the bmp are written as
+//! a surrogate pair with each half encoded separately (6 bytes instead of 4).
+use std::borrow::Cow;
+
+use crate::error::JniError;
+
+/// encodes `string` as modified utf-8, borrowing when the bytes are already identical to the utf-8 ones
+pub fn encode(string:&str) -> Cow<'_,[u8]> {
+    if !string.chars().any(|c| c == '\0' || c as u32 > 0xFFFF) {
+        return Cow::Borrowed(string.as_bytes());
+    }
+    let mut out = Vec::with_capacity(string.len() + 8);
+    for c in string.chars() {
+        match c {
+            '\0' => out.extend_from_slice(&[0xC0, 0x80]),
+            c if c as u32 > 0xFFFF => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    push_three_bytes(&mut out, *unit);
+                }
+            }
+            c => out.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn push_three_bytes(out:&mut Vec<u8>, unit:u16) {
+    out.push(0xE0 | (unit >> 12) as u8);
+    out.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+    out.push(0x80 | (unit & 0x3F) as u8);
+}
+
+/// decodes modified utf-8 into the utf-16 units of the java string
+pub fn decode_utf16(bytes:&[u8]) -> Result<Vec<u16>,JniError> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    let cont = |idx:usize| -> Result<u16,JniError> {
+        match bytes.get(idx) {
+            Some(b) if b & 0xC0 == 0x80 => Ok((b & 0x3F) as u16),
+            _ => Err(JniError::InvalidUtf(format!("bad continuation byte at {idx}"))),
+        }
+    };
+    while idx < bytes.len() {
+        let b = bytes[idx];
+        if b & 0x80 == 0 {
+            units.push(b as u16);
+            idx += 1;
+        } else if b & 0xE0 == 0xC0 {
+            units.push(((b & 0x1F) as u16) << 6 | cont(idx + 1)?);
+            idx += 2;
+        } else if b & 0xF0 == 0xE0 {
+            units.push(((b & 0x0F) as u16) << 12 | cont(idx + 1)? << 6 | cont(idx + 2)?);
+            idx += 3;
+        } else {
+            return Err(JniError::InvalidUtf(format!("unexpected byte {b:#x} at {idx}")));
+        }
+    }
+    Ok(units)
+}
+
+/// decodes modified utf-8, borrowing when the bytes are plain utf-8
+pub fn decode(bytes:&[u8]) -> Result<Cow<'_,str>,JniError> {
+    // `C0` and `ED` only start the sequences that differ from utf-8
+    if !bytes.iter().any(|b| *b == 0xC0 || *b == 0xED) {
+        if let Ok(string) = std::str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(string));
+        }
+    }
+    String::from_utf16(&decode_utf16(bytes)?)
+        .map(Cow::Owned)
+        .map_err(|e| JniError::InvalidUtf(e.to_string()))
+}
+
+/// like [`decode`] but replaces anything invalid (eg. unpaired surrogates) with `U+FFFD`
+pub fn decode_lossy(bytes:&[u8]) -> Cow<'_,str> {
+    match decode(bytes) {
+        Ok(string) => string,
+        Err(_) => match decode_utf16(bytes) {
+            Ok(units) => Cow::Owned(String::from_utf16_lossy(&units)),
+            Err(_) => String::from_utf8_lossy(bytes),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for s in ["plain", "nul\0inside", "é and ✓", "chat 😀 emoji", ""] {
+            let encoded = encode(s);
+            assert!(!encoded.contains(&0), "{s:?} encoded with a raw nul");
+            assert_eq!(decode(&encoded).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn java_encoding() {
+        assert_eq!(&*encode("\0"), &[0xC0, 0x80]);
+        // U+1F600 is the surrogate pair D83D DE00
+        assert_eq!(&*encode("😀"), &[0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+        assert!(matches!(encode("abc"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn unpaired_surrogate() {
+        let bytes = [0xED, 0xA0, 0xBD];
+        assert!(decode(&bytes).is_err());
+        assert_eq!(decode_lossy(&bytes), "\u{FFFD}");
+    }
+}
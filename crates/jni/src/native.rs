@@ -0,0 +1,163 @@
+use std::{any::Any, ffi::{c_void, CString}, panic::{self, AssertUnwindSafe}};
+
+pub use jdk_sys::{JNIEnv, jboolean, jbyte, jchar, jshort, jint, jlong, jfloat, jdouble, jobject};
+pub use jni_proc::jni_native;
+
+use jdk_sys::{jclass, JNINativeMethod, JNI_OK, JNI_TRUE, JNI_FALSE};
+
+use crate::{env::Jenv, class::JClass, object::{JObject, JClassInstance}, jthrowable::{ExceptionDefault, ThrowableError}, error::JniError, unchecked_jnice};
+
+/// A rust function which backs a java `native` method, usually made by [`jni_native`]. pass them to [`JClass::register_natives`].
+#[derive(Debug, Clone, Copy)]
+pub struct NativeMethod {
+    pub name : &'static str,
+    /// jni descriptor, eg. `(ILjava/lang/String;)V`
+    pub sig : &'static str,
+    /// an `extern "system"` function taking the env, `this` / the class and then the raw arguments
+    pub fn_ptr : *mut c_void,
+}
+
+unsafe impl Send for NativeMethod {}
+unsafe impl Sync for NativeMethod {}
+
+impl<'a> JClass<'a> {
+    /// `RegisterNatives`, binds the `native` methods of this class to rust functions
+    pub fn register_natives(&self, methods:&[NativeMethod]) -> Result<(),JniError> {
+        let strings = methods.iter()
+            .map(|m| Ok((CString::new(m.name)?, CString::new(m.sig)?)))
+            .collect::<Result<Vec<_>,JniError>>()?;
+        let raw = methods.iter().zip(&strings).map(|(m, (name, sig))| JNINativeMethod {
+            name : name.as_ptr() as *mut _,
+            signature : sig.as_ptr() as *mut _,
+            fnPtr : m.fn_ptr,
+        }).collect::<Vec<_>>();
+        let status = unchecked_jnice!(self.env.ptr,RegisterNatives, self.ptr, raw.as_ptr(), raw.len() as jint)?;
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        Ok(())
+    }
+    /// `UnregisterNatives`, the `native` methods go back to being unlinked
+    pub fn unregister_natives(&self) -> Result<(),JniError> {
+        let status = unchecked_jnice!(self.env.ptr,UnregisterNatives, self.ptr)?;
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        Ok(())
+    }
+}
+
+/// a native method argument, built from the raw jni value
+pub trait FromNative<'a> : Sized {
+    type Raw;
+    fn from_native(raw:Self::Raw, env:&'a Jenv<'a>) -> Self;
+}
+
+/// a native method return value, turned into the raw jni value
+pub trait IntoNative {
+    type Raw : ExceptionDefault;
+    fn into_native(self, env:&Jenv) -> Self::Raw;
+}
+
+/// body of every [`jni_native`] trampoline. a panic is turned into a `java.lang.Error` instead of unwinding into the jvm
+pub fn run_native<'a, R : IntoNative>(env:&'a Jenv<'a>, f:impl FnOnce(&'a Jenv<'a>) -> R) -> R::Raw {
+    match panic::catch_unwind(AssertUnwindSafe(|| f(env).into_native(env))) {
+        Ok(raw) => raw,
+        Err(payload) => {
+            let _ = env.throw_new("java/lang/Error", &format!("rust panicked: {}", panic_message(&payload)));
+            R::Raw::exception_default()
+        }
+    }
+}
+
+fn panic_message(payload:&Box<dyn Any + Send>) -> &str {
+    payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("<non string panic>")
+}
+
+impl<'a, T : From<JObject<'a>> + JClassInstance> FromNative<'a> for T {
+    type Raw = jobject;
+    fn from_native(raw:Self::Raw, env:&'a Jenv<'a>) -> Self {
+        T::from(if raw.is_null() { JObject::null(env) } else { JObject::new(raw, env) })
+    }
+}
+impl<'a> FromNative<'a> for JClass<'a> {
+    type Raw = jclass;
+    fn from_native(raw:Self::Raw, env:&'a Jenv<'a>) -> Self {
+        JClass::new(raw, env)
+    }
+}
+impl<'a> FromNative<'a> for bool {
+    type Raw = jboolean;
+    fn from_native(raw:Self::Raw, _:&'a Jenv<'a>) -> Self {
+        raw != JNI_FALSE as jboolean
+    }
+}
+impl<'a> FromNative<'a> for char {
+    type Raw = jchar;
+    fn from_native(raw:Self::Raw, _:&'a Jenv<'a>) -> Self {
+        char::from_u32(raw as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+impl<T : JClassInstance> IntoNative for T {
+    type Raw = jobject;
+    fn into_native(self, _:&Jenv) -> Self::Raw {
+        self.get_jobject().ptr
+    }
+}
+impl IntoNative for JClass<'_> {
+    type Raw = jobject;
+    fn into_native(self, _:&Jenv) -> Self::Raw {
+        self.ptr
+    }
+}
+impl IntoNative for () {
+    type Raw = ();
+    fn into_native(self, _:&Jenv) -> Self::Raw {}
+}
+impl IntoNative for bool {
+    type Raw = jboolean;
+    fn into_native(self, _:&Jenv) -> Self::Raw {
+        (if self { JNI_TRUE } else { JNI_FALSE }) as jboolean
+    }
+}
+impl IntoNative for char {
+    type Raw = jchar;
+    /// characters outside the bmp do not fit a java `char` and become `U+FFFD`
+    fn into_native(self, _:&Jenv) -> Self::Raw {
+        u16::try_from(self as u32).unwrap_or(0xFFFD)
+    }
+}
+/// errors are thrown into java, see [`ThrowableError`]
+impl<T : IntoNative, E : ThrowableError> IntoNative for Result<T,E> {
+    type Raw = T::Raw;
+    fn into_native(self, env:&Jenv) -> Self::Raw {
+        match self {
+            Ok(value) => value.into_native(env),
+            Err(e) => {
+                e.throw_into(env);
+                T::Raw::exception_default()
+            }
+        }
+    }
+}
+
+macro_rules! same_repr {
+    ($($t:ty),*) => {
+        $(impl<'a> FromNative<'a> for $t {
+            type Raw = $t;
+            fn from_native(raw:Self::Raw, _:&'a Jenv<'a>) -> Self {
+                raw
+            }
+        }
+        impl IntoNative for $t {
+            type Raw = $t;
+            fn into_native(self, _:&Jenv) -> Self::Raw {
+                self
+            }
+        })*
+    };
+}
+same_repr!(i8, i16, i32, i64, f32, f64);
@@ -0,0 +1,188 @@
+use std::sync::RwLock;
+
+use crate::{env::Jenv, class::JClass, object::JObject, jarray::JArray, jvalue::JValue, error::JniError, refs::GlobalRef, vm::JavaVm, jstring::JString, unchecked_jnic};
+use crate::native::jni_native;
+
+/// `mci/RustProxyHandler`, an `InvocationHandler` forwarding to the closure behind its `ptr` field. source is in `java/mci`
+const HANDLER_CLASS : &str = "mci/RustProxyHandler";
+const HANDLER_BYTES : &[u8] = include_bytes!("../java/mci/RustProxyHandler.class");
+
+/// the handler class defined on each vm, same scheme as the class cache
+static HANDLER : RwLock<Option<(usize, GlobalRef<JObject<'static>>)>> = RwLock::new(None);
+
+/// A call made on a proxy. `object` methods (`hashCode`, `equals`, `toString`) are answered in java and never show up here.
+pub struct Invocation<'a> {
+    pub proxy : JObject<'a>,
+    /// the `java.lang.reflect.Method` being called
+    pub method : JObject<'a>,
+    pub method_name : String,
+    /// primitive arguments arrive boxed (`java.lang.Integer`, ...)
+    pub args : Vec<JObject<'a>>,
+}
+
+/// What a proxy calls. return [`JObject::null`] for `void` methods, primitive results must be boxed.
+/// errors are thrown into java, anything other than a `RuntimeException` reaches the caller wrapped in an `UndeclaredThrowableException`.
+pub type ProxyHandler = dyn for<'e> Fn(&'e Jenv<'e>, Invocation<'e>) -> Result<JObject<'e>,JniError> + Send + Sync;
+
+impl Jenv<'_> {
+    /// `java.lang.reflect.Proxy::newProxyInstance`, an object implementing every interface in `interfaces` which calls `handler`.
+    /// the proxy is created in the class loader of the first interface, the handler is dropped once java collects the proxy.
+    pub fn new_proxy<'b, F>(&'b self, interfaces:&[JClass], handler:F) -> Result<JObject<'b>,JniError>
+        where F : for<'e> Fn(&'e Jenv<'e>, Invocation<'e>) -> Result<JObject<'e>,JniError> + Send + Sync + 'static
+    {
+        let first = interfaces.first().ok_or(JniError::IndexOutOfBounds { index: 0, length: 0 })?;
+        let handler_class = handler_class(self)?;
+
+        let boxed : Box<Box<ProxyHandler>> = Box::new(Box::new(handler));
+        let ptr = Box::into_raw(boxed);
+        let handler_obj = JClass::new(handler_class.as_raw(), self)
+            .new_object::<JObject>("<init>", "(J)V", &vec![JValue::from(ptr as i64)])
+            // the java side never got the pointer so nothing else will free it
+            .inspect_err(|_| drop(unsafe { Box::from_raw(ptr) }))?;
+
+        let class_class = self.find_class_cached("java/lang/Class")?;
+        let array = JArray::<JObject>::create_object(self, interfaces.len(), &class_class, None)?;
+        for (idx, interface) in interfaces.iter().enumerate() {
+            array.set(idx, JObject::new(interface.ptr, self))?;
+        }
+        // interfaces from the jdk itself have no loader (null), which `newProxyInstance` accepts
+        let loader = match JObject::new(first.ptr, self).call_object_method::<JObject>("getClassLoader", "()Ljava/lang/ClassLoader;", &vec![]) {
+            Err(JniError::NullReturn) => JObject::null(self),
+            loader => loader?,
+        };
+
+        self.find_class_cached("java/lang/reflect/Proxy")?.call_static_object_method(
+            "newProxyInstance",
+            "(Ljava/lang/ClassLoader;[Ljava/lang/Class;Ljava/lang/reflect/InvocationHandler;)Ljava/lang/Object;",
+            &vec![JValue::from(&loader), JValue::from(&array.ptr), JValue::from(&handler_obj)],
+        )
+    }
+    /// drops the handler of a proxy made by [`Self::new_proxy`] now rather than once java collects it, later calls on the proxy throw.
+    /// other proxies fail with [`JniError::MethodNotFound`]
+    pub fn close_proxy(&self, proxy:&JObject) -> Result<(),JniError> {
+        let handler = self.find_class_cached("java/lang/reflect/Proxy")?.call_static_object_method::<JObject>(
+            "getInvocationHandler",
+            "(Ljava/lang/Object;)Ljava/lang/reflect/InvocationHandler;",
+            &vec![JValue::from(proxy)],
+        )?;
+        handler.call_void_method("close", "()V", &vec![])
+    }
+    /// a `java.lang.Runnable` calling `f`
+    pub fn new_runnable<'b>(&'b self, f:impl for<'e> Fn(&'e Jenv<'e>) -> Result<(),JniError> + Send + Sync + 'static) -> Result<JObject<'b>,JniError> {
+        let runnable = self.find_class_cached("java/lang/Runnable")?;
+        self.new_proxy(&[runnable], move |env, _| {
+            f(env)?;
+            Ok(JObject::null(env))
+        })
+    }
+}
+
+fn handler_class(env:&Jenv) -> Result<GlobalRef<JObject<'static>>,JniError> {
+    let vm = JavaVm::from_env(env)?.as_raw() as usize;
+    if let Some((cached_vm, class)) = HANDLER.read().unwrap().as_ref() {
+        if *cached_vm == vm {
+            return Ok(class.clone());
+        }
+    }
+    let mut lock = HANDLER.write().unwrap();
+    // defined in the bootstrap loader, it only refers to java.* classes
+    let local = match env.define_class(HANDLER_CLASS, None, HANDLER_BYTES) {
+        Ok(class) => class,
+        // already defined by an earlier load of this library
+        Err(JniError::JavaException(t)) if t.class_name == "java.lang.LinkageError" => env.find_class(HANDLER_CLASS)?,
+        Err(e) => return Err(e),
+    };
+    local.register_natives(&[INVOKE_NATIVE, RELEASE_NATIVE])?;
+    let global = GlobalRef::from_raw(env, local.ptr)?;
+    unchecked_jnic!(env.ptr,DeleteLocalRef, local.ptr);
+    *lock = Some((vm, global.clone()));
+    Ok(global)
+}
+
+#[jni_native(name = "invoke0", sig = "(JLjava/lang/Object;Ljava/lang/reflect/Method;[Ljava/lang/Object;)Ljava/lang/Object;")]
+fn invoke<'a>(env:&'a Jenv<'a>, _class:JClass<'a>, ptr:i64, proxy:JObject<'a>, method:JObject<'a>, args:JArray<'a,JObject<'a>>) -> Result<JObject<'a>,JniError> {
+    let handler = unsafe { &*(ptr as *const Box<ProxyHandler>) };
+    let method_name = method.call_object_method::<JString>("getName", "()Ljava/lang/String;", &vec![])?.to_rust_string()?;
+    handler(env, Invocation {
+        proxy,
+        method,
+        method_name,
+        args : args.get_all()?,
+    })
+}
+
+#[jni_native(name = "release0")]
+fn release(_env:&Jenv, _class:JClass, ptr:i64) {
+    drop(unsafe { Box::from_raw(ptr as *mut Box<ProxyHandler>) });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+
+    use super::*;
+    use crate::vm::tests::attach;
+
+    fn thrown<T>(result : Result<T,JniError>) -> String {
+        match result {
+            Err(JniError::JavaException(t)) => t.class_name,
+            _ => panic!("nothing was thrown"),
+        }
+    }
+
+    #[test]
+    fn calls() {
+        let env = attach();
+        let function = env.find_class("java/util/function/Function").unwrap();
+        let proxy = env.new_proxy(&[function], |env, invocation| {
+            assert_eq!(invocation.method_name, "apply");
+            match JString::from(invocation.args[0].clone()).to_rust_string()?.as_str() {
+                "err" => Err(JniError::NullReturn),
+                "panic" => panic!("asked to"),
+                arg => Ok(JString::from_str(&arg.to_uppercase(), env)?.obj),
+            }
+        }).unwrap();
+        let apply = |arg : &str| proxy.call_object_method::<JString>("apply", "(Ljava/lang/Object;)Ljava/lang/Object;", &vec![JValue::from(&JString::new(arg, &env).obj)]);
+        assert_eq!(apply("abc").unwrap().to_rust_string().unwrap(), "ABC");
+        assert_eq!(thrown(apply("err")), "java.lang.NullPointerException");
+        assert_eq!(thrown(apply("panic")), "java.lang.Error");
+        // object methods never reach the closure
+        let name = proxy.call_object_method::<JString>("toString", "()Ljava/lang/String;", &vec![]).unwrap().to_rust_string().unwrap();
+        assert!(name.starts_with("RustProxy@"));
+
+        env.close_proxy(&proxy).unwrap();
+        assert_eq!(thrown(apply("abc")), "java.lang.IllegalStateException");
+        // closing twice does nothing
+        env.close_proxy(&proxy).unwrap();
+    }
+
+    #[test]
+    fn released() {
+        let env = attach();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = runs.clone();
+        let runnable = env.new_runnable(move |_| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }).unwrap();
+        runnable.call_void_method("run", "()V", &vec![]).unwrap();
+        runnable.call_void_method("run", "()V", &vec![]).unwrap();
+        assert_eq!((runs.load(Ordering::SeqCst), Arc::strong_count(&runs)), (2, 2));
+        env.close_proxy(&runnable).unwrap();
+        assert_eq!(Arc::strong_count(&runs), 1);
+
+        // a closure closing its own proxy keeps running, it is released once the call returns
+        let held = Arc::new(());
+        let inside = held.clone();
+        let class = env.find_class("java/lang/Runnable").unwrap();
+        let proxy = env.new_proxy(&[class], move |env, invocation| {
+            env.close_proxy(&invocation.proxy)?;
+            assert_eq!(Arc::strong_count(&inside), 2);
+            Ok(JObject::null(env))
+        }).unwrap();
+        proxy.call_void_method("run", "()V", &vec![]).unwrap();
+        assert_eq!(Arc::strong_count(&held), 1);
+        assert_eq!(thrown(proxy.call_void_method("run", "()V", &vec![])), "java.lang.IllegalStateException");
+    }
+}
@@ -0,0 +1,245 @@
+use std::{fmt, marker::PhantomData, ops::{Deref, DerefMut}, ptr};
+
+use jdk_sys::{jobject, JNIEnv};
+
+use crate::{env::Jenv, error::JniError, object::{JObject, JClassInstance}, vm::JavaVm, unchecked_jnic};
+
+/// Wrapper types which can be stored behind a [`GlobalRef`] / [`WeakRef`] and handed back out bound to whatever env is current.
+/// Implement it on the `'static` form of the wrapper, eg. `GlobalRef<MinecraftClient<'static>>`.
+pub trait JRefTarget {
+    type Bound<'a> : From<JObject<'a>> + JClassInstance;
+}
+
+impl JRefTarget for JObject<'_> {
+    type Bound<'a> = JObject<'a>;
+}
+
+/// runs `f` with an env for the current thread, attaching (and detaching afterwards) if the thread is not attached yet.
+/// used by the drop impls since references may be dropped on any thread.
+fn with_thread_env<R>(vm : JavaVm, f : impl FnOnce(*mut JNIEnv) -> R) -> Result<R,JniError> {
+    vm.attach_current_thread().map(|guard| f(guard.ptr))
+}
+
+/// A global reference, keeps the object alive until dropped and can be used from any thread and any frame.
+pub struct GlobalRef<T : JRefTarget> {
+    ptr : jobject,
+    vm : JavaVm,
+    phantom_type : PhantomData<fn() -> T>,
+}
+
+unsafe impl<T : JRefTarget> Send for GlobalRef<T> {}
+unsafe impl<T : JRefTarget> Sync for GlobalRef<T> {}
+
+impl<T : JRefTarget> GlobalRef<T> {
+    pub fn new(obj : &T::Bound<'_>) -> Result<Self,JniError> {
+        Self::from_raw(obj.get_jobject().env, obj.get_jobject().ptr)
+    }
+    pub fn from_raw(env : &Jenv, obj : jobject) -> Result<Self,JniError> {
+        if obj.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        let vm = JavaVm::from_env(env)?;
+        let ptr = unchecked_jnic!(env.ptr,NewGlobalRef, obj);
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self {
+            ptr,
+            vm,
+            phantom_type : PhantomData,
+        })
+    }
+    /// the object bound to `env`, the returned value borrows the global reference so it is not deleted while in use.
+    /// no local reference is created unless the object's class is asked for
+    pub fn get<'a>(&'a self, env : &'a Jenv<'a>) -> T::Bound<'a> {
+        <T::Bound<'a>>::from(JObject::new(self.ptr, env))
+    }
+    pub fn as_raw(&self) -> jobject {
+        self.ptr
+    }
+    pub fn downgrade(&self, env : &Jenv) -> Result<WeakRef<T>,JniError> {
+        WeakRef::from_raw(env, self.ptr)
+    }
+    /// a second global reference to the same object, fails if the thread can't be attached or the jvm is out of global references
+    pub fn try_clone(&self) -> Result<Self,JniError> {
+        let ptr = with_thread_env(self.vm, |env| unchecked_jnic!(env,NewGlobalRef, self.ptr))?;
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self {
+            ptr,
+            vm : self.vm,
+            phantom_type : PhantomData,
+        })
+    }
+}
+
+impl<T : JRefTarget> Clone for GlobalRef<T> {
+    /// panics when the jvm can't create another global reference, see [`GlobalRef::try_clone`]
+    fn clone(&self) -> Self {
+        self.try_clone().expect("unable to create a global reference")
+    }
+}
+
+impl<T : JRefTarget> fmt::Debug for GlobalRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GlobalRef").field(&self.ptr).finish()
+    }
+}
+
+impl<T : JRefTarget> Drop for GlobalRef<T> {
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+        let _ = with_thread_env(self.vm, |env| unchecked_jnic!(env,DeleteGlobalRef, self.ptr));
+    }
+}
+
+/// A weak global reference, does not keep the object alive. use [`WeakRef::upgrade`] to get at the object.
+pub struct WeakRef<T : JRefTarget> {
+    ptr : jobject,
+    vm : JavaVm,
+    phantom_type : PhantomData<fn() -> T>,
+}
+
+unsafe impl<T : JRefTarget> Send for WeakRef<T> {}
+unsafe impl<T : JRefTarget> Sync for WeakRef<T> {}
+
+impl<T : JRefTarget> WeakRef<T> {
+    pub fn new(obj : &T::Bound<'_>) -> Result<Self,JniError> {
+        Self::from_raw(obj.get_jobject().env, obj.get_jobject().ptr)
+    }
+    pub fn from_raw(env : &Jenv, obj : jobject) -> Result<Self,JniError> {
+        if obj.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        let vm = JavaVm::from_env(env)?;
+        let ptr = unchecked_jnic!(env.ptr,NewWeakGlobalRef, obj);
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self {
+            ptr,
+            vm,
+            phantom_type : PhantomData,
+        })
+    }
+    /// `None` once the object has been garbage collected
+    pub fn upgrade(&self, env : &Jenv) -> Option<GlobalRef<T>> {
+        let ptr = unchecked_jnic!(env.ptr,NewGlobalRef, self.ptr);
+        if ptr.is_null() {
+            return None;
+        }
+        Some(GlobalRef {
+            ptr,
+            vm : self.vm,
+            phantom_type : PhantomData,
+        })
+    }
+    pub fn is_collected(&self, env : &Jenv) -> bool {
+        unchecked_jnic!(env.ptr,IsSameObject, self.ptr, ptr::null_mut()) == jdk_sys::JNI_TRUE as u8
+    }
+}
+
+impl<T : JRefTarget> Drop for WeakRef<T> {
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+        let _ = with_thread_env(self.vm, |env| unchecked_jnic!(env,DeleteWeakGlobalRef, self.ptr));
+    }
+}
+
+/// Owns a local reference and deletes it (and the cached class reference of the object) when dropped.
+/// Copies taken out of it with `clone` / [`JClassInstance::get_jobject`] are only valid while it is alive.
+pub struct LocalRef<T : JClassInstance> {
+    inner : Option<T>,
+}
+
+impl<T : JClassInstance> LocalRef<T> {
+    pub fn new(inner : T) -> Self {
+        Self {
+            inner : Some(inner),
+        }
+    }
+    /// gives the value back without deleting the reference
+    pub fn into_inner(mut self) -> T {
+        self.inner.take().unwrap()
+    }
+}
+
+impl<T : JClassInstance> Deref for LocalRef<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref().unwrap()
+    }
+}
+impl<T : JClassInstance> DerefMut for LocalRef<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner.as_mut().unwrap()
+    }
+}
+
+impl<T : JClassInstance> Drop for LocalRef<T> {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            let obj = inner.get_jobject();
+            if !obj.ptr.is_null() {
+                if let Some(class) = obj.resolved_class().filter(|c| !c.ptr.is_null()) {
+                    unchecked_jnic!(obj.env.ptr,DeleteLocalRef, class.ptr);
+                }
+                unchecked_jnic!(obj.env.ptr,DeleteLocalRef, obj.ptr);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jstring::JString, vm::tests::{attach, vm}};
+
+    /// `GetObjectRefType`, 1 is a local, 2 a global and 3 a weak global reference
+    fn ref_type(env : &Jenv, obj : jobject) -> u32 {
+        unchecked_jnic!(env.ptr,GetObjectRefType, obj) as u32
+    }
+
+    #[test]
+    fn global_and_weak() {
+        let env = attach();
+        let local = JString::new("shared", &env);
+        let global = GlobalRef::<JString<'static>>::new(&local).unwrap();
+        assert_eq!(ref_type(&env, global.as_raw()), 2);
+        let weak = global.downgrade(&env).unwrap();
+        assert_eq!(ref_type(&env, weak.ptr), 3);
+
+        let copy = global.clone();
+        assert_ne!(copy.as_raw(), global.as_raw());
+        std::thread::spawn(move || {
+            let env = attach();
+            assert_eq!(String::try_from(&copy.get(&env)).unwrap(), "shared");
+        }).join().unwrap();
+        // dropping on a thread that isn't attached attaches just for the delete
+        let moved = global.clone();
+        std::thread::spawn(move || {
+            drop(moved);
+            assert!(!vm().is_current_thread_attached());
+        }).join().unwrap();
+
+        drop(global);
+        // the local reference still keeps it alive
+        assert!(!weak.is_collected(&env));
+        assert_eq!(String::try_from(&weak.upgrade(&env).unwrap().get(&env)).unwrap(), "shared");
+        assert!(matches!(GlobalRef::<JObject<'static>>::from_raw(&env, ptr::null_mut()), Err(JniError::NullReturn)));
+    }
+
+    #[test]
+    fn local() {
+        let env = attach();
+        let local = LocalRef::new(JString::new("local", &env));
+        assert_eq!(String::try_from(&*local).unwrap(), "local");
+        let kept = local.into_inner();
+        assert_eq!(ref_type(&env, kept.obj.ptr), 1);
+    }
+}
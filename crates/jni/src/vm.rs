@@ -0,0 +1,324 @@
+use std::{ffi::{c_void, CString}, marker::PhantomData, ops::Deref, path::{Path, PathBuf}, ptr};
+
+use jdk_sys::{JavaVM, JavaVMInitArgs, JavaVMOption, JNIEnv, JNI_EDETACHED, JNI_OK, JNI_TRUE, JNI_FALSE,
+    JNI_VERSION_1_1, JNI_VERSION_1_2, JNI_VERSION_1_4, JNI_VERSION_1_6, JNI_VERSION_1_8, JNI_VERSION_9, JNI_VERSION_10};
+
+use crate::{env::Jenv, error::JniError, unchecked_jnic};
+
+/// A handle to a running jvm. it is only a pointer so it can be copied around and shared between threads freely,
+/// use [`JavaVm::attach_current_thread`] to get a [`Jenv`] on whatever thread you are on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JavaVm {
+    ptr : *mut JavaVM,
+}
+
+unsafe impl Send for JavaVm {}
+unsafe impl Sync for JavaVm {}
+
+macro_rules! vm_call {
+    ($vm:expr,$fna:tt$(, $a:expr)*) => {
+        unsafe {(*(*$vm)).$fna.unwrap_unchecked()($vm$(, $a)*)}
+    };
+}
+
+impl JavaVm {
+    /// # Safety
+    /// `ptr` must point to a live `JavaVM`
+    pub unsafe fn from_raw(ptr : *mut JavaVM) -> Result<Self,JniError> {
+        if ptr.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(Self { ptr })
+    }
+    /// the vm `env` belongs to
+    pub fn from_env(env : &Jenv) -> Result<Self,JniError> {
+        let mut ptr : *mut JavaVM = ptr::null_mut();
+        let status = unchecked_jnic!(env.ptr,GetJavaVM, &mut ptr);
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        unsafe { Self::from_raw(ptr) }
+    }
+    /// `JNI_GetCreatedJavaVMs`, the vm of the process we are loaded into. `None` when no vm has been created yet
+    pub fn get_created() -> Result<Option<Self>,JniError> {
+        let mut ptr : *mut JavaVM = ptr::null_mut();
+        let mut count = 0;
+        let status = unsafe { jdk_sys::JNI_GetCreatedJavaVMs(&mut ptr, 1, &mut count) };
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        if count < 1 || ptr.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(Self { ptr }))
+    }
+    /// `JNI_CreateJavaVM`, starts a jvm inside this process. the calling thread is left attached as the jvm's main thread.
+    /// hotspot only allows one jvm per process, even after the first one was destroyed.
+    pub fn create(options : &JavaVmOptions) -> Result<Self,JniError> {
+        let strings = options.option_strings()?;
+        let mut raw_options = strings.iter().map(|s| JavaVMOption {
+            optionString : s.as_ptr() as *mut _,
+            extraInfo : ptr::null_mut(),
+        }).collect::<Vec<_>>();
+        let mut args = JavaVMInitArgs {
+            version : options.version.as_raw(),
+            nOptions : raw_options.len() as i32,
+            options : raw_options.as_mut_ptr(),
+            ignoreUnrecognized : if options.ignore_unrecognized { JNI_TRUE } else { JNI_FALSE } as u8,
+        };
+        let mut vm : *mut JavaVM = ptr::null_mut();
+        let mut env : *mut c_void = ptr::null_mut();
+        let status = unsafe { jdk_sys::JNI_CreateJavaVM(&mut vm, &mut env, &mut args as *mut JavaVMInitArgs as _) };
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        unsafe { Self::from_raw(vm) }
+    }
+    pub fn as_raw(&self) -> *mut JavaVM {
+        self.ptr
+    }
+
+    /// `GetEnv`, the env of the current thread if it is already attached
+    pub fn get_env(&self) -> Result<Option<Jenv<'static>>,JniError> {
+        let mut env : *mut JNIEnv = ptr::null_mut();
+        let status = vm_call!(self.ptr,GetEnv, &mut env as *mut *mut JNIEnv as _, JNI_VERSION_1_8 as i32);
+        match status {
+            s if s == JNI_OK as i32 => Ok(Some(Jenv::from_raw(env))),
+            JNI_EDETACHED => Ok(None),
+            s => Err(JniError::Status(s)),
+        }
+    }
+    pub fn is_current_thread_attached(&self) -> bool {
+        matches!(self.get_env(), Ok(Some(_)))
+    }
+
+    /// `AttachCurrentThread`. if the thread is already attached the guard just borrows the existing env and leaves the thread attached when dropped,
+    /// otherwise the thread is detached again when the guard is dropped.
+    pub fn attach_current_thread(&self) -> Result<AttachGuard,JniError> {
+        self.attach(false)
+    }
+    /// `AttachCurrentThreadAsDaemon`, same as [`Self::attach_current_thread`] but the thread will not keep the jvm from shutting down
+    pub fn attach_current_thread_as_daemon(&self) -> Result<AttachGuard,JniError> {
+        self.attach(true)
+    }
+    fn attach(&self, daemon : bool) -> Result<AttachGuard,JniError> {
+        if let Some(env) = self.get_env()? {
+            return Ok(AttachGuard {
+                vm : *self,
+                env,
+                detach : false,
+                not_send : PhantomData,
+            });
+        }
+        let mut env : *mut JNIEnv = ptr::null_mut();
+        let penv = &mut env as *mut *mut JNIEnv as _;
+        let status = if daemon {
+            vm_call!(self.ptr,AttachCurrentThreadAsDaemon, penv, ptr::null_mut())
+        } else {
+            vm_call!(self.ptr,AttachCurrentThread, penv, ptr::null_mut())
+        };
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        if env.is_null() {
+            return Err(JniError::NullReturn);
+        }
+        Ok(AttachGuard {
+            vm : *self,
+            env : Jenv::from_raw(env),
+            detach : true,
+            not_send : PhantomData,
+        })
+    }
+    /// `DetachCurrentThread`. any [`Jenv`] (and everything created from it) of this thread is invalid afterwards, prefer dropping an [`AttachGuard`]
+    pub fn detach_current_thread(&self) -> Result<(),JniError> {
+        let status = vm_call!(self.ptr,DetachCurrentThread);
+        if status != JNI_OK as i32 {
+            return Err(JniError::Status(status));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum JniVersion {
+    V1_1,
+    V1_2,
+    V1_4,
+    V1_6,
+    #[default]
+    V1_8,
+    V9,
+    V10,
+}
+
+impl JniVersion {
+    pub fn as_raw(self) -> i32 {
+        (match self {
+            JniVersion::V1_1 => JNI_VERSION_1_1,
+            JniVersion::V1_2 => JNI_VERSION_1_2,
+            JniVersion::V1_4 => JNI_VERSION_1_4,
+            JniVersion::V1_6 => JNI_VERSION_1_6,
+            JniVersion::V1_8 => JNI_VERSION_1_8,
+            JniVersion::V9 => JNI_VERSION_9,
+            JniVersion::V10 => JNI_VERSION_10,
+        }) as i32
+    }
+}
+
+/// arguments for [`JavaVm::create`]
+#[derive(Debug, Clone, Default)]
+pub struct JavaVmOptions {
+    pub version : JniVersion,
+    pub classpath : Vec<PathBuf>,
+    /// `-Dkey=value`, the jvm decodes these (and the classpath) with the platform encoding
+    pub properties : Vec<(String, String)>,
+    /// passed through as is, eg. `-Xmx2G` or `-Xcheck:jni`
+    pub options : Vec<String>,
+    pub ignore_unrecognized : bool,
+}
+
+impl JavaVmOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn version(mut self, version : JniVersion) -> Self {
+        self.version = version;
+        self
+    }
+    pub fn classpath(mut self, entry : impl AsRef<Path>) -> Self {
+        self.classpath.push(entry.as_ref().to_path_buf());
+        self
+    }
+    pub fn property(mut self, key : impl Into<String>, value : impl Into<String>) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+    pub fn option(mut self, option : impl Into<String>) -> Self {
+        self.options.push(option.into());
+        self
+    }
+    pub fn ignore_unrecognized(mut self, ignore : bool) -> Self {
+        self.ignore_unrecognized = ignore;
+        self
+    }
+    fn option_strings(&self) -> Result<Vec<CString>,JniError> {
+        let mut strings = vec![];
+        if !self.classpath.is_empty() {
+            let classpath = std::env::join_paths(&self.classpath).map_err(|e| JniError::InvalidUtf(e.to_string()))?;
+            let classpath = classpath.into_string().map_err(|e| JniError::InvalidUtf(format!("{e:?}")))?;
+            strings.push(CString::new(format!("-Djava.class.path={classpath}"))?);
+        }
+        for (key, value) in &self.properties {
+            strings.push(CString::new(format!("-D{key}={value}"))?);
+        }
+        for option in &self.options {
+            strings.push(CString::new(option.as_str())?);
+        }
+        Ok(strings)
+    }
+}
+
+/// Keeps the current thread attached to the jvm, derefs to the thread's [`Jenv`]. it can not be sent to another thread.
+pub struct AttachGuard {
+    vm : JavaVm,
+    env : Jenv<'static>,
+    detach : bool,
+    not_send : PhantomData<*mut ()>,
+}
+
+impl AttachGuard {
+    pub fn env(&self) -> &Jenv<'_> {
+        &self.env
+    }
+    pub fn vm(&self) -> JavaVm {
+        self.vm
+    }
+    /// false when the thread was already attached before the guard was made
+    pub fn will_detach(&self) -> bool {
+        self.detach
+    }
+}
+
+impl Deref for AttachGuard {
+    type Target = Jenv<'static>;
+    fn deref(&self) -> &Self::Target {
+        &self.env
+    }
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        if self.detach {
+            let _ = self.vm.detach_current_thread();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    /// the jvm the tests of this crate share, hotspot only allows one per process
+    pub(crate) fn vm() -> JavaVm {
+        static VM : OnceLock<JavaVm> = OnceLock::new();
+        *VM.get_or_init(|| {
+            // the debug agent (not listening until asked to by jcmd) takes the capabilities that are only to be had at OnLoad,
+            // which leaves method entry / exit and local variables to the jvmti tests. it keeps breakpoints to itself
+            let options = JavaVmOptions::new().property("mci.test", "yes").option("-Xmx64m").option("-agentlib:jdwp=transport=dt_socket,server=y,suspend=n,onjcmd=y");
+            let vm = JavaVm::create(&options).expect("unable to start the test jvm");
+            // the test that happens to create it should not stay attached
+            vm.detach_current_thread().unwrap();
+            vm
+        })
+    }
+
+    /// attaches the test's thread to the shared jvm
+    pub(crate) fn attach() -> AttachGuard {
+        vm().attach_current_thread().unwrap()
+    }
+
+    #[test]
+    fn option_strings() {
+        let options = JavaVmOptions::new().classpath("a.jar").classpath("classes").option("-Xss4m").property("b", "c d");
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        let strings = options.option_strings().unwrap().into_iter().map(|s| s.into_string().unwrap()).collect::<Vec<_>>();
+        // the classpath first, then properties, then the rest in the order they were given
+        assert_eq!(strings, [format!("-Djava.class.path=a.jar{separator}classes"), "-Db=c d".to_string(), "-Xss4m".to_string()]);
+        assert!(JavaVmOptions::new().option_strings().unwrap().is_empty());
+        assert!(matches!(JavaVmOptions::new().property("nul", "\0").option_strings(), Err(JniError::InvalidUtf(_))));
+    }
+
+    #[test]
+    fn created() {
+        let env = attach();
+        let system = env.find_class("java/lang/System").unwrap();
+        let key = crate::jstring::JString::new("mci.test", &env);
+        let value : crate::jstring::JString = system.call_static_object_method("getProperty", "(Ljava/lang/String;)Ljava/lang/String;", &vec![crate::jvalue::JValue::from(&key.obj)]).unwrap();
+        assert_eq!(String::try_from(&value).unwrap(), "yes");
+        assert_eq!(JavaVm::get_created().unwrap(), Some(vm()));
+        // there is already one
+        assert!(matches!(JavaVm::create(&JavaVmOptions::new()), Err(JniError::Status(jdk_sys::JNI_EEXIST))));
+    }
+
+    #[test]
+    fn attach_guards() {
+        let vm = vm();
+        std::thread::spawn(move || {
+            assert!(!vm.is_current_thread_attached());
+            assert!(vm.get_env().unwrap().is_none());
+            let guard = vm.attach_current_thread().unwrap();
+            assert!(guard.will_detach() && vm.is_current_thread_attached());
+            assert_eq!(JavaVm::from_env(&guard).unwrap(), vm);
+            // a second guard borrows the attachment and leaves it alone
+            let nested = vm.attach_current_thread_as_daemon().unwrap();
+            assert!(!nested.will_detach());
+            drop(nested);
+            assert!(vm.is_current_thread_attached());
+            drop(guard);
+            assert!(!vm.is_current_thread_attached());
+        }).join().unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::fmt;
+
+/// `access_flags` of a class, field or method (jvms 4.1, 4.5, 4.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccessFlags(pub u16);
+
+impl AccessFlags {
+    pub const PUBLIC: u16 = 0x0001;
+    pub const PRIVATE: u16 = 0x0002;
+    pub const PROTECTED: u16 = 0x0004;
+    pub const STATIC: u16 = 0x0008;
+    pub const FINAL: u16 = 0x0010;
+    /// `ACC_SUPER` on classes, `ACC_SYNCHRONIZED` on methods
+    pub const SYNCHRONIZED: u16 = 0x0020;
+    /// `ACC_VOLATILE` on fields, `ACC_BRIDGE` on methods
+    pub const BRIDGE: u16 = 0x0040;
+    /// `ACC_TRANSIENT` on fields, `ACC_VARARGS` on methods
+    pub const VARARGS: u16 = 0x0080;
+    pub const NATIVE: u16 = 0x0100;
+    pub const INTERFACE: u16 = 0x0200;
+    pub const ABSTRACT: u16 = 0x0400;
+    pub const SYNTHETIC: u16 = 0x1000;
+    pub const ANNOTATION: u16 = 0x2000;
+    pub const ENUM: u16 = 0x4000;
+
+    pub fn contains(&self, flag: u16) -> bool {
+        self.0 & flag == flag
+    }
+    pub fn is_static(&self) -> bool {
+        self.contains(Self::STATIC)
+    }
+    pub fn is_final(&self) -> bool {
+        self.contains(Self::FINAL)
+    }
+    pub fn is_abstract(&self) -> bool {
+        self.contains(Self::ABSTRACT)
+    }
+    pub fn is_interface(&self) -> bool {
+        self.contains(Self::INTERFACE)
+    }
+    pub fn is_synthetic(&self) -> bool {
+        self.contains(Self::SYNTHETIC)
+    }
+    pub fn visibility(&self) -> Visibility {
+        if self.contains(Self::PUBLIC) {
+            Visibility::Public
+        } else if self.contains(Self::PROTECTED) {
+            Visibility::Protected
+        } else if self.contains(Self::PRIVATE) {
+            Visibility::Private
+        } else {
+            Visibility::Package
+        }
+    }
+}
+
+/// java modifiers in source order, eg. `public static final`
+impl fmt::Display for AccessFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = vec![];
+        match self.visibility() {
+            Visibility::Package => {},
+            vis => words.push(vis.keyword()),
+        }
+        if self.is_abstract() && !self.is_interface() {
+            words.push("abstract");
+        }
+        if self.is_static() {
+            words.push("static");
+        }
+        if self.is_final() {
+            words.push("final");
+        }
+        if self.contains(Self::NATIVE) {
+            words.push("native");
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Protected,
+    /// no modifier
+    Package,
+    Private,
+}
+
+impl Visibility {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Protected => "protected",
+            Visibility::Package => "",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+/// A constant pool entry (jvms 4.4), indexes point back into the pool
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    FieldRef(u16, u16),
+    MethodRef(u16, u16),
+    InterfaceMethodRef(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    Dynamic(u16, u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
+    /// index 0 and the slot after a long or double
+    Unusable,
+}
+
+/// A field or method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub access: AccessFlags,
+    pub name: String,
+    pub descriptor: String,
+    /// the generic `Signature` attribute, eg. `Ljava/util/List<Ljava/lang/String;>;`
+    pub signature: Option<String>,
+}
+
+/// The parts of a class file the generator cares about, attributes other than `Signature` are skipped
+#[derive(Debug, Clone)]
+pub struct ClassFile {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: Vec<Constant>,
+    pub access: AccessFlags,
+    /// internal name, eg. `net/minecraft/class_310` or the obfuscated `eev`
+    pub name: String,
+    /// `None` only for `java/lang/Object` (and module-info)
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: Vec<Member>,
+    pub methods: Vec<Member>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassFileError {
+    BadMagic,
+    UnexpectedEof,
+    UnknownConstant(u8),
+    /// a constant pool index that is out of bounds or points at the wrong kind of constant
+    BadConstant(u16),
+    Io(String),
+}
+
+impl fmt::Display for ClassFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassFileError::BadMagic => write!(f, "not a class file"),
+            ClassFileError::UnexpectedEof => write!(f, "class file ends early"),
+            ClassFileError::UnknownConstant(tag) => write!(f, "unknown constant pool tag {tag}"),
+            ClassFileError::BadConstant(index) => write!(f, "bad constant pool index {index}"),
+            ClassFileError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ClassFileError {}
+
+/// a big endian cursor over the class bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ClassFileError> {
+        let out = self.bytes.get(self.pos..self.pos + len).ok_or(ClassFileError::UnexpectedEof)?;
+        self.pos += len;
+        Ok(out)
+    }
+    fn u1(&mut self) -> Result<u8, ClassFileError> {
+        Ok(self.take(1)?[0])
+    }
+    fn u2(&mut self) -> Result<u16, ClassFileError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+    fn u4(&mut self) -> Result<u32, ClassFileError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+    fn u64(&mut self) -> Result<u64, ClassFileError> {
+        Ok((self.u4()? as u64) << 32 | self.u4()? as u64)
+    }
+}
+
+impl ClassFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ClassFileError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.u4()? != 0xCAFEBABE {
+            return Err(ClassFileError::BadMagic);
+        }
+        let minor_version = r.u2()?;
+        let major_version = r.u2()?;
+        let count = r.u2()?;
+        let mut constant_pool = vec![Constant::Unusable];
+        while constant_pool.len() < count as usize {
+            let tag = r.u1()?;
+            let constant = match tag {
+                1 => {
+                    let len = r.u2()? as usize;
+                    Constant::Utf8(decode_mutf8(r.take(len)?))
+                }
+                3 => Constant::Integer(r.u4()? as i32),
+                4 => Constant::Float(f32::from_bits(r.u4()?)),
+                5 => Constant::Long(r.u64()? as i64),
+                6 => Constant::Double(f64::from_bits(r.u64()?)),
+                7 => Constant::Class(r.u2()?),
+                8 => Constant::String(r.u2()?),
+                9 => Constant::FieldRef(r.u2()?, r.u2()?),
+                10 => Constant::MethodRef(r.u2()?, r.u2()?),
+                11 => Constant::InterfaceMethodRef(r.u2()?, r.u2()?),
+                12 => Constant::NameAndType(r.u2()?, r.u2()?),
+                15 => Constant::MethodHandle(r.u1()?, r.u2()?),
+                16 => Constant::MethodType(r.u2()?),
+                17 => Constant::Dynamic(r.u2()?, r.u2()?),
+                18 => Constant::InvokeDynamic(r.u2()?, r.u2()?),
+                19 => Constant::Module(r.u2()?),
+                20 => Constant::Package(r.u2()?),
+                tag => return Err(ClassFileError::UnknownConstant(tag)),
+            };
+            // longs and doubles take up two slots
+            let wide = matches!(constant, Constant::Long(_) | Constant::Double(_));
+            constant_pool.push(constant);
+            if wide {
+                constant_pool.push(Constant::Unusable);
+            }
+        }
+        let mut class = ClassFile {
+            minor_version,
+            major_version,
+            constant_pool,
+            access: AccessFlags::default(),
+            name: String::new(),
+            super_class: None,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            signature: None,
+        };
+        class.access = AccessFlags(r.u2()?);
+        class.name = class.class_name(r.u2()?)?.to_string();
+        class.super_class = match r.u2()? {
+            0 => None,
+            index => Some(class.class_name(index)?.to_string()),
+        };
+        for _ in 0..r.u2()? {
+            let interface = class.class_name(r.u2()?)?.to_string();
+            class.interfaces.push(interface);
+        }
+        for _ in 0..r.u2()? {
+            let field = class.read_member(&mut r)?;
+            class.fields.push(field);
+        }
+        for _ in 0..r.u2()? {
+            let method = class.read_member(&mut r)?;
+            class.methods.push(method);
+        }
+        class.signature = class.read_attributes(&mut r)?;
+        Ok(class)
+    }
+
+    /// the `CONSTANT_Utf8` at `index`
+    pub fn utf8(&self, index: u16) -> Result<&str, ClassFileError> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Utf8(s)) => Ok(s),
+            _ => Err(ClassFileError::BadConstant(index)),
+        }
+    }
+    /// the name of the `CONSTANT_Class` at `index`
+    pub fn class_name(&self, index: u16) -> Result<&str, ClassFileError> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Class(name)) => self.utf8(*name),
+            _ => Err(ClassFileError::BadConstant(index)),
+        }
+    }
+
+    pub fn field(&self, name: &str, descriptor: &str) -> Option<&Member> {
+        self.fields.iter().find(|f| f.name == name && f.descriptor == descriptor)
+    }
+    pub fn method(&self, name: &str, descriptor: &str) -> Option<&Member> {
+        self.methods.iter().find(|m| m.name == name && m.descriptor == descriptor)
+    }
+
+    fn read_member(&self, r: &mut Reader) -> Result<Member, ClassFileError> {
+        let access = AccessFlags(r.u2()?);
+        let name = self.utf8(r.u2()?)?.to_string();
+        let descriptor = self.utf8(r.u2()?)?.to_string();
+        let signature = self.read_attributes(r)?;
+        Ok(Member { access, name, descriptor, signature })
+    }
+    /// skips over an attribute table, returning the `Signature` attribute if there is one
+    fn read_attributes(&self, r: &mut Reader) -> Result<Option<String>, ClassFileError> {
+        let mut signature = None;
+        for _ in 0..r.u2()? {
+            let name = self.utf8(r.u2()?)?;
+            let len = r.u4()? as usize;
+            let mut body = Reader { bytes: r.take(len)?, pos: 0 };
+            if name == "Signature" {
+                signature = Some(self.utf8(body.u2()?)?.to_string());
+            }
+        }
+        Ok(signature)
+    }
+}
+
+/// modified utf-8 (jvms 4.4.7): nul is two bytes and supplementary characters are surrogate pairs, bad sequences become U+FFFD
+fn decode_mutf8(bytes: &[u8]) -> String {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return s.to_string();
+    }
+    let mut units = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let (unit, len) = if b & 0x80 == 0 {
+            (b as u16, 1)
+        } else if b & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+            (((b & 0x1F) as u16) << 6 | (bytes[i + 1] & 0x3F) as u16, 2)
+        } else if b & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+            (((b & 0x0F) as u16) << 12 | ((bytes[i + 1] & 0x3F) as u16) << 6 | (bytes[i + 2] & 0x3F) as u16, 3)
+        } else {
+            (0xFFFD, 1)
+        };
+        units.push(unit);
+        i += len;
+    }
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf8(out: &mut Vec<u8>, s: &str) {
+        out.push(1);
+        out.extend((s.len() as u16).to_be_bytes());
+        out.extend(s.as_bytes());
+    }
+
+    #[test]
+    fn parse_class() {
+        let mut b = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+        b.extend(11u16.to_be_bytes());
+        utf8(&mut b, "eev"); // 1
+        b.extend([7, 0, 1]); // 2
+        utf8(&mut b, "java/lang/Object"); // 3
+        b.extend([7, 0, 3]); // 4
+        b.extend([5, 0, 0, 0, 1, 0, 0, 0, 0]); // 5 and 6
+        utf8(&mut b, "E"); // 7
+        utf8(&mut b, "Ljava/util/List;"); // 8
+        utf8(&mut b, "Signature"); // 9
+        utf8(&mut b, "Ljava/util/List<Ljava/lang/String;>;"); // 10
+        b.extend((AccessFlags::PUBLIC | AccessFlags::ABSTRACT).to_be_bytes());
+        b.extend([0, 2, 0, 4, 0, 0]);
+        // one field with a Signature attribute
+        b.extend([0, 1]);
+        b.extend((AccessFlags::PRIVATE | AccessFlags::STATIC | AccessFlags::FINAL).to_be_bytes());
+        b.extend([0, 7, 0, 8, 0, 1, 0, 9, 0, 0, 0, 2, 0, 10]);
+        b.extend([0, 0, 0, 0]);
+
+        let class = ClassFile::parse(&b).unwrap();
+        assert_eq!(class.name, "eev");
+        assert_eq!(class.super_class.as_deref(), Some("java/lang/Object"));
+        assert_eq!(class.constant_pool[5], Constant::Long(1 << 32));
+        assert_eq!(class.constant_pool[6], Constant::Unusable);
+        assert!(class.access.is_abstract());
+        let field = class.field("E", "Ljava/util/List;").unwrap();
+        assert!(field.access.is_static() && field.access.is_final());
+        assert_eq!(field.access.visibility(), Visibility::Private);
+        assert_eq!(field.access.to_string(), "private static final");
+        assert_eq!(field.signature.as_deref(), Some("Ljava/util/List<Ljava/lang/String;>;"));
+        assert!(class.methods.is_empty());
+
+        assert_eq!(ClassFile::parse(&b[..b.len() - 3]).unwrap_err(), ClassFileError::UnexpectedEof);
+        assert_eq!(ClassFile::parse(&[0; 8]).unwrap_err(), ClassFileError::BadMagic);
+    }
+
+    #[test]
+    fn modified_utf8() {
+        assert_eq!(decode_mutf8(b"a\xC0\x80b"), "a\0b");
+        assert_eq!(decode_mutf8(&[0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]), "\u{1F600}");
+    }
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, fs::File, io::{Read, Seek}, path::Path};
+
+use super::file::{ClassFile, ClassFileError, Member};
+
+/// Every class in a jar (eg. the minecraft client jar) by internal name
+#[derive(Debug, Clone, Default)]
+pub struct Jar {
+    pub classes: HashMap<String, ClassFile>,
+    /// the `.class` entries that could not be parsed, with why
+    pub skipped: Vec<(String, ClassFileError)>,
+}
+
+impl Jar {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ClassFileError> {
+        let file = File::open(path.as_ref()).map_err(|e| ClassFileError::Io(format!("could not open {}: {e}", path.as_ref().display())))?;
+        Self::from_reader(file)
+    }
+    /// reads every `.class` entry, entries that are not valid class files end up in [`Self::skipped`]
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, ClassFileError> {
+        let mut zip = zip::ZipArchive::new(reader).map_err(|e| ClassFileError::Io(e.to_string()))?;
+        let mut jar = Jar::default();
+        let mut bytes = vec![];
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i).map_err(|e| ClassFileError::Io(e.to_string()))?;
+            if !entry.name().ends_with(".class") {
+                continue;
+            }
+            bytes.clear();
+            entry.read_to_end(&mut bytes).map_err(|e| ClassFileError::Io(e.to_string()))?;
+            match ClassFile::parse(&bytes) {
+                Ok(class) => jar.add(class),
+                Err(e) => jar.skipped.push((entry.name().to_string(), e)),
+            }
+        }
+        Ok(jar)
+    }
+    pub fn add(&mut self, class: ClassFile) {
+        self.classes.insert(class.name.clone(), class);
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassFile> {
+        self.classes.get(name)
+    }
+    /// looks `name` up in `class` and then its super classes, like the jvm resolves a field
+    pub fn field(&self, class: &str, name: &str, descriptor: &str) -> Option<&Member> {
+        let mut class = self.class(class)?;
+        loop {
+            if let Some(field) = class.field(name, descriptor) {
+                return Some(field);
+            }
+            class = self.class(class.super_class.as_deref()?)?;
+        }
+    }
+    /// looks `name` up in `class` and then its super classes, methods only inherited from interfaces are not found
+    pub fn method(&self, class: &str, name: &str, descriptor: &str) -> Option<&Member> {
+        let mut class = self.class(class)?;
+        loop {
+            if let Some(method) = class.method(name, descriptor) {
+                return Some(method);
+            }
+            class = self.class(class.super_class.as_deref()?)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    #[test]
+    fn skipped_entries() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        zip.start_file("META-INF/MANIFEST.MF", Default::default()).unwrap();
+        zip.write_all(b"Manifest-Version: 1.0\n").unwrap();
+        zip.start_file("eev.class", Default::default()).unwrap();
+        zip.write_all(&[0; 8]).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let jar = Jar::from_reader(Cursor::new(bytes)).unwrap();
+        assert!(jar.classes.is_empty());
+        assert_eq!(jar.skipped, [("eev.class".to_string(), ClassFileError::BadMagic)]);
+    }
+}
@@ -1,279 +1,507 @@
-use std::sync::Arc;
-
-use codegen::Block;
-use parking_lot::RwLock;
-use proc_macro2::TokenStream;
-use quote::ToTokens;
-use quote::format_ident;
-use quote::quote;
+use std::collections::HashMap;
+
 use rand::Rng;
 use rand::rngs::ThreadRng;
 
-use crate::tiny;
-use crate::tiny::map::LookupType;
-use crate::yarn::map::{*};
-use crate::tiny::map::Tiny;
+use crate::class::file::AccessFlags;
+use crate::class::jar::Jar;
+use crate::mapping::tree::{ClassMapping, FieldMapping, MappingError, MappingTree, MethodMapping};
+use crate::yarn::map::Signatures;
 
 
 #[derive(Debug)]
 pub struct Generator {
 
-    pub Yarn: Yarn,
-    pub Tiny: Tiny,
+    /// the obfuscated client jar, when set only the accessor (static or instance) a member actually has is generated.
+    /// it is looked up by the names and descriptors of the tree's first namespace (`official`, `obf` for srg), whatever the runtime one is
+    pub Jar: Option<Jar>,
+    mappings: Option<Mappings>,
+
+}
+
+/// the tree the bindings are generated from and which of its namespaces is which, see [`Generator::load`]
+#[derive(Debug)]
+struct Mappings {
+    tree: MappingTree,
+    runtime: usize,
+    key: usize,
+    named: usize,
+}
+
+/// the classes of a package, and the packages in it, as they end up in the generated modules
+#[derive(Default)]
+struct Package<'t> {
+    name: String,
+    packages: Vec<Package<'t>>,
+    classes: Vec<&'t ClassMapping>,
+}
 
+impl<'t> Package<'t> {
+    /// the package `package` (`net/minecraft/client`) in this one, made on the way if needed
+    fn package(&mut self, package: &str) -> &mut Package<'t> {
+        let mut current = self;
+        for name in package.split('/').filter(|n| !n.is_empty()) {
+            let index = match current.packages.iter().position(|p| p.name == name) {
+                Some(index) => index,
+                None => {
+                    current.packages.push(Package { name: name.to_string(), ..Default::default() });
+                    current.packages.len() - 1
+                }
+            };
+            current = &mut current.packages[index];
+        }
+        current
+    }
+}
+
+/// what one generation works with, worked out from the [`Mappings`] up front
+struct View<'t> {
+    mappings: &'t Mappings,
+    /// class names in the first namespace -> in `key`, and -> in `runtime`, for remapping descriptors
+    key_names: HashMap<&'t str, &'t str>,
+    runtime_names: HashMap<&'t str, &'t str>,
+    /// class names in `key` -> the path of their binding, for argument and return types
+    paths: HashMap<&'t str, String>,
+    root: Package<'t>,
+}
+
+impl<'t> View<'t> {
+    fn new(mappings: &'t Mappings) -> Self {
+        let mut view = View {
+            mappings,
+            key_names: mappings.tree.class_names(mappings.key),
+            runtime_names: mappings.tree.class_names(mappings.runtime),
+            paths: HashMap::new(),
+            root: Package { name: "mappings".to_string(), ..Default::default() },
+        };
+        for class in view.classes() {
+            let (Some(key), Some(named)) = (class.name(mappings.key), class.name(mappings.named)) else {
+                continue;
+            };
+            // inner classes go next to their outer class, `Outer$Inner` becomes `OuterInner`
+            let package = named.rsplit_once('/').map_or("", |(package, _)| package);
+            let path = ["mappings"].into_iter().chain(package.split('/').filter(|n| !n.is_empty())).map(str::to_string)
+                .chain([safe_name(named)]).collect::<Vec<_>>().join("::");
+            view.paths.insert(key, path);
+            view.root.package(package).classes.push(class);
+        }
+        view
+    }
+    fn classes(&self) -> std::slice::Iter<'t, ClassMapping> {
+        self.mappings.tree.classes.iter()
+    }
+    /// a descriptor of the first namespace in `key`
+    fn in_key(&self, descriptor: &str) -> String {
+        if self.mappings.key == 0 { descriptor.to_string() } else { MappingTree::remap_descriptor(descriptor, &self.key_names) }
+    }
+    /// a descriptor of the first namespace in `runtime`
+    fn in_runtime(&self, descriptor: &str) -> String {
+        if self.mappings.runtime == 0 { descriptor.to_string() } else { MappingTree::remap_descriptor(descriptor, &self.runtime_names) }
+    }
+    /// the binding of the class called `class` in `key`
+    fn binding(&self, class: &str) -> Option<String> {
+        self.paths.get(class).map(|path| format!("crate::{path}"))
+    }
+}
+
+/// how many local variable slots each parameter of a method descriptor takes, 2 for longs and doubles
+fn parameter_sizes(descriptor: &str) -> Vec<u32> {
+    let params = descriptor.strip_prefix('(').and_then(|d| d.split_once(')')).map_or("", |(params, _)| params);
+    let mut sizes = vec![];
+    let mut chars = params.chars();
+    while let Some(c) = chars.next() {
+        let mut c = c;
+        let mut array = false;
+        while c == '[' {
+            array = true;
+            c = chars.next().unwrap_or('V');
+        }
+        if c == 'L' {
+            chars.by_ref().find(|c| *c == ';');
+        }
+        sizes.push(if !array && (c == 'J' || c == 'D') { 2 } else { 1 });
+    }
+    sizes
+}
+
+/// a name for every argument of `method`, its parameter's name in `named` or `arg<n>` for the unnamed ones
+fn arguments(method: &MethodMapping, named: usize) -> Vec<String> {
+    let sizes = parameter_sizes(&method.descriptor);
+    // parameters are keyed by local variable slot, slot 0 is `this` for instance methods
+    let slots = |start: u32| sizes.iter().scan(start, |lv, size| { let at = *lv; *lv += size; Some(at) }).collect::<Vec<_>>();
+    let (instance, stat) = (slots(1), slots(0));
+    let slots = if method.parameters.iter().all(|p| instance.contains(&p.lv_index)) { instance } else { stat };
+    slots.iter().enumerate().map(|(i, lv)| {
+        method.parameters.iter().find(|p| p.lv_index == *lv).and_then(|p| p.name(named)).map_or(format!("arg{i}"), str::to_string)
+    }).collect()
+}
+
+/// the last part of `name` as an identifier, `net/minecraft/Foo$Bar` -> `FooBar`
+fn safe_name(name: &str) -> String {
+    let mut name = name.trim().rsplit('/').next().unwrap_or_default().to_string();
+    name.retain(|c| c.is_alphanumeric() || c == '_');
+    name
 }
 
 impl Generator {
     pub fn new() -> Self {
         Self {
-            Yarn: Yarn::new(),
-            Tiny: Tiny::new(),
+            Jar: None,
+            mappings: None,
         }
     }
+    /// takes the mapping tree the bindings are generated from.
+    /// `runtime` is what the jvm calls things (`official`, or `srg` on forge), `key` a namespace whose member names are unique
+    /// across classes (`intermediary`) and show up in the generated function names, and `named` what the bindings are called
+    pub fn load(&mut self, tree: &MappingTree, runtime: &str, key: &str, named: &str) -> Result<(), MappingError> {
+        self.mappings = Some(Mappings {
+            runtime: tree.namespace_index(runtime)?,
+            key: tree.namespace_index(key)?,
+            named: tree.namespace_index(named)?,
+            tree: tree.clone(),
+        });
+        Ok(())
+    }
     pub fn generate(&self) -> String {
         let mut scp = codegen::Scope::new();
-        for module in &self.Yarn.modules {
-            let clmod = module.clone();
-            let clmod = clmod.read();
-            
-            let cmod = scp.get_or_new_module(&clmod.name).vis("pub").import("jni::prelude", "*");
-            for clzz_mod in &clmod.scope {
-                self.generate_mod_or_class(cmod, clzz_mod);
-            }
+        let Some(mappings) = &self.mappings else {
+            return scp.to_string();
         };
+        let view = View::new(mappings);
+        let cmod = scp.get_or_new_module(&view.root.name).vis("pub").import("jni::prelude", "*");
+        self.generate_package(cmod, &view.root, &view);
+        // obfuscated -> yarn names of every class, for remapping what the jvm reports (eg. `jni::jvmti::HeapHistogram::remap`)
+        let mapped_classes = view.classes().filter(|c| c.name(mappings.key).is_some()).filter_map(|c| {
+            Some(format!(r#"jni::jvmti::MappedClass{{yarn:"{}",name:"{}"}}"#,c.name(mappings.named)?,c.name(mappings.runtime)?))
+        }).collect::<Vec<_>>();
+        scp.raw(&format!("pub static MAPPED_CLASSES : &[jni::jvmti::MappedClass] = &[{}];", mapped_classes.join(",")));
         scp.to_string()
     }
-    pub fn generate_mod_or_class(&self,up_mod : &mut codegen::Module, mod_or_class: &ModuleOrClass) -> () {
-        match mod_or_class {
-            ModuleOrClass::Module(module) => self.generate_module(up_mod,module),
-            ModuleOrClass::Class(class) => self.generate_class(up_mod,class),
+    fn generate_package(&self, gen_on : &mut codegen::Module, package: &Package, view: &View) -> () {
+        for inner in &package.packages {
+            let cmod = gen_on.new_module(&inner.name).vis("pub").import("jni::prelude", "*");
+            self.generate_package(cmod, inner, view);
+        }
+        for class in &package.classes {
+            self.generate_class(gen_on, class, view);
         }
     }
-    pub fn generate_module(&self,gen_on : &mut codegen::Module, module: &Arc<RwLock<Module>>) -> () {
-        let moder = module.clone();
-        let moder = moder.read();
-        let mut cmod = gen_on.new_module(&moder.name).vis("pub").import("jni::prelude", "*");
-        for clzz_mod in &moder.scope {
-            self.generate_mod_or_class(&mut cmod, clzz_mod);
-        };
-    }
-    pub fn generate_class(&self,gen_on : &mut codegen::Module, class: &Arc<RwLock<Class>>) -> () {
-        let clz = class.clone();
-        let clz = clz.read();
-        let cstruct = gen_on.new_struct(&clz.map_data.get_safe_name()).vis("pub");
+    fn generate_class(&self,gen_on : &mut codegen::Module, class: &ClassMapping, view: &View) -> () {
+        let mappings = view.mappings;
+        let name = safe_name(class.name(mappings.named).unwrap_or_default());
+        let cstruct = gen_on.new_struct(&name).vis("pub");
         cstruct.generic("'a");
         cstruct.field("pub i", "JObject<'a>");
 
-        gen_on.new_impl(&clz.map_data.get_safe_name()).generic("'a").target_generic("'a").impl_trait("From<JObject<'a>>")
+        gen_on.new_impl(&name).generic("'a").target_generic("'a").impl_trait("From<JObject<'a>>")
             .new_fn("from").arg("obj", "JObject<'a>").ret("Self").line("Self { i: obj }");
-        // gen_on.new_impl(&clz.map_data.get_safe_name()).generic("'a").target_generic("'a").impl_trait("Into<JObject<'a>>")
-        //     .new_fn("into").ret("JObject<'a>").line("self.i").arg_self();
-        gen_on.new_impl(&clz.map_data.get_safe_name()).impl_trait("jni::object::JClassInstance").generic("'a").target_generic("'a")
+        gen_on.new_impl(&name).impl_trait("jni::object::JClassInstance").generic("'a").target_generic("'a")
             .new_fn("get_jobject").ret("JObject<'a>").arg_ref_self().line("self.i.clone()");
-        if let Some(ti_name) = self.Tiny.lookup.clone().read().get(&format!("{}_c",clz.map_data.from)) {
-            gen_on.new_impl(&clz.map_data.get_safe_name()).generic("'a").target_generic("'a").associate_const("M_S", "&'static str", format!(r#""{}""#,ti_name.get_obfuscated()), "pub");
-        } else {
+        gen_on.new_impl(&name).impl_trait("jni::refs::JRefTarget").generic("'a").target_generic("'a")
+            .associate_type("Bound<'b>", format!("{}<'b>",name));
+        // without a runtime name there is nothing to look the class up by
+        let Some(class_sig) = class.name(mappings.runtime) else {
             return ();
-        }
-        let cimpl = gen_on.new_impl(&clz.map_data.get_safe_name()).generic("'a").target_generic("'a");
-        for fiel in &clz.fields { 
-            self.generate_field(fiel, class, cimpl)
+        };
+        gen_on.new_impl(&name).generic("'a").target_generic("'a").associate_const("M_S", "&'static str", format!(r#""{}""#,class_sig), "pub");
+        let cimpl = gen_on.new_impl(&name).generic("'a").target_generic("'a");
+        // srg and csrg fields have no descriptor unless it was filled in with MappingTree::fill_field_descriptors
+        for fiel in class.fields.iter().filter(|f| !f.descriptor.is_empty()) {
+            self.generate_field(fiel, class, cimpl, view)
         }
         let mut thr_rand = rand::thread_rng();
-        for meth in &clz.methods {
-            self.generate_method(meth, class, cimpl, &mut thr_rand)
-            
+        let mut mapped_methods = vec![];
+        for meth in &class.methods {
+            self.generate_method(meth, &name, class, cimpl, &mut thr_rand, &mut mapped_methods, view)
         }
-        // let sig = self.Tiny.lookup.clone().read().get(&format!("{}_c",clz.map_data.from)).unwrap().get_obfuscated();
-        // println!("{}",sig);
+        // yarn -> obfuscated names for the jvmti hooks, see `jni::jvmti::Mapped`
+        gen_on.new_impl(&name).generic("'a").target_generic("'a").impl_trait("jni::jvmti::Mapped")
+            .associate_const("CLASS", "&'static str", format!(r#""{}""#,class_sig), "")
+            .associate_const("METHODS", "&'static [jni::jvmti::MappedMethod]", format!("&[{}]",mapped_methods.join(",")), "");
     }
     //@todo refactor
-    fn generate_method(&self, meth:&Method,class:&Arc<RwLock<Class>>,cimpl:&mut codegen::Impl,thr_rand : &mut ThreadRng) -> () {
-        let clz = class.clone();
-        let clz = clz.read();
-        let (pre_lookup_a,pre_lookup_ret) = meth.type_signature.clone().unwrap_method();
-        let is_ret_clz = pre_lookup_ret.is_class();
-        let ret_looked_up = if is_ret_clz {
-            if let Some(lkup) = self.Yarn.lookup.get(&pre_lookup_ret.to_rust().to_uppercase()) {
-                pre_lookup_ret.to_rust_custom_life(&format!("crate::{}<'a>",lkup))
+    #[allow(clippy::too_many_arguments)]
+    fn generate_method(&self, meth:&MethodMapping,class_name:&str,class:&ClassMapping,cimpl:&mut codegen::Impl,thr_rand : &mut ThreadRng,mapped_methods : &mut Vec<String>, view: &View) -> () {
+        let mappings = view.mappings;
+        let (Some(key_name), Some(runtime_name), Some(class_sig)) = (meth.name(mappings.key), meth.name(mappings.runtime), class.name(mappings.runtime)) else {
+            return ();
+        };
+        // static initializers can't be called
+        if runtime_name == "<clinit>" {
+            return ();
+        }
+        let is_constructor = runtime_name == "<init>";
+        if is_constructor && self.is_abstract_class(class) {
+            return ();
+        }
+        let key_desc = view.in_key(&meth.descriptor);
+        let runtime_desc = view.in_runtime(&meth.descriptor);
+        let type_signature = Signatures::parse_from_str(&key_desc);
+        let (pre_lookup_a,pre_lookup_ret) = type_signature.clone().unwrap_method();
+        let ret_looked_up = if pre_lookup_ret.is_class() {
+            if let Some(lkup) = view.binding(&pre_lookup_ret.to_rust()) {
+                pre_lookup_ret.to_rust_custom_life(&format!("{}<'a>",lkup))
             } else {
                 pre_lookup_ret.to_rust_custom_life("JObject<'a>")
             }
         } else {
             pre_lookup_ret.to_rust_life()
         };
-        let temp_lt = LookupType::Method(tiny::map::Method{
-            Obfuscated: meth.map_data.from.clone(),
-            Signature: meth.type_signature.to_java(),
-            Yarn:format!("{}_{}",meth.map_data.get_safe_name(),meth.type_signature.to_javas()),
-            parent: meth.map_data.from.clone(),
+        // the named name, when it is one
+        let named = meth.name(mappings.named).filter(|n| *n != key_name);
+        let yarn_name = named.unwrap_or(key_name);
+
+        let mut ret = format!("Result<{},JniError>",ret_looked_up);
+        // overloads of a constructor only differ in their arguments
+        let (nst, sst) = if is_constructor {
+            (format!("m_init_{}",type_signature.to_javas()), format!("ms_init_{}",type_signature.to_javas()))
+        } else {
+            (format!("m_{}_{}",safe_name(yarn_name),key_name), format!("ms_{}_{}",safe_name(yarn_name),key_name))
+        };
+        let access = match (&self.Jar, class.name(0), meth.name(0)) {
+            (Some(jar), Some(owner), Some(name)) => jar.method(owner, name, &meth.descriptor).map(|m| m.access),
+            _ => None,
+        };
+        // the jar, else the mappings (tsrg2 marks static methods), else there is no telling and both accessors are generated
+        let is_static = access.map(|a| a.is_static()).or(meth.is_static);
+        let gen_instance = is_static != Some(true);
+        let gen_static = !is_constructor && is_static != Some(false);
+        let mut mcf = if is_constructor {
+            ret = format!("Result<{}<'a>,JniError>",class_name);
+            let mut mcf = codegen::Function::new(&nst);
+            mcf.ret(&ret).vis("pub").arg("e", "&'a Jenv<'a>");
+            mcf
 
-        });
-        let mut use_temp = false;
-        if let Some(ti_name) = self.Tiny.lookup.clone().read().get(&format!("{}_m",meth.map_data.from)).or_else(|| {
-            println!("i got you = {temp_lt:?}");
-            if !meth.map_data.from.is_empty() && meth.map_data.to.is_empty() {
-                use_temp = true;
-                Some(&temp_lt)
-            } else {None}
-        }) {
-            let mut ret = format!("Result<{},()>",ret_looked_up);
-
-            let is_ret_clz = is_ret_clz || pre_lookup_ret.is_array();
-            let primative = if is_ret_clz {"".to_string()} else {pre_lookup_ret.get_content().to_java_name()};
-
-
-            let mut n_mname = if is_ret_clz {format!("call_object_method::<{ret_looked_up}>")} else {format!("call_{}_method",primative)};
-            let s_mname = if is_ret_clz {format!("call_static_object_method::<{ret_looked_up}>")} else {format!("call_static_{}_method",primative)};
-            let nst = if use_temp {
-                format!("m_{}",temp_lt.get_yarn())
-            } else {
-                format!("m_{}_{}",&meth.map_data.get_safe_name(),&meth.map_data.from)
-            };
-            let sst = if use_temp {
-                format!("ms_{}",temp_lt.get_yarn())
-            } else {
-                format!("ms_{}_{}",&meth.map_data.get_safe_name(),&meth.map_data.from)
-            };
-            let is_constructor = meth.map_data.from.contains("<init>");
-            let mut mcf = if is_constructor {
-                let clzn = clz.map_data.get_safe_name();
-                n_mname = format!("new_object::<{}>",&clzn);
-                ret = format!("Result<{}<'a>,()>",clzn);
-                let mut mcf = codegen::Function::new(&nst);
-                mcf.ret(&ret).vis("pub").arg("e", "&'a Jenv<'a>");
-                mcf
+        } else {
 
-            } else {
+            let mut mcf = codegen::Function::new(&nst);
+            mcf.ret(&ret).vis("pub").arg_ref_self();
+            mcf
+        };
+        let mut mcfs = codegen::Function::new(&sst);
+        mcfs.ret(ret).vis("pub").arg("e", "&'a Jenv<'a>");
 
-                let mut mcf = codegen::Function::new(&nst);
-                mcf.ret(&ret).vis("pub").arg_ref_self();
-                mcf
-            };
-            let mut mcfs = codegen::Function::new(&sst);
-            mcfs.ret(ret).vis("pub").arg("e", "&'a Jenv<'a>");
-
-            let mut argument_jobjects = vec![];
-            let mut argument_names = vec![];
-            for (idx,marg) in meth.arguments.iter().enumerate() {
-                let mut a_name = marg.get_safe_name();
-                let a_sig = &pre_lookup_a[idx];
-
-                let is_a_clz = a_sig.is_class();
-
-                // now lookup the type.
-
-                let arg_looked_up = if is_a_clz {
-                    format!("&'a {}",if let Some(lkup) = self.Yarn.lookup.get(&a_sig.to_rust_no_array().to_uppercase()) {
-                        a_sig.to_rust_custom_life(&format!("crate::{}",lkup))
-                    } else {
-                        a_sig.to_rust_custom_life("JObject<'a>")
-                    })
-                } else {
-                    a_sig.to_rust_life()
-                };
-
-                if argument_names.contains(&a_name) {
-                    a_name = format!("{}{}",a_name,thr_rand.gen::<u32>());
-                }
+        let mut argument_jobjects = vec![];
+        let mut argument_names = vec![];
+        for (a_sig,a_name) in pre_lookup_a.iter().zip(arguments(meth, mappings.named)) {
+            let mut a_name = safe_name(&a_name);
+            let is_a_clz = a_sig.is_class();
 
-                argument_names.push(a_name.clone());
+            // now lookup the type.
 
-                let fa_name = format!("a_{a_name}");
+            let arg_looked_up = if is_a_clz {
+                format!("&'a {}",a_sig.to_rust_custom_life(&view.binding(&a_sig.to_rust_no_array()).unwrap_or("JObject<'a>".to_string())))
+            } else {
+                a_sig.to_rust_life()
+            };
 
-                let jobj_n = format!("JValue::from({})",if is_a_clz {
-                    format!("{}.get_jobject()",fa_name)
-                } else {
-                    fa_name.clone()
-                });
-                argument_jobjects.push(jobj_n);
+            if argument_names.contains(&a_name) {
+                a_name = format!("{}{}",a_name,thr_rand.gen::<u32>());
+            }
 
+            argument_names.push(a_name.clone());
 
+            let fa_name = format!("a_{a_name}");
 
-                mcf.arg(&fa_name,&arg_looked_up);
-                mcfs.arg(&fa_name,&arg_looked_up);
-            }
-            let code_args = argument_jobjects.join(",");
-            let args = format!(r#"vec![{code_args}]"#);
-            let args_post = format!(r#"("{}","{}",&{args})"#,ti_name.get_obfuscated(),ti_name.get_signature());
-            if is_constructor {
-                mcf.line(format!(r#"e.find_class(Self::M_S)?.{n_mname}{args_post}"#));
+            let jobj_n = format!("JValue::from({})",if is_a_clz {
+                format!("{}.get_jobject()",fa_name)
             } else {
-                mcf.line(format!(r#"self.i.{n_mname}{args_post}"#));
-            }
-            mcfs.line(format!(r#"e.find_class(Self::M_S)?.{s_mname}{args_post}"#));
+                fa_name.clone()
+            });
+            argument_jobjects.push(jobj_n);
+
+
+
+            mcf.arg(&fa_name,&arg_looked_up);
+            mcfs.arg(&fa_name,&arg_looked_up);
+        }
+        let code_args = argument_jobjects.join(",");
+        let args = format!(r#"vec![{code_args}]"#);
+        // ids are resolved on first use and kept in a static per method, see `jni::cache`
+        let id_args = format!(r#"("{}","{}","{}")"#,class_sig,runtime_name,runtime_desc);
+        mapped_methods.push(format!(r#"jni::jvmti::MappedMethod{{yarn:"{}",name:"{}",sig:"{}"}}"#,yarn_name,runtime_name,runtime_desc));
+        mcf.line(format!(r#"static ID: jni::cache::CachedMethod = jni::cache::CachedMethod::new{id_args};"#));
+        if is_constructor {
+            mcf.line(format!(r#"ID.new_object(e,&{args})"#));
+        } else {
+            mcf.line(format!(r#"ID.call(&self.i,&{args})"#));
+        }
+        mcfs.line(format!(r#"static ID: jni::cache::CachedMethod = jni::cache::CachedMethod::new_static{id_args};"#));
+        mcfs.line(format!(r#"ID.call_static(e,&{args})"#));
 
+        if let Some(access) = access {
+            mcf.doc(&access_doc(access));
+            mcfs.doc(&access_doc(access));
+        }
+        if gen_instance {
             cimpl.push_fn(mcf);
-            if !is_constructor {
-                cimpl.push_fn(mcfs);
-            }
         }
-        
+        if gen_static {
+            cimpl.push_fn(mcfs);
+        }
     }
 
-    fn generate_field(&self,fiel:&Field, class: &Arc<RwLock<Class>>, cimpl: &mut codegen::Impl) -> () {
-        let clz = class.clone();
-        let clz = clz.read();
-        let pre_lookup = fiel.type_signature.clone().unwrap_field();
-        let is_clz = pre_lookup.is_class();
-        let looked_up = if is_clz {
-            if let Some(lkup) = self.Yarn.lookup.get(&pre_lookup.to_rust_no_array().to_uppercase()) {
-                pre_lookup.to_rust_custom(&format!("crate::{}",lkup))
-            } else {
-                pre_lookup.to_rust_custom("JObject<'a>")
-            }
+    fn generate_field(&self,fiel:&FieldMapping, class:&ClassMapping, cimpl: &mut codegen::Impl, view: &View) -> () {
+        let mappings = view.mappings;
+        let (Some(key_name), Some(runtime_name), Some(class_sig)) = (fiel.name(mappings.key), fiel.name(mappings.runtime), class.name(mappings.runtime)) else {
+            return ();
+        };
+        let pre_lookup = Signatures::parse_from_str(&view.in_key(&fiel.descriptor)).unwrap_field();
+        let looked_up = if pre_lookup.is_class() {
+            pre_lookup.to_rust_custom(&view.binding(&pre_lookup.to_rust_no_array()).unwrap_or("JObject<'a>".to_string()))
         } else {
             pre_lookup.to_rust()
         };
-
-        if let Some(ti_name) = self.Tiny.lookup.read().get(&format!("{}_f",fiel.map_data.from)) {
-            let ret = format!("Result<{},()>",looked_up);
-
-            let sig_type = fiel.type_signature.to_java();
-            let is_clz = is_clz || fiel.type_signature.clone().unwrap_field().is_array();
-            let primative = if is_clz {"".to_string()} else {fiel.type_signature.clone().unwrap_field().get_content().to_java_name()};
-
-            let static_mname = if is_clz {format!("get_static_object_field::<{looked_up}>")} else {format!("get_static_{}_field",primative)};
-            let n_mname = if is_clz {format!("get_field_object::<{looked_up}>")} else {format!("get_field_{}",primative)};
-
-            cimpl.new_fn(&format!("s_{}",fiel.map_data.get_safe_name())).ret(&ret).arg("e", "&'a Jenv<'a>").vis("pub")
-                .line(format!(r#"e.find_class(Self::M_S)?.{static_mname}("{}", "{}")"#,ti_name.get_obfuscated(),ti_name.get_signature()));
-            cimpl.new_fn(&format!("r#{}",fiel.map_data.get_safe_name())).ret(&ret).arg_ref_self().vis("pub")
-                .line(format!(r#"self.i.{n_mname}("{}", "{}")"#,ti_name.get_obfuscated(),ti_name.get_signature()));
-
+        let name = safe_name(fiel.name(mappings.named).unwrap_or(key_name));
+        let runtime_desc = view.in_runtime(&fiel.descriptor);
+        let ret = format!("Result<{},JniError>",looked_up);
+
+        let id_args = format!(r#"("{}","{}","{}")"#,class_sig,runtime_name,runtime_desc);
+        let access = match (&self.Jar, class.name(0), fiel.name(0)) {
+            (Some(jar), Some(owner), Some(name)) => jar.field(owner, name, &fiel.descriptor).map(|f| f.access),
+            _ => None,
+        };
+        let doc = access.map(access_doc);
+
+        if access.map_or(true, |a| a.is_static()) {
+            let sfn = cimpl.new_fn(&format!("s_{}",name)).ret(&ret).arg("e", "&'a Jenv<'a>").vis("pub")
+                .line(format!(r#"static ID: jni::cache::CachedField = jni::cache::CachedField::new_static{id_args};"#))
+                .line("ID.get_static(e)");
+            if let Some(doc) = &doc {
+                sfn.doc(doc);
+            }
         }
-        else {
-            println!("No tiny lookup for {:?} in {}",fiel,clz.get_namespaced());
+        if access.map_or(true, |a| !a.is_static()) {
+            let ifn = cimpl.new_fn(&format!("r#{}",name)).ret(&ret).arg_ref_self().vis("pub")
+                .line(format!(r#"static ID: jni::cache::CachedField = jni::cache::CachedField::new{id_args};"#))
+                .line("ID.get(&self.i)");
+            if let Some(doc) = &doc {
+                ifn.doc(doc);
+            }
         }
     }
+    /// abstract classes and interfaces can't be constructed, so they get no `<init>` bindings
+    fn is_abstract_class(&self, class:&ClassMapping) -> bool {
+        self.Jar.as_ref().zip(class.name(0)).and_then(|(jar, name)| jar.class(name)).is_some_and(|c| c.access.is_abstract() || c.access.is_interface())
+    }
 
 }
 
+/// the modifiers from the jar, as the doc comment of an accessor. only `static` decides what is generated:
+/// jni does no access checks, so private and package private members are called like public ones,
+/// and fields only get getters, which `final` does not affect
+fn access_doc(access: AccessFlags) -> String {
+    let modifiers = access.to_string();
+    if modifiers.is_empty() {
+        "package private in the jar".to_string()
+    } else {
+        format!("`{modifiers}` in the jar")
+    }
+}
+
 mod tests {
-    use std::{path::PathBuf, io::{BufReader, Write}, fs::{File, self}, str::FromStr};
+    use std::{path::PathBuf, io::{BufReader, Write}, fs::{File, self}};
     use super::*;
+    use crate::class::file::{ClassFile, Member};
+    use crate::mapping::tree::ParameterMapping;
+    use crate::tiny::v1;
+    use crate::yarn::enigma;
 
     #[test]
     fn test_all() {
         let mut gen = Generator::new();
         // println!("@ => {}", std::e::current_dir().unwrap().display());
-        gen.Yarn.run_directory(PathBuf::from("../../mappings/yarn-maps/mappings/net/minecraft/"), None).expect("bnruh");
-        let mut tiny = BufReader::new(File::open("../../mappings/maps.tiny").expect("fw"));
-        gen.Tiny.populate_from_reader(&mut tiny);
-
-        // println!("yarn : {:#?}",gen.Yarn);
+        let yarn = enigma::read_dir(PathBuf::from("../../mappings/yarn-maps/mappings/net/minecraft/")).expect("bnruh");
+        let tiny = v1::parse(BufReader::new(File::open("../../mappings/maps.tiny").expect("fw"))).expect("unable to read the tiny mappings");
+        let tree = tiny.compose(&yarn, enigma::INTERMEDIARY).expect("unable to chain tiny and yarn mappings");
+        gen.load(&tree, "official", enigma::INTERMEDIARY, enigma::NAMED).expect("unable to read names from the mappings");
 
-        // println!("{:?}",gen.Tiny);
-
-        // let code = gen.Yarn.gen(&gen);
         let code = gen.generate();
         let f = fs::File::create("../mc-mappings/gen_src.rs");
         f.unwrap().write_all(code.to_string().as_bytes());
 
-        println!("writing lookup table");
-        let f = fs::File::create("./lookup.ron");
-        f.unwrap().write_all(format!("{:#?}",gen.Yarn.lookup).as_bytes());
-
         // println!("{}",code);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generate_from_tree() {
+        let mut tree = MappingTree::new(vec!["official".into(), "intermediary".into(), "named".into()]);
+        tree.classes.push(ClassMapping {
+            names: vec!["eei".into(), "net/minecraft/class_243".into(), "net/minecraft/util/math/Vec3d".into()],
+            fields: vec![FieldMapping { descriptor: "D".into(), names: vec!["c".into(), "field_1352".into(), "x".into()], comment: None }],
+            methods: vec![
+                MethodMapping {
+                    descriptor: "(Leei;)Leei;".into(),
+                    names: vec!["e".into(), "method_1020".into(), "add".into()],
+                    parameters: vec![ParameterMapping { lv_index: 1, names: vec!["".into(), "".into(), "other".into()], comment: None }],
+                    ..Default::default()
+                },
+                MethodMapping { descriptor: "(DDD)V".into(), names: vec!["<init>".into(), "<init>".into(), "<init>".into()], ..Default::default() },
+                MethodMapping { descriptor: "()V".into(), names: vec!["<clinit>".into(), "<clinit>".into(), "<clinit>".into()], ..Default::default() },
+            ],
+            ..Default::default()
+        });
+        // inner classes are generated next to their outer class
+        tree.classes.push(ClassMapping { names: vec!["eei$a".into(), "net/minecraft/class_243$class_1".into(), "net/minecraft/util/math/Vec3d$Axis".into()], ..Default::default() });
+        // nothing to look it up by at runtime
+        tree.classes.push(ClassMapping { names: vec!["".into(), "net/minecraft/class_9".into(), "net/minecraft/Gone".into()], ..Default::default() });
+
+        let mut gen = Generator::new();
+        assert_eq!(gen.load(&tree, "official", "intermediary", "yarn"), Err(MappingError::UnknownNamespace("yarn".into())));
+        gen.load(&tree, "official", "intermediary", "named").unwrap();
+        let code = gen.generate();
+        // the descriptors are official, the argument and return types are the bindings
+        assert!(code.contains("pub fn m_add_method_1020(&self, a_other: &'a crate::mappings::net::minecraft::util::math::Vec3d) -> Result<crate::mappings::net::minecraft::util::math::Vec3d<'a>,JniError>"), "{code}");
+        assert!(code.contains(r#"CachedMethod::new("eei","e","(Leei;)Leei;")"#));
+        assert!(code.contains(r#"jni::jvmti::MappedMethod{yarn:"add",name:"e",sig:"(Leei;)Leei;"}"#));
+        assert!(code.contains("pub fn m_init_leleleid(e: &'a Jenv<'a>, a_arg0: f64, a_arg1: f64, a_arg2: f64) -> Result<Vec3d<'a>,JniError>"));
+        assert!(!code.contains("clinit"));
+        assert!(code.contains(r#"CachedField::new("eei","c","D")"#) && code.contains("pub fn r#x(&self)"));
+        assert!(code.contains("pub struct Vec3dAxis<'a>"));
+        assert!(code.contains("pub struct Gone<'a>") && !code.contains(r#"name:"""#));
+        assert!(code.contains(r#"jni::jvmti::MappedClass{yarn:"net/minecraft/util/math/Vec3d",name:"eei"}"#));
+    }
+
+    #[test]
+    fn jar_by_first_namespace() {
+        // forge looks members up by srg names, the jar only has the obfuscated ones
+        let mut tree = MappingTree::new(vec!["obf".into(), "srg".into()]);
+        tree.classes.push(ClassMapping {
+            names: vec!["eei".into(), "net/minecraft/world/phys/Vec3".into()],
+            fields: vec![FieldMapping { descriptor: "Leei;".into(), names: vec!["a".into(), "f_82478_".into()], comment: None }],
+            methods: vec![MethodMapping { descriptor: "(Leei;)Leei;".into(), names: vec!["e".into(), "m_82549_".into()], ..Default::default() }],
+            ..Default::default()
+        });
+        let member = |access, name: &str, descriptor: &str| Member { access: AccessFlags(access), name: name.into(), descriptor: descriptor.into(), signature: None };
+        let mut jar = Jar::default();
+        jar.add(ClassFile {
+            minor_version: 0,
+            major_version: 61,
+            constant_pool: vec![],
+            access: AccessFlags(AccessFlags::PUBLIC),
+            name: "eei".into(),
+            super_class: None,
+            interfaces: vec![],
+            fields: vec![member(AccessFlags::PUBLIC | AccessFlags::STATIC, "a", "Leei;")],
+            methods: vec![member(AccessFlags::PUBLIC, "e", "(Leei;)Leei;")],
+            signature: None,
+        });
+
+        let mut gen = Generator::new();
+        gen.load(&tree, "srg", "srg", "srg").unwrap();
+        gen.Jar = Some(jar);
+        let code = gen.generate();
+        assert!(code.contains(r#"CachedField::new_static("net/minecraft/world/phys/Vec3","f_82478_","Lnet/minecraft/world/phys/Vec3;")"#), "{code}");
+        assert!(code.contains("pub fn s_f_82478_") && !code.contains("pub fn r#f_82478_"));
+        assert!(code.contains("pub fn m_m_82549__m_82549_") && !code.contains("pub fn ms_m_82549_"));
+
+        // without the jar, tsrg2's static marker decides
+        tree.classes[0].methods[0].is_static = Some(true);
+        tree.classes[0].methods.push(MethodMapping { descriptor: "()V".into(), names: vec!["f".into(), "m_82550_".into()], is_static: Some(false), ..Default::default() });
+        let mut gen = Generator::new();
+        gen.load(&tree, "srg", "srg", "srg").unwrap();
+        let code = gen.generate();
+        assert!(code.contains("pub fn ms_m_82549__m_82549_") && !code.contains("pub fn m_m_82549__m_82549_"), "{code}");
+        assert!(code.contains("pub fn m_m_82550__m_82550_") && !code.contains("pub fn ms_m_82550_"));
+    }
+}
@@ -1,4 +1,8 @@
 #![feature(is_some_and)]
+pub mod mapping;
 pub mod tiny;
 pub mod yarn;
+pub mod proguard;
+pub mod srg;
+pub mod class;
 pub mod generator;
\ No newline at end of file
@@ -0,0 +1,124 @@
+use std::{fmt, io::{self, Read, Write}};
+
+use serde::{Deserialize, Serialize};
+
+use super::tree::MappingTree;
+
+/// the version of the json layout, bumped whenever a field changes meaning or goes away
+pub const VERSION: u32 = 1;
+
+/// A [`MappingTree`] as json, `{ "version": 1, "namespaces": [..], "classes": [..] }`.
+/// comments, members, parameters and locals are left out when there are none
+#[derive(Serialize, Deserialize)]
+struct Document<T> {
+    version: u32,
+    #[serde(flatten)]
+    mappings: T,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+    /// written by a newer (or older) map-gen
+    UnsupportedVersion(u32),
+    /// `names` that are empty or longer than `namespaces`, at eg. `classes[3].methods[1].names`
+    BadNames(String),
+    Json(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnsupportedVersion(version) => write!(f, "unsupported mapping json version {version}, expected {VERSION}"),
+            JsonError::BadNames(at) => write!(f, "{at} needs between 1 and a name per namespace"),
+            JsonError::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// writes `tree` as pretty printed json, names stay in namespace order so the output only changes when the mappings do
+pub fn write<W: Write>(tree: &MappingTree, writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, &Document { version: VERSION, mappings: tree }).map_err(io::Error::from)
+}
+
+pub fn read<R: Read>(reader: R) -> Result<MappingTree, JsonError> {
+    let value: serde_json::Value = serde_json::from_reader(reader).map_err(|e| JsonError::Json(e.to_string()))?;
+    // the version is checked before the rest so a newer layout is reported as such
+    match value.get("version").and_then(serde_json::Value::as_u64) {
+        Some(version) if version == VERSION as u64 => {}
+        Some(version) => return Err(JsonError::UnsupportedVersion(version as u32)),
+        None => return Err(JsonError::Json("missing version".to_string())),
+    }
+    let document: Document<MappingTree> = serde_json::from_value(value).map_err(|e| JsonError::Json(e.to_string()))?;
+    check_names(&document.mappings)?;
+    Ok(document.mappings)
+}
+
+/// every reader gives each entry a name per namespace (missing ones empty) and the writers count on at least the first,
+/// so ragged json is refused here rather than panicking later
+fn check_names(tree: &MappingTree) -> Result<(), JsonError> {
+    let count = tree.namespaces.len();
+    let check = |names: &[String], at: &dyn Fn() -> String| {
+        if (1..=count).contains(&names.len()) { Ok(()) } else { Err(JsonError::BadNames(at())) }
+    };
+    for (c, class) in tree.classes.iter().enumerate() {
+        check(&class.names, &|| format!("classes[{c}].names"))?;
+        for (f, field) in class.fields.iter().enumerate() {
+            check(&field.names, &|| format!("classes[{c}].fields[{f}].names"))?;
+        }
+        for (m, method) in class.methods.iter().enumerate() {
+            check(&method.names, &|| format!("classes[{c}].methods[{m}].names"))?;
+            for (p, parameter) in method.parameters.iter().enumerate() {
+                check(&parameter.names, &|| format!("classes[{c}].methods[{m}].parameters[{p}].names"))?;
+            }
+            for (l, local) in method.locals.iter().enumerate() {
+                check(&local.names, &|| format!("classes[{c}].methods[{m}].locals[{l}].names"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::tree::{ClassMapping, FieldMapping, MethodMapping, ParameterMapping};
+
+    #[test]
+    fn round_trip() {
+        let mut tree = MappingTree::new(vec!["official".into(), "named".into()]);
+        tree.classes.push(ClassMapping {
+            names: vec!["eei".into(), "net/minecraft/world/phys/Vec3".into()],
+            comment: Some("An immutable vector.".into()),
+            fields: vec![FieldMapping { descriptor: "D".into(), names: vec!["c".into(), "x".into()], comment: None }],
+            methods: vec![MethodMapping {
+                descriptor: "(Leei;)Leei;".into(),
+                names: vec!["e".into(), "add".into()],
+                parameters: vec![ParameterMapping { lv_index: 1, names: vec!["".into(), "other".into()], comment: None }],
+                ..Default::default()
+            }],
+        });
+        tree.classes.push(ClassMapping { names: vec!["eev".into(), "".into()], ..Default::default() });
+        let mut written = vec![];
+        write(&tree, &mut written).unwrap();
+        let json = String::from_utf8(written.clone()).unwrap();
+        assert!(json.starts_with("{\n  \"version\": 1,\n  \"namespaces\": [\n    \"official\",\n    \"named\"\n  ],"));
+        // nothing for the empty parts of the second class
+        assert!(json.ends_with("{\n      \"names\": [\n        \"eev\",\n        \"\"\n      ]\n    }\n  ]\n}"));
+        assert_eq!(read(written.as_slice()).unwrap(), tree);
+
+        assert_eq!(read("{\"version\": 2, \"classes\": {}}".as_bytes()), Err(JsonError::UnsupportedVersion(2)));
+        assert!(matches!(read("{\"namespaces\": []}".as_bytes()), Err(JsonError::Json(_))));
+    }
+
+    #[test]
+    fn ragged_names() {
+        let document = |class: &str| format!("{{\"version\": 1, \"namespaces\": [\"official\", \"named\"], \"classes\": [{class}]}}");
+        assert!(read(document(r#"{"names": ["eei"]}"#).as_bytes()).is_ok());
+        assert_eq!(read(document(r#"{"names": []}"#).as_bytes()), Err(JsonError::BadNames("classes[0].names".into())));
+        assert_eq!(read(document(r#"{"names": ["a", "b", "c"]}"#).as_bytes()), Err(JsonError::BadNames("classes[0].names".into())));
+        let method = r#"{"names": ["eei"], "methods": [{"descriptor": "()V", "names": ["e"], "parameters": [{"lv_index": 1, "names": []}]}]}"#;
+        assert_eq!(read(document(method).as_bytes()), Err(JsonError::BadNames("classes[0].methods[0].parameters[0].names".into())));
+    }
+}
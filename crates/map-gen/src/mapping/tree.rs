@@ -0,0 +1,497 @@
+use std::{collections::{HashMap, HashSet}, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::class::jar::Jar;
+
+/// Mappings between any number of namespaces (eg. `official intermediary named`), what every reader in map-gen produces
+/// and what [`crate::generator::Generator::load`] generates from.
+/// names are stored per namespace in `namespaces` order, an empty name means the namespace has no mapping for it.
+/// descriptors are always in the first namespace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingTree {
+    pub namespaces: Vec<String>,
+    pub classes: Vec<ClassMapping>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassMapping {
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldMapping>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<MethodMapping>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldMapping {
+    /// empty for formats that leave it out (srg, csrg), see [`MappingTree::fill_field_descriptors`]
+    pub descriptor: String,
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodMapping {
+    pub descriptor: String,
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<ParameterMapping>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locals: Vec<LocalMapping>,
+    /// `None` unless the format says, tsrg2 marks static methods
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_static: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterMapping {
+    /// local variable index, `this` is 0 for instance methods and longs / doubles take two
+    pub lv_index: u32,
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalMapping {
+    pub lv_index: u32,
+    /// bytecode offset the variable starts at
+    pub lv_start_offset: u32,
+    /// row in the local variable table, `None` when the file has `-1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lvt_row_index: Option<u32>,
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MappingError {
+    UnknownNamespace(String),
+    /// a namespace picked twice in [`MappingTree::select`]
+    DuplicateNamespace(String),
+}
+
+impl fmt::Display for MappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MappingError::UnknownNamespace(ns) => write!(f, "no namespace called {ns}"),
+            MappingError::DuplicateNamespace(ns) => write!(f, "namespace {ns} is there twice"),
+        }
+    }
+}
+
+impl std::error::Error for MappingError {}
+
+macro_rules! names_of {
+    ($($mapping:ty),*) => {
+        $(
+            impl $mapping {
+                /// the name in namespace `ns`, `None` when it has no mapping there
+                pub fn name(&self, ns: usize) -> Option<&str> {
+                    self.names.get(ns).map(String::as_str).filter(|n| !n.is_empty())
+                }
+            }
+        )*
+    };
+}
+names_of!(ClassMapping, FieldMapping, MethodMapping, ParameterMapping, LocalMapping);
+
+/// `names` reordered to `picks` (indices into `names`), missing ones are empty
+fn pick(names: &[String], picks: &[usize]) -> Vec<String> {
+    picks.iter().map(|i| names.get(*i).cloned().unwrap_or_default()).collect()
+}
+
+/// copies the names of `theirs` into the empty slots of `ours`, `slots` says where each of their namespaces goes
+fn fill(ours: &mut Vec<String>, theirs: &[String], slots: &[usize], count: usize) {
+    ours.resize(count, String::new());
+    for (name, slot) in theirs.iter().zip(slots) {
+        if ours[*slot].is_empty() {
+            ours[*slot] = name.clone();
+        }
+    }
+}
+
+/// `theirs` laid out in the joined namespaces
+fn place(theirs: &[String], slots: &[usize], count: usize) -> Vec<String> {
+    let mut names = vec![String::new(); count];
+    fill(&mut names, theirs, slots, count);
+    names
+}
+
+impl MappingTree {
+    pub fn new(namespaces: Vec<String>) -> Self {
+        Self { namespaces, classes: vec![] }
+    }
+
+    /// the index of the namespace called `name`
+    pub fn namespace(&self, name: &str) -> Option<usize> {
+        self.namespaces.iter().position(|ns| ns == name)
+    }
+    pub(crate) fn namespace_index(&self, name: &str) -> Result<usize, MappingError> {
+        self.namespace(name).ok_or_else(|| MappingError::UnknownNamespace(name.to_string()))
+    }
+    /// class names in the first namespace -> names in `ns`, classes without a name there are left out
+    pub fn class_names(&self, ns: usize) -> HashMap<&str, &str> {
+        self.classes.iter()
+            .filter_map(|c| Some((c.name(0)?, c.name(ns)?)))
+            .collect()
+    }
+    /// rewrites the class names in a descriptor of the first namespace with `names` (see [`Self::class_names`]),
+    /// eg. `(Leei;)V` -> `(Lnet/minecraft/class_243;)V`
+    pub fn remap_descriptor(descriptor: &str, names: &HashMap<&str, &str>) -> String {
+        let mut out = String::with_capacity(descriptor.len());
+        let mut rest = descriptor;
+        while let Some(start) = rest.find('L') {
+            out.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            let class = &rest[..end];
+            out.push_str(names.get(class).copied().unwrap_or(class));
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+    /// fills in the field descriptors srg and csrg files leave out, from the classes in `jar` which has to use the first namespace's names
+    pub fn fill_field_descriptors(&mut self, jar: &Jar) {
+        for class in &mut self.classes {
+            let Some(file) = jar.class(&class.names[0]) else {
+                continue;
+            };
+            for field in class.fields.iter_mut().filter(|f| f.descriptor.is_empty()) {
+                if let Some(member) = file.fields.iter().find(|m| m.name == field.names[0]) {
+                    field.descriptor = member.descriptor.clone();
+                }
+            }
+        }
+    }
+
+    /// renames a namespace, eg. srg's `obf` to tiny's `official` so the two can be composed
+    pub fn rename_namespace(&mut self, from: &str, to: &str) -> Result<(), MappingError> {
+        let ns = self.namespace_index(from)?;
+        if self.namespace(to).is_some_and(|existing| existing != ns) {
+            return Err(MappingError::DuplicateNamespace(to.to_string()));
+        }
+        self.namespaces[ns] = to.to_string();
+        Ok(())
+    }
+
+    /// only the namespaces `namespaces`, in that order. descriptors move to the new first namespace,
+    /// classes and members without a name there are dropped
+    pub fn select(&self, namespaces: &[&str]) -> Result<Self, MappingError> {
+        let mut picks = Vec::with_capacity(namespaces.len());
+        for ns in namespaces {
+            let index = self.namespace_index(ns)?;
+            if picks.contains(&index) {
+                return Err(MappingError::DuplicateNamespace(ns.to_string()));
+            }
+            picks.push(index);
+        }
+        let mut tree = MappingTree::new(namespaces.iter().map(|ns| ns.to_string()).collect());
+        let Some(&first) = picks.first() else {
+            return Ok(tree);
+        };
+        let first_names = self.class_names(first);
+        let descriptor = |d: &str| if first == 0 { d.to_string() } else { Self::remap_descriptor(d, &first_names) };
+        let has_names = |names: &Vec<String>| names.iter().any(|n| !n.is_empty());
+        for class in self.classes.iter().filter(|c| c.name(first).is_some()) {
+            tree.classes.push(ClassMapping {
+                names: pick(&class.names, &picks),
+                comment: class.comment.clone(),
+                fields: class.fields.iter().filter(|f| f.name(first).is_some()).map(|f| FieldMapping {
+                    descriptor: descriptor(&f.descriptor),
+                    names: pick(&f.names, &picks),
+                    comment: f.comment.clone(),
+                }).collect(),
+                methods: class.methods.iter().filter(|m| m.name(first).is_some()).map(|m| MethodMapping {
+                    descriptor: descriptor(&m.descriptor),
+                    names: pick(&m.names, &picks),
+                    comment: m.comment.clone(),
+                    parameters: m.parameters.iter()
+                        .map(|p| ParameterMapping { names: pick(&p.names, &picks), ..p.clone() })
+                        .filter(|p| has_names(&p.names))
+                        .collect(),
+                    locals: m.locals.iter()
+                        .map(|l| LocalMapping { names: pick(&l.names, &picks), ..l.clone() })
+                        .filter(|l| has_names(&l.names))
+                        .collect(),
+                    is_static: m.is_static,
+                }).collect(),
+            });
+        }
+        Ok(tree)
+    }
+    /// the namespaces in reverse, `official intermediary` becomes `intermediary official`.
+    /// like [`Self::select`], classes and members without a name in the last namespace are dropped since nothing could look them up
+    /// once it is first. fails when a namespace is there twice, which trees read from json can have
+    pub fn invert(&self) -> Result<Self, MappingError> {
+        let namespaces = self.namespaces.iter().rev().map(String::as_str).collect::<Vec<_>>();
+        self.select(&namespaces)
+    }
+
+    /// chains two trees through the namespace `on` they share, eg. `official intermediary` with `intermediary named`
+    /// on `intermediary` gives `official intermediary named`. `other`'s namespaces are added after this tree's,
+    /// names this tree already has win. entries only `other` has are dropped, see [`Self::merge`] to keep them
+    pub fn compose(&self, other: &MappingTree, on: &str) -> Result<Self, MappingError> {
+        self.join(other, on, false)
+    }
+    /// everything in both trees, matched by this tree's first namespace which `other` needs to have too.
+    /// names this tree already has win and `other`'s extra namespaces are added after this tree's
+    pub fn merge(&self, other: &MappingTree) -> Result<Self, MappingError> {
+        let first = self.namespaces.first().ok_or_else(|| MappingError::UnknownNamespace(String::new()))?;
+        self.join(other, first, true)
+    }
+
+    fn join(&self, other: &MappingTree, on: &str, keep_theirs: bool) -> Result<Self, MappingError> {
+        let ours_on = self.namespace_index(on)?;
+        let theirs_on = other.namespace_index(on)?;
+        // where each of other's namespaces ends up
+        let mut namespaces = self.namespaces.clone();
+        let slots = other.namespaces.iter().map(|ns| match self.namespace(ns) {
+            Some(index) => index,
+            None => {
+                namespaces.push(ns.clone());
+                namespaces.len() - 1
+            }
+        }).collect::<Vec<_>>();
+        let count = namespaces.len();
+        // methods are matched by name and descriptor in `on`
+        let (ours_names, theirs_names) = (self.class_names(ours_on), other.class_names(theirs_on));
+        let ours_key = |d: &str| if ours_on == 0 { d.to_string() } else { Self::remap_descriptor(d, &ours_names) };
+        let theirs_key = |d: &str| if theirs_on == 0 { d.to_string() } else { Self::remap_descriptor(d, &theirs_names) };
+        let ours_from_on = ours_names.iter().map(|(first, on)| (*on, *first)).collect::<HashMap<_, _>>();
+        // entries only other has need a name and descriptor in our first namespace
+        let theirs_first = other.namespace(&self.namespaces[0]);
+        let first_names = theirs_first.map(|ns| other.class_names(ns)).unwrap_or_default();
+        let has_first = |names: &Vec<String>| theirs_first.and_then(|ns| names.get(ns)).is_some_and(|n| !n.is_empty());
+        let theirs_descriptor = |d: &str| match theirs_first {
+            Some(0) => d.to_string(),
+            _ => Self::remap_descriptor(d, &first_names),
+        };
+
+        let theirs_classes = other.classes.iter().enumerate()
+            .filter_map(|(i, c)| Some((c.name(theirs_on)?, i)))
+            .collect::<HashMap<_, _>>();
+        let mut matched = HashSet::new();
+        let mut tree = MappingTree::new(namespaces);
+        for class in &self.classes {
+            let mut joined = class.clone();
+            joined.names.resize(count, String::new());
+            for field in &mut joined.fields {
+                field.names.resize(count, String::new());
+            }
+            for method in &mut joined.methods {
+                method.names.resize(count, String::new());
+                for parameter in &mut method.parameters {
+                    parameter.names.resize(count, String::new());
+                }
+                for local in &mut method.locals {
+                    local.names.resize(count, String::new());
+                }
+            }
+            let Some(&index) = class.name(ours_on).and_then(|n| theirs_classes.get(n)) else {
+                tree.classes.push(joined);
+                continue;
+            };
+            matched.insert(index);
+            let theirs = &other.classes[index];
+            fill(&mut joined.names, &theirs.names, &slots, count);
+            joined.comment = joined.comment.or_else(|| theirs.comment.clone());
+
+            // fields by name alone, srg leaves their descriptors out
+            let mut theirs_fields = theirs.fields.iter().filter_map(|f| Some((f.name(theirs_on)?, f))).collect::<HashMap<_, _>>();
+            for field in &mut joined.fields {
+                let Some(their_field) = field.name(ours_on).and_then(|n| theirs_fields.remove(n)) else {
+                    continue;
+                };
+                fill(&mut field.names, &their_field.names, &slots, count);
+                field.comment = field.comment.take().or_else(|| their_field.comment.clone());
+                if field.descriptor.is_empty() && !their_field.descriptor.is_empty() && theirs_first.is_some() {
+                    field.descriptor = theirs_descriptor(&their_field.descriptor);
+                }
+            }
+            let mut theirs_methods = theirs.methods.iter()
+                .filter_map(|m| Some(((m.name(theirs_on)?.to_string(), theirs_key(&m.descriptor)), m)))
+                .collect::<HashMap<_, _>>();
+            for method in &mut joined.methods {
+                let key = match method.name(ours_on) {
+                    Some(name) => (name.to_string(), ours_key(&method.descriptor)),
+                    None => continue,
+                };
+                let Some(their_method) = theirs_methods.remove(&key) else {
+                    continue;
+                };
+                fill(&mut method.names, &their_method.names, &slots, count);
+                method.comment = method.comment.take().or_else(|| their_method.comment.clone());
+                method.is_static = method.is_static.or(their_method.is_static);
+                for their_parameter in &their_method.parameters {
+                    match method.parameters.iter_mut().find(|p| p.lv_index == their_parameter.lv_index) {
+                        Some(parameter) => {
+                            fill(&mut parameter.names, &their_parameter.names, &slots, count);
+                            parameter.comment = parameter.comment.take().or_else(|| their_parameter.comment.clone());
+                        }
+                        None => method.parameters.push(ParameterMapping { names: place(&their_parameter.names, &slots, count), ..their_parameter.clone() }),
+                    }
+                }
+                for their_local in &their_method.locals {
+                    match method.locals.iter_mut().find(|l| (l.lv_index, l.lv_start_offset) == (their_local.lv_index, their_local.lv_start_offset)) {
+                        Some(local) => {
+                            fill(&mut local.names, &their_local.names, &slots, count);
+                            local.comment = local.comment.take().or_else(|| their_local.comment.clone());
+                        }
+                        None => method.locals.push(LocalMapping { names: place(&their_local.names, &slots, count), ..their_local.clone() }),
+                    }
+                }
+            }
+            // constructors are named the same everywhere, so they come along even when this tree leaves them out like tiny v1 does
+            let constructors = theirs.methods.iter()
+                .filter(|m| m.name(theirs_on).is_some_and(|n| n.starts_with('<')))
+                .filter_map(|m| theirs_methods.remove(&(m.names[theirs_on].clone(), theirs_key(&m.descriptor))))
+                .collect::<Vec<_>>();
+            for constructor in constructors {
+                let mut method = their_method(constructor, &slots, count, &|d: &str| Self::remap_descriptor(&theirs_key(d), &ours_from_on));
+                for name in method.names.iter_mut().filter(|n| n.is_empty()) {
+                    name.clone_from(&constructor.names[theirs_on]);
+                }
+                joined.methods.push(method);
+            }
+            if keep_theirs && theirs_first.is_some() {
+                // what is left in the maps was not matched, added in the order other has them
+                let fields = theirs.fields.iter().filter(|f| f.name(theirs_on).is_some_and(|n| theirs_fields.contains_key(n)));
+                let methods = theirs.methods.iter().filter(|m| m.name(theirs_on).is_some_and(|n| theirs_methods.contains_key(&(n.to_string(), theirs_key(&m.descriptor)))));
+                joined.fields.extend(fields.filter(|f| has_first(&f.names)).map(|f| their_field(f, &slots, count, &theirs_descriptor)));
+                joined.methods.extend(methods.filter(|m| has_first(&m.names)).map(|m| their_method(m, &slots, count, &theirs_descriptor)));
+            }
+            tree.classes.push(joined);
+        }
+        if keep_theirs && theirs_first.is_some() {
+            for (i, theirs) in other.classes.iter().enumerate() {
+                if matched.contains(&i) || !has_first(&theirs.names) {
+                    continue;
+                }
+                tree.classes.push(ClassMapping {
+                    names: place(&theirs.names, &slots, count),
+                    comment: theirs.comment.clone(),
+                    fields: theirs.fields.iter().filter(|f| has_first(&f.names)).map(|f| their_field(f, &slots, count, &theirs_descriptor)).collect(),
+                    methods: theirs.methods.iter().filter(|m| has_first(&m.names)).map(|m| their_method(m, &slots, count, &theirs_descriptor)).collect(),
+                });
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/// an entry only the other tree of a join has, laid out in the joined namespaces
+fn their_field(field: &FieldMapping, slots: &[usize], count: usize, descriptor: &impl Fn(&str) -> String) -> FieldMapping {
+    FieldMapping { descriptor: descriptor(&field.descriptor), names: place(&field.names, slots, count), comment: field.comment.clone() }
+}
+fn their_method(method: &MethodMapping, slots: &[usize], count: usize, descriptor: &impl Fn(&str) -> String) -> MethodMapping {
+    MethodMapping {
+        descriptor: descriptor(&method.descriptor),
+        names: place(&method.names, slots, count),
+        comment: method.comment.clone(),
+        parameters: method.parameters.iter().map(|p| ParameterMapping { names: place(&p.names, slots, count), ..p.clone() }).collect(),
+        locals: method.locals.iter().map(|l| LocalMapping { names: place(&l.names, slots, count), ..l.clone() }).collect(),
+        is_static: method.is_static,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiny::v1;
+    use crate::yarn::enigma;
+
+    fn intermediary() -> MappingTree {
+        v1::parse("v1\tofficial\tintermediary
+CLASS\teev\tnet/minecraft/class_310
+CLASS\teei\tnet/minecraft/class_243
+FIELD\teev\tLeei;\tp\tfield_1724
+METHOD\teev\t(Leei;D)V\tb\tmethod_1000
+METHOD\teev\t()Z\ta\tmethod_1533
+".as_bytes()).unwrap()
+    }
+
+    fn yarn() -> MappingTree {
+        let mut tree = MappingTree::new(vec![enigma::INTERMEDIARY.to_string(), enigma::NAMED.to_string()]);
+        enigma::parse("CLASS net/minecraft/class_310 net/minecraft/client/MinecraftClient
+\tFIELD field_1724 pos Lnet/minecraft/class_243;
+\tMETHOD method_1000 setPos (Lnet/minecraft/class_243;D)V
+\t\tARG 1 pos
+CLASS net/minecraft/class_243 net/minecraft/util/math/Vec3d
+\tMETHOD <init> (DDD)V
+CLASS net/minecraft/class_999 net/minecraft/Unused
+".as_bytes(), &mut tree).unwrap();
+        tree
+    }
+
+    #[test]
+    fn compose() {
+        let tree = intermediary().compose(&yarn(), "intermediary").unwrap();
+        assert_eq!(tree.namespaces, ["official", "intermediary", "named"]);
+        // only what the first tree has
+        assert_eq!(tree.classes.len(), 2);
+        let client = &tree.classes[0];
+        assert_eq!(client.names, ["eev", "net/minecraft/class_310", "net/minecraft/client/MinecraftClient"]);
+        assert_eq!(client.fields[0].names, ["p", "field_1724", "pos"]);
+        // matched through the descriptor in intermediary, kept in official
+        assert_eq!(client.methods[0].names, ["b", "method_1000", "setPos"]);
+        assert_eq!(client.methods[0].descriptor, "(Leei;D)V");
+        assert_eq!(client.methods[0].parameters[0].names, ["", "", "pos"]);
+        assert_eq!(client.methods[1].names, ["a", "method_1533", ""]);
+        let init = &tree.classes[1].methods[0];
+        assert_eq!((init.names.as_slice(), init.descriptor.as_str()), (["<init>", "<init>", "<init>"].map(String::from).as_slice(), "(DDD)V"));
+        assert_eq!(tree.classes[1].names, ["eei", "net/minecraft/class_243", "net/minecraft/util/math/Vec3d"]);
+
+        assert_eq!(intermediary().compose(&yarn(), "named"), Err(MappingError::UnknownNamespace("named".to_string())));
+    }
+
+    #[test]
+    fn invert_and_select() {
+        let tree = intermediary().compose(&yarn(), "intermediary").unwrap();
+        let inverted = tree.invert().unwrap();
+        assert_eq!(inverted.namespaces, ["named", "intermediary", "official"]);
+        assert_eq!(inverted.classes.len(), 2);
+        let client = &inverted.classes[0];
+        // `method_1533` has no named name to be looked up by
+        assert_eq!(client.methods.len(), 1);
+        // descriptors move to the new first namespace
+        assert_eq!(client.methods[0].descriptor, "(Lnet/minecraft/util/math/Vec3d;D)V");
+        assert_eq!(client.methods[0].names, ["setPos", "method_1000", "b"]);
+        assert_eq!(inverted.invert().unwrap().classes[0].names, tree.classes[0].names);
+        let twice = MappingTree::new(vec!["x".to_string(), "x".to_string()]);
+        assert_eq!(twice.invert(), Err(MappingError::DuplicateNamespace("x".to_string())));
+
+        let named = tree.select(&["named"]).unwrap();
+        assert_eq!(named.classes[0].fields[0].descriptor, "Lnet/minecraft/util/math/Vec3d;");
+        assert_eq!(tree.select(&["named", "named"]), Err(MappingError::DuplicateNamespace("named".to_string())));
+    }
+
+    #[test]
+    fn merge() {
+        let mut srg = MappingTree::new(vec!["official".to_string(), "srg".to_string()]);
+        srg.classes.push(ClassMapping {
+            names: vec!["eev".into(), "net/minecraft/client/Minecraft".into()],
+            fields: vec![FieldMapping { descriptor: String::new(), names: vec!["p".into(), "f_91075_".into()], comment: None }],
+            methods: vec![MethodMapping { descriptor: "()V".into(), names: vec!["z".into(), "m_1_".into()], ..Default::default() }],
+            ..Default::default()
+        });
+        srg.classes.push(ClassMapping { names: vec!["abc".into(), "net/minecraft/Other".into()], ..Default::default() });
+        let tree = intermediary().merge(&srg).unwrap();
+        assert_eq!(tree.namespaces, ["official", "intermediary", "srg"]);
+        assert_eq!(tree.classes.len(), 3);
+        let client = &tree.classes[0];
+        assert_eq!(client.names, ["eev", "net/minecraft/class_310", "net/minecraft/client/Minecraft"]);
+        assert_eq!(client.fields[0].names, ["p", "field_1724", "f_91075_"]);
+        // the method only srg has
+        assert_eq!(client.methods[2].names, ["z", "", "m_1_"]);
+        assert_eq!(tree.classes[2].names, ["abc", "", "net/minecraft/Other"]);
+    }
+}
@@ -0,0 +1,364 @@
+use std::{collections::HashMap, fmt, io::{self, BufRead, Write}};
+
+use crate::mapping::tree::{ClassMapping, FieldMapping, MappingError, MappingTree, MethodMapping};
+
+/// A ProGuard mapping file, the format mojang publishes its official names in (`client.txt`).
+/// names are java source style (`net.minecraft.client.Minecraft`), [`ProGuard::to_tree`] turns them into internal names and descriptors
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProGuard {
+    pub classes: Vec<ProGuardClass>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProGuardClass {
+    /// the readable name, eg. `net.minecraft.client.Minecraft`
+    pub name: String,
+    pub obfuscated: String,
+    pub fields: Vec<ProGuardField>,
+    pub methods: Vec<ProGuardMethod>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProGuardField {
+    /// java type, eg. `int` or `net.minecraft.world.phys.Vec3[]`
+    pub field_type: String,
+    pub name: String,
+    pub obfuscated: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProGuardMethod {
+    pub return_type: String,
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub obfuscated: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProGuardParseError {
+    /// a line (1 based) that is neither a class nor a member
+    BadLine(usize),
+    /// a member line before the first class
+    NoClass(usize),
+    Io(String),
+}
+
+impl fmt::Display for ProGuardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProGuardParseError::BadLine(line) => write!(f, "can't read line {line}"),
+            ProGuardParseError::NoClass(line) => write!(f, "member outside of a class on line {line}"),
+            ProGuardParseError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ProGuardParseError {}
+
+/// the namespaces of [`ProGuard::to_tree`]
+pub const OFFICIAL: &str = "official";
+/// stands in for intermediary: the readable class names, and made up `method_<hash>` / `field_<hash>` ids for members
+/// since readable member names are only unique within their class, see [`member_id`]
+pub const IDS: &str = "ids";
+pub const NAMED: &str = "named";
+
+impl ProGuard {
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self, ProGuardParseError> {
+        let mut mappings = ProGuard::default();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| ProGuardParseError::Io(e.to_string()))?;
+            let number = i + 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let (left, obfuscated) = trimmed.split_once(" -> ").ok_or(ProGuardParseError::BadLine(number))?;
+            if !line.starts_with(char::is_whitespace) {
+                // `net.minecraft.client.Minecraft -> eev:`
+                let obfuscated = obfuscated.strip_suffix(':').ok_or(ProGuardParseError::BadLine(number))?;
+                mappings.classes.push(ProGuardClass { name: left.to_string(), obfuscated: obfuscated.to_string(), ..Default::default() });
+                continue;
+            }
+            let class = mappings.classes.last_mut().ok_or(ProGuardParseError::NoClass(number))?;
+            let (member_type, rest) = strip_line_numbers(left).split_once(' ').ok_or(ProGuardParseError::BadLine(number))?;
+            match rest.split_once('(') {
+                // `12:15:void setPos(net.minecraft.world.phys.Vec3,double):100:103 -> b`
+                Some((name, arguments)) => {
+                    // methods inlined from another class carry its name, the method they were inlined into is listed too
+                    if name.contains('.') {
+                        continue;
+                    }
+                    let arguments = arguments.split_once(')').ok_or(ProGuardParseError::BadLine(number))?.0;
+                    let method = ProGuardMethod {
+                        return_type: member_type.to_string(),
+                        name: name.to_string(),
+                        arguments: arguments.split(',').filter(|a| !a.is_empty()).map(str::to_string).collect(),
+                        obfuscated: obfuscated.to_string(),
+                    };
+                    // a method shows up once per line range
+                    if !class.methods.contains(&method) {
+                        class.methods.push(method);
+                    }
+                }
+                // `int fps -> q`
+                None => class.fields.push(ProGuardField { field_type: member_type.to_string(), name: rest.to_string(), obfuscated: obfuscated.to_string() }),
+            }
+        }
+        Ok(mappings)
+    }
+
+    /// the same mappings as a tree with the namespaces [`OFFICIAL`], [`IDS`] and [`NAMED`], which is what
+    /// the generator is loaded with
+    pub fn to_tree(&self) -> MappingTree {
+        // descriptors are in the first (obfuscated) namespace
+        let obfuscated = self.classes.iter()
+            .map(|c| (c.name.as_str(), c.obfuscated.as_str()))
+            .collect::<HashMap<_, _>>();
+        let mut tree = MappingTree::new(vec![OFFICIAL.to_string(), IDS.to_string(), NAMED.to_string()]);
+        for class in &self.classes {
+            let name = class.name.replace('.', "/");
+            let mut mapping = ClassMapping {
+                names: vec![class.obfuscated.replace('.', "/"), name.clone(), name],
+                ..Default::default()
+            };
+            for field in &class.fields {
+                mapping.fields.push(FieldMapping {
+                    descriptor: java_to_descriptor(&field.field_type, &obfuscated),
+                    names: vec![field.obfuscated.clone(), member_id("field", &[&class.name, &field.name]), field.name.clone()],
+                    comment: None,
+                });
+            }
+            for method in &class.methods {
+                if method.name == "<clinit>" {
+                    continue;
+                }
+                let id = if method.name == "<init>" {
+                    method.name.clone()
+                } else {
+                    member_id("method", &[&class.name, &method.name, &method.arguments.join(","), &method.return_type])
+                };
+                let arguments = method.arguments.iter().map(|a| java_to_descriptor(a, &obfuscated)).collect::<String>();
+                mapping.methods.push(MethodMapping {
+                    descriptor: format!("({arguments}){}", java_to_descriptor(&method.return_type, &obfuscated)),
+                    names: vec![method.obfuscated.clone(), id, method.name.clone()],
+                    ..Default::default()
+                });
+            }
+            tree.classes.push(mapping);
+        }
+        tree
+    }
+
+    /// the `obfuscated` and `named` namespaces of `tree` as ProGuard mappings, members missing a `named` name keep their obfuscated one.
+    /// fields without a descriptor are left out since ProGuard needs their type
+    pub fn from_tree(tree: &MappingTree, obfuscated: &str, named: &str) -> Result<Self, MappingError> {
+        let (obfuscated, named) = (tree.namespace_index(obfuscated)?, tree.namespace_index(named)?);
+        let names = tree.class_names(named);
+        let mut mappings = ProGuard::default();
+        for class in &tree.classes {
+            let obf = class.name(obfuscated).unwrap_or(&class.names[0]);
+            let mut out = ProGuardClass {
+                name: class.name(named).unwrap_or(obf).replace('/', "."),
+                obfuscated: obf.replace('/', "."),
+                ..Default::default()
+            };
+            for field in &class.fields {
+                let descriptor = MappingTree::remap_descriptor(&field.descriptor, &names);
+                let Some((field_type, "")) = descriptor_to_java(&descriptor) else { continue };
+                let obf = field.name(obfuscated).unwrap_or(&field.names[0]);
+                out.fields.push(ProGuardField { field_type, name: field.name(named).unwrap_or(obf).to_string(), obfuscated: obf.to_string() });
+            }
+            for method in &class.methods {
+                let Some((arguments, return_type)) = method_to_java(&MappingTree::remap_descriptor(&method.descriptor, &names)) else { continue };
+                let obf = method.name(obfuscated).unwrap_or(&method.names[0]);
+                out.methods.push(ProGuardMethod { return_type, name: method.name(named).unwrap_or(obf).to_string(), arguments, obfuscated: obf.to_string() });
+            }
+            mappings.classes.push(out);
+        }
+        Ok(mappings)
+    }
+
+    /// writes the mappings in the layout [`ProGuard::parse`] reads, without line numbers
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for class in &self.classes {
+            writeln!(writer, "{} -> {}:", class.name, class.obfuscated)?;
+            for field in &class.fields {
+                writeln!(writer, "    {} {} -> {}", field.field_type, field.name, field.obfuscated)?;
+            }
+            for method in &class.methods {
+                writeln!(writer, "    {} {}({}) -> {}", method.return_type, method.name, method.arguments.join(","), method.obfuscated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `<kind>_<hash>` of the readable owner, name and types of a member, so its id stays the same when other members come
+/// and go between versions. fnv-1a since std's hasher isn't guaranteed to give the same hash across rust versions
+fn member_id(kind: &str, parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in parts.join("\0").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{kind}_{hash:016x}")
+}
+
+/// drops the `12:15:` line range in front of a member and the `:100:103` original lines after a method
+fn strip_line_numbers(member: &str) -> &str {
+    let mut member = member;
+    while let Some((number, rest)) = member.split_once(':') {
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+        member = rest;
+    }
+    match member.rfind(')') {
+        Some(end) => &member[..=end],
+        None => member,
+    }
+}
+
+/// `int` -> `I`, `net.minecraft.world.phys.Vec3[]` -> `[Lphy;` (through `obfuscated`, readable -> obfuscated class names)
+pub fn java_to_descriptor(java: &str, obfuscated: &HashMap<&str, &str>) -> String {
+    let mut element = java.trim();
+    let mut out = String::new();
+    while let Some(inner) = element.strip_suffix("[]") {
+        out.push('[');
+        element = inner;
+    }
+    match element {
+        "void" => out.push('V'),
+        "boolean" => out.push('Z'),
+        "byte" => out.push('B'),
+        "char" => out.push('C'),
+        "short" => out.push('S'),
+        "int" => out.push('I'),
+        "long" => out.push('J'),
+        "float" => out.push('F'),
+        "double" => out.push('D'),
+        class => {
+            let class = obfuscated.get(class).copied().unwrap_or(class);
+            out.push('L');
+            out.push_str(&class.replace('.', "/"));
+            out.push(';');
+        }
+    }
+    out
+}
+
+/// the first type in `descriptor` as java and what is left after it, `[Lnet/minecraft/world/phys/Vec3;I` -> (`net.minecraft.world.phys.Vec3[]`, `I`)
+pub fn descriptor_to_java(descriptor: &str) -> Option<(String, &str)> {
+    let dimensions = descriptor.bytes().take_while(|b| *b == b'[').count();
+    let element = &descriptor[dimensions..];
+    let (java, rest) = match element.chars().next()? {
+        'V' => ("void".to_string(), &element[1..]),
+        'Z' => ("boolean".to_string(), &element[1..]),
+        'B' => ("byte".to_string(), &element[1..]),
+        'C' => ("char".to_string(), &element[1..]),
+        'S' => ("short".to_string(), &element[1..]),
+        'I' => ("int".to_string(), &element[1..]),
+        'J' => ("long".to_string(), &element[1..]),
+        'F' => ("float".to_string(), &element[1..]),
+        'D' => ("double".to_string(), &element[1..]),
+        'L' => {
+            let end = element.find(';')?;
+            (element[1..end].replace('/', "."), &element[end + 1..])
+        }
+        _ => return None,
+    };
+    Some((java + &"[]".repeat(dimensions), rest))
+}
+
+/// a method descriptor as java argument types and return type
+fn method_to_java(descriptor: &str) -> Option<(Vec<String>, String)> {
+    let mut rest = descriptor.strip_prefix('(')?;
+    let mut arguments = vec![];
+    while !rest.starts_with(')') {
+        let (argument, next) = descriptor_to_java(rest)?;
+        arguments.push(argument);
+        rest = next;
+    }
+    match descriptor_to_java(&rest[1..])? {
+        (return_type, "") => Some((arguments, return_type)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: &str = "# {\"id\":\"com.android.tools.r8.mapping\",\"version\":\"2.0\"}
+net.minecraft.world.phys.Vec3 -> eei:
+# {\"fileName\":\"Vec3.java\",\"id\":\"sourceFile\"}
+    double x -> c
+    1:4:void <init>(double,double,double) -> <init>
+    5:5:net.minecraft.world.phys.Vec3 add(net.minecraft.world.phys.Vec3):10:10 -> e
+    6:6:net.minecraft.world.phys.Vec3 add(net.minecraft.world.phys.Vec3):11:11 -> e
+    7:7:void net.minecraft.util.Mth.inlined():20:20 -> e
+    8:8:void <clinit>() -> <clinit>
+net.minecraft.client.Minecraft -> eev:
+    net.minecraft.world.phys.Vec3[][] path -> p
+    void setPos(net.minecraft.world.phys.Vec3,long,java.lang.String) -> b
+";
+
+    #[test]
+    fn parse() {
+        let mappings = ProGuard::parse(CLIENT.as_bytes()).unwrap();
+        let vec = &mappings.classes[0];
+        assert_eq!((vec.name.as_str(), vec.obfuscated.as_str()), ("net.minecraft.world.phys.Vec3", "eei"));
+        assert_eq!(vec.fields[0], ProGuardField { field_type: "double".into(), name: "x".into(), obfuscated: "c".into() });
+        // the second line range of `add` and the inlined method are dropped
+        assert_eq!(vec.methods.len(), 3);
+        assert_eq!(vec.methods[1], ProGuardMethod {
+            return_type: "net.minecraft.world.phys.Vec3".into(),
+            name: "add".into(),
+            arguments: vec!["net.minecraft.world.phys.Vec3".into()],
+            obfuscated: "e".into(),
+        });
+        assert_eq!(ProGuard::parse("    int x -> a".as_bytes()), Err(ProGuardParseError::NoClass(1)));
+        assert_eq!(ProGuard::parse("a.B\n".as_bytes()), Err(ProGuardParseError::BadLine(1)));
+    }
+
+    #[test]
+    fn tree() {
+        let tree = ProGuard::parse(CLIENT.as_bytes()).unwrap().to_tree();
+        let vec = &tree.classes[0];
+        assert_eq!(vec.names, ["eei", "net/minecraft/world/phys/Vec3", "net/minecraft/world/phys/Vec3"]);
+        assert_eq!(vec.fields[0].names, ["c", "field_aa8e186ce3e982fc", "x"]);
+        // no <clinit>
+        assert_eq!(vec.methods.len(), 2);
+        assert_eq!(vec.methods[0].names, ["<init>", "<init>", "<init>"]);
+        assert_eq!(vec.methods[0].descriptor, "(DDD)V");
+        assert_eq!(vec.methods[1].names, ["e", "method_fadcf0dfe3ffa013", "add"]);
+        assert_eq!(vec.methods[1].descriptor, "(Leei;)Leei;");
+        let client = &tree.classes[1];
+        assert_eq!(client.fields[0].descriptor, "[[Leei;");
+        assert_eq!(client.methods[0].descriptor, "(Leei;JLjava/lang/String;)V");
+
+        // ids don't move when an earlier class gains members
+        let mut grown = ProGuard::parse(CLIENT.as_bytes()).unwrap();
+        grown.classes[0].fields.push(ProGuardField { field_type: "double".into(), name: "y".into(), obfuscated: "d".into() });
+        let grown = grown.to_tree();
+        assert_eq!(grown.classes[1].fields[0].names, client.fields[0].names);
+        assert_eq!(grown.classes[1].methods[0].names, client.methods[0].names);
+    }
+
+    #[test]
+    fn write() {
+        let parsed = ProGuard::parse(CLIENT.as_bytes()).unwrap();
+        let mappings = ProGuard::from_tree(&parsed.to_tree(), OFFICIAL, NAMED).unwrap();
+        // everything but <clinit> makes it through the tree
+        let mut expected = parsed.clone();
+        expected.classes[0].methods.pop();
+        assert_eq!(mappings, expected);
+        assert_eq!(ProGuard::from_tree(&parsed.to_tree(), OFFICIAL, "yarn"), Err(MappingError::UnknownNamespace("yarn".into())));
+
+        let mut written = vec![];
+        mappings.write(&mut written).unwrap();
+        assert!(String::from_utf8(written.clone()).unwrap().starts_with("net.minecraft.world.phys.Vec3 -> eei:\n    double x -> c\n    void <init>(double,double,double) -> <init>\n"));
+        assert_eq!(ProGuard::parse(written.as_slice()).unwrap(), mappings);
+        assert_eq!(descriptor_to_java("[[Leei;I"), Some(("eei[][]".to_string(), "I")));
+    }
+}
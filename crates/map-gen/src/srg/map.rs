@@ -0,0 +1,444 @@
+use std::{collections::HashMap, fmt, io::{self, BufRead, Write}};
+
+use crate::mapping::tree::{ClassMapping, FieldMapping, MappingTree, MethodMapping, ParameterMapping};
+
+/// the namespaces of the two column formats (srg, csrg and tsrg), obfuscated and srg names
+pub const OBF: &str = "obf";
+pub const SRG: &str = "srg";
+
+/// The SRG family of mappings Forge / MCP use, see [`read`].
+/// they are all read into a [`MappingTree`] so they go through [`crate::generator::Generator::load`]
+/// like any other mappings, with the namespaces picked there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrgFormat {
+    /// `CL: a net/minecraft/client/Minecraft`, `FD: a/b net/minecraft/client/Minecraft/f_90981_` and `MD: ..` lines
+    Srg,
+    /// compact srg: `a net/minecraft/client/Minecraft`, `a b f_90981_` and `a b (I)V m_91087_`
+    Csrg,
+    /// csrg with the members indented under their class
+    Tsrg,
+    /// `tsrg2 obf srg id` and any number of namespaces, with field descriptors, parameters and static markers
+    Tsrg2,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SrgParseError {
+    /// a line (1 based) with the wrong number of columns or an unknown `XX:` prefix
+    BadLine(usize),
+    /// a member line (1 based) that does not belong to a class
+    NoClass(usize),
+    BadNumber(usize),
+    Io(String),
+}
+
+impl fmt::Display for SrgParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SrgParseError::BadLine(line) => write!(f, "can't read line {line}"),
+            SrgParseError::NoClass(line) => write!(f, "member outside of a class on line {line}"),
+            SrgParseError::BadNumber(line) => write!(f, "bad number on line {line}"),
+            SrgParseError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SrgParseError {}
+
+impl SrgFormat {
+    /// guesses the format from the lines of a file: a `tsrg2` header, `XX:` prefixes or indented members
+    pub fn detect(lines: &[String]) -> Option<Self> {
+        let first = lines.iter().find(|l| !is_comment(l))?;
+        if first.starts_with("tsrg2 ") {
+            Some(SrgFormat::Tsrg2)
+        } else if ["PK: ", "CL: ", "FD: ", "MD: "].iter().any(|p| first.starts_with(p)) {
+            Some(SrgFormat::Srg)
+        } else if lines.iter().any(|l| l.starts_with(['\t', ' '])) {
+            Some(SrgFormat::Tsrg)
+        } else {
+            Some(SrgFormat::Csrg)
+        }
+    }
+}
+
+/// reads srg, csrg, tsrg or tsrg2, whichever `reader` turns out to be (see [`SrgFormat::detect`])
+pub fn read<R: BufRead>(reader: R) -> Result<MappingTree, SrgParseError> {
+    let lines = reader.lines().collect::<Result<Vec<_>, _>>().map_err(|e| SrgParseError::Io(e.to_string()))?;
+    match SrgFormat::detect(&lines) {
+        Some(format) => parse_lines(&lines, format),
+        None => Ok(two_columns()),
+    }
+}
+
+pub fn parse<R: BufRead>(reader: R, format: SrgFormat) -> Result<MappingTree, SrgParseError> {
+    let lines = reader.lines().collect::<Result<Vec<_>, _>>().map_err(|e| SrgParseError::Io(e.to_string()))?;
+    parse_lines(&lines, format)
+}
+
+fn parse_lines(lines: &[String], format: SrgFormat) -> Result<MappingTree, SrgParseError> {
+    match format {
+        SrgFormat::Srg => parse_srg(lines),
+        SrgFormat::Csrg => parse_csrg(lines),
+        SrgFormat::Tsrg => parse_tsrg(lines),
+        SrgFormat::Tsrg2 => parse_tsrg2(lines),
+    }
+}
+
+/// `#` comments, and blank lines
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn two_columns() -> MappingTree {
+    MappingTree::new(vec![OBF.to_string(), SRG.to_string()])
+}
+
+/// the class called `name` in the first namespace, added if srg lists its members before (or without) a `CL:` line
+/// `classes` maps first namespace names to their index in `tree.classes`
+fn class_mut<'a>(tree: &'a mut MappingTree, classes: &mut HashMap<String, usize>, name: &str) -> &'a mut ClassMapping {
+    let index = *classes.entry(name.to_string()).or_insert_with(|| {
+        tree.classes.push(ClassMapping { names: vec![name.to_string(), String::new()], ..Default::default() });
+        tree.classes.len() - 1
+    });
+    &mut tree.classes[index]
+}
+
+/// `net/minecraft/client/Minecraft/f_90981_` -> (`net/minecraft/client/Minecraft`, `f_90981_`)
+fn split_member(line: usize, path: &str) -> Result<(&str, &str), SrgParseError> {
+    path.rsplit_once('/').ok_or(SrgParseError::BadLine(line))
+}
+
+fn parse_srg(lines: &[String]) -> Result<MappingTree, SrgParseError> {
+    let mut tree = two_columns();
+    let mut classes = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        if is_comment(line) {
+            continue;
+        }
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        match columns.as_slice() {
+            ["PK:", ..] => {}
+            ["CL:", obf, srg] => class_mut(&mut tree, &mut classes, obf).names[1] = srg.to_string(),
+            // xsrg adds the descriptors, plain srg has none
+            ["FD:", obf, srg] | ["FD:", obf, _, srg, _] => {
+                let (class, obf) = split_member(number, obf)?;
+                let srg = split_member(number, srg)?.1;
+                let descriptor = if columns.len() == 5 { columns[2].to_string() } else { String::new() };
+                class_mut(&mut tree, &mut classes, class).fields.push(FieldMapping { descriptor, names: vec![obf.to_string(), srg.to_string()], comment: None });
+            }
+            ["MD:", obf, descriptor, srg, _] => {
+                let (class, obf) = split_member(number, obf)?;
+                let srg = split_member(number, srg)?.1;
+                class_mut(&mut tree, &mut classes, class).methods.push(MethodMapping {
+                    descriptor: descriptor.to_string(),
+                    names: vec![obf.to_string(), srg.to_string()],
+                    ..Default::default()
+                });
+            }
+            _ => return Err(SrgParseError::BadLine(number)),
+        }
+    }
+    Ok(tree)
+}
+
+fn parse_csrg(lines: &[String]) -> Result<MappingTree, SrgParseError> {
+    let mut tree = two_columns();
+    let mut classes = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        if is_comment(line) {
+            continue;
+        }
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            // packages end with a slash
+            [obf, _] if obf.ends_with('/') => {}
+            [obf, srg] => class_mut(&mut tree, &mut classes, obf).names[1] = srg.to_string(),
+            [class, obf, srg] => class_mut(&mut tree, &mut classes, class).fields.push(FieldMapping {
+                descriptor: String::new(),
+                names: vec![obf.to_string(), srg.to_string()],
+                comment: None,
+            }),
+            [class, obf, descriptor, srg] => class_mut(&mut tree, &mut classes, class).methods.push(MethodMapping {
+                descriptor: descriptor.to_string(),
+                names: vec![obf.to_string(), srg.to_string()],
+                ..Default::default()
+            }),
+            _ => return Err(SrgParseError::BadLine(number)),
+        }
+    }
+    Ok(tree)
+}
+
+fn parse_tsrg(lines: &[String]) -> Result<MappingTree, SrgParseError> {
+    let mut tree = two_columns();
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        if is_comment(line) {
+            continue;
+        }
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        if !line.starts_with(['\t', ' ']) {
+            match columns.as_slice() {
+                [obf, _] if obf.ends_with('/') => {}
+                [obf, srg] => tree.classes.push(ClassMapping { names: vec![obf.to_string(), srg.to_string()], ..Default::default() }),
+                _ => return Err(SrgParseError::BadLine(number)),
+            }
+            continue;
+        }
+        let class = tree.classes.last_mut().ok_or(SrgParseError::NoClass(number))?;
+        match columns.as_slice() {
+            [obf, srg] => class.fields.push(FieldMapping { descriptor: String::new(), names: vec![obf.to_string(), srg.to_string()], comment: None }),
+            [obf, descriptor, srg] => class.methods.push(MethodMapping {
+                descriptor: descriptor.to_string(),
+                names: vec![obf.to_string(), srg.to_string()],
+                ..Default::default()
+            }),
+            _ => return Err(SrgParseError::BadLine(number)),
+        }
+    }
+    Ok(tree)
+}
+
+fn parse_tsrg2(lines: &[String]) -> Result<MappingTree, SrgParseError> {
+    let mut lines = lines.iter().enumerate().filter(|(_, l)| !is_comment(l));
+    let (_, header) = lines.next().ok_or(SrgParseError::BadLine(1))?;
+    let namespaces = header.split_whitespace().skip(1).map(str::to_string).collect::<Vec<_>>();
+    if namespaces.len() < 2 {
+        return Err(SrgParseError::BadLine(1));
+    }
+    let count = namespaces.len();
+    let mut tree = MappingTree::new(namespaces);
+    let names = |columns: &[&str]| columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    // the last member line, parameters and `static` only follow methods
+    let mut in_method = false;
+    for (i, line) in lines {
+        let number = i + 1;
+        let depth = line.len() - line.trim_start_matches('\t').len();
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        match depth {
+            0 if columns.len() == count => {
+                tree.classes.push(ClassMapping { names: names(&columns), ..Default::default() });
+                in_method = false;
+            }
+            1 => {
+                let class = tree.classes.last_mut().ok_or(SrgParseError::NoClass(number))?;
+                in_method = false;
+                if columns.len() == count {
+                    class.fields.push(FieldMapping { descriptor: String::new(), names: names(&columns), comment: None });
+                } else if columns.len() == count + 1 && columns[1].starts_with('(') {
+                    // methods without the `static` line are instance methods
+                    class.methods.push(MethodMapping { descriptor: columns[1].to_string(), names: names(&[&columns[..1], &columns[2..]].concat()), is_static: Some(false), ..Default::default() });
+                    in_method = true;
+                } else if columns.len() == count + 1 {
+                    class.fields.push(FieldMapping { descriptor: columns[1].to_string(), names: names(&[&columns[..1], &columns[2..]].concat()), comment: None });
+                } else {
+                    return Err(SrgParseError::BadLine(number));
+                }
+            }
+            2 if in_method => {
+                let method = tree.classes.last_mut().and_then(|c| c.methods.last_mut()).ok_or(SrgParseError::NoClass(number))?;
+                match columns.as_slice() {
+                    ["static"] => method.is_static = Some(true),
+                    // the parameter indices are already local variable slots
+                    [index, rest @ ..] if rest.len() == count => method.parameters.push(ParameterMapping {
+                        lv_index: index.parse().map_err(|_| SrgParseError::BadNumber(number))?,
+                        names: names(rest),
+                        comment: None,
+                    }),
+                    _ => return Err(SrgParseError::BadLine(number)),
+                }
+            }
+            _ => return Err(SrgParseError::BadLine(number)),
+        }
+    }
+    Ok(tree)
+}
+
+/// writes `tree` in `format`. srg, csrg and tsrg hold its first two namespaces, names missing from the second keep the first one.
+/// tsrg2 holds every namespace along with field descriptors, parameters and which methods are static
+pub fn write<W: Write>(tree: &MappingTree, format: SrgFormat, mut writer: W) -> io::Result<()> {
+    if format == SrgFormat::Tsrg2 {
+        return write_tsrg2(tree, writer);
+    }
+    let names = tree.class_names(1);
+    let named = |names: &[String]| -> String { names.get(1).filter(|n| !n.is_empty()).unwrap_or(&names[0]).clone() };
+    for class in &tree.classes {
+        let (obf, srg) = (&class.names[0], named(&class.names));
+        match format {
+            SrgFormat::Srg if class.name(1).is_some() => writeln!(writer, "CL: {obf} {srg}")?,
+            SrgFormat::Csrg if class.name(1).is_some() => writeln!(writer, "{obf} {srg}")?,
+            // members are nested under their class, so it is written even without a name
+            SrgFormat::Tsrg => writeln!(writer, "{obf} {srg}")?,
+            _ => {}
+        }
+        for field in &class.fields {
+            let (from, to) = (&field.names[0], named(&field.names));
+            match format {
+                SrgFormat::Srg if field.descriptor.is_empty() => writeln!(writer, "FD: {obf}/{from} {srg}/{to}")?,
+                SrgFormat::Srg => {
+                    let remapped = MappingTree::remap_descriptor(&field.descriptor, &names);
+                    writeln!(writer, "FD: {obf}/{from} {} {srg}/{to} {remapped}", field.descriptor)?
+                }
+                SrgFormat::Csrg => writeln!(writer, "{obf} {from} {to}")?,
+                _ => writeln!(writer, "\t{from} {to}")?,
+            }
+        }
+        for method in &class.methods {
+            let (from, to, descriptor) = (&method.names[0], named(&method.names), &method.descriptor);
+            match format {
+                SrgFormat::Srg => writeln!(writer, "MD: {obf}/{from} {descriptor} {srg}/{to} {}", MappingTree::remap_descriptor(descriptor, &names))?,
+                SrgFormat::Csrg => writeln!(writer, "{obf} {from} {descriptor} {to}")?,
+                _ => writeln!(writer, "\t{from} {descriptor} {to}")?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the names of something as tsrg2 columns, which can't be empty, so missing names are filled with the first name there is
+fn tsrg2_columns(names: &[String], count: usize) -> Option<String> {
+    let first = names.iter().find(|n| !n.is_empty())?;
+    Some((0..count).map(|i| names.get(i).filter(|n| !n.is_empty()).unwrap_or(first).as_str()).collect::<Vec<_>>().join(" "))
+}
+
+fn write_tsrg2<W: Write>(tree: &MappingTree, mut writer: W) -> io::Result<()> {
+    let count = tree.namespaces.len();
+    writeln!(writer, "tsrg2 {}", tree.namespaces.join(" "))?;
+    for class in &tree.classes {
+        let Some(names) = tsrg2_columns(&class.names, count) else { continue };
+        writeln!(writer, "{names}")?;
+        for field in &class.fields {
+            let Some(names) = tsrg2_columns(&field.names, count) else { continue };
+            match names.split_once(' ') {
+                Some((obf, rest)) if !field.descriptor.is_empty() => writeln!(writer, "\t{obf} {} {rest}", field.descriptor)?,
+                _ => writeln!(writer, "\t{names}")?,
+            }
+        }
+        for method in &class.methods {
+            let Some(names) = tsrg2_columns(&method.names, count) else { continue };
+            let (obf, rest) = names.split_once(' ').unwrap_or((&names, ""));
+            writeln!(writer, "\t{obf} {} {rest}", method.descriptor)?;
+            if method.is_static == Some(true) {
+                writeln!(writer, "\t\tstatic")?;
+            }
+            for parameter in &method.parameters {
+                if let Some(names) = tsrg2_columns(&parameter.names, count) {
+                    writeln!(writer, "\t\t{} {names}", parameter.lv_index)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_str(s: &str) -> MappingTree {
+        read(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn srg() {
+        let tree = read_str("PK: ./ net/minecraft
+CL: eei net/minecraft/world/phys/Vec3
+FD: eei/c net/minecraft/world/phys/Vec3/f_82479_
+MD: eei/e (Leei;)Leei; net/minecraft/world/phys/Vec3/m_82549_ (Lnet/minecraft/world/phys/Vec3;)Lnet/minecraft/world/phys/Vec3;
+FD: eev/p [Leei; net/minecraft/client/Minecraft/f_90981_ [Lnet/minecraft/world/phys/Vec3;
+");
+        assert_eq!(tree.namespaces, [OBF, SRG]);
+        let vec = &tree.classes[0];
+        assert_eq!(vec.names, ["eei", "net/minecraft/world/phys/Vec3"]);
+        assert_eq!(vec.fields[0], FieldMapping { descriptor: String::new(), names: vec!["c".into(), "f_82479_".into()], comment: None });
+        assert_eq!((vec.methods[0].descriptor.as_str(), vec.methods[0].name(1)), ("(Leei;)Leei;", Some("m_82549_")));
+        // members of a class without a CL: line
+        let client = &tree.classes[1];
+        assert_eq!((client.name(0), client.name(1)), (Some("eev"), None));
+        assert_eq!(client.fields[0].descriptor, "[Leei;");
+        assert_eq!(read("CL: a".as_bytes()), Err(SrgParseError::BadLine(1)));
+    }
+
+    #[test]
+    fn csrg_and_tsrg() {
+        let csrg = read_str("./ net/minecraft/
+eei net/minecraft/world/phys/Vec3
+eei c f_82479_
+eei e (Leei;)Leei; m_82549_
+");
+        let tsrg = read_str("# comment
+eei net/minecraft/world/phys/Vec3
+\tc f_82479_
+\te (Leei;)Leei; m_82549_
+");
+        assert_eq!(SrgFormat::detect(&["a b".to_string(), "\tc d".to_string()]), Some(SrgFormat::Tsrg));
+        assert_eq!(csrg, tsrg);
+        assert_eq!(tsrg.classes[0].methods[0].names, ["e", "m_82549_"]);
+        assert_eq!(read("\tc d".as_bytes()), Err(SrgParseError::NoClass(1)));
+    }
+
+    #[test]
+    fn tsrg2() {
+        let tree = read_str("tsrg2 obf srg id
+eei net/minecraft/world/phys/Vec3 1234
+\tc D f_82479_ 4567
+\td f_82480_ 4568
+\te (Leei;)Leei; m_82549_ 8901
+\t\t1 a p_82550_ 9000
+\t<init> (DDD)V <init> 8902
+\ta (DDD)Leei; m_82498_ 8903
+\t\tstatic
+\t\t0 a p_82499_ 9001
+");
+        assert_eq!(tree.namespaces, ["obf", "srg", "id"]);
+        let vec = &tree.classes[0];
+        assert_eq!(vec.names, ["eei", "net/minecraft/world/phys/Vec3", "1234"]);
+        assert_eq!(vec.fields[0].descriptor, "D");
+        assert_eq!(vec.fields[1].names, ["d", "f_82480_", "4568"]);
+        assert_eq!(vec.methods[0].names, ["e", "m_82549_", "8901"]);
+        assert_eq!(vec.methods[0].parameters[0], ParameterMapping { lv_index: 1, names: vec!["a".into(), "p_82550_".into(), "9000".into()], comment: None });
+        assert_eq!(vec.methods[1].descriptor, "(DDD)V");
+        assert_eq!(vec.methods.iter().map(|m| m.is_static).collect::<Vec<_>>(), [Some(false), Some(false), Some(true)]);
+        assert_eq!(vec.methods[2].parameters[0].lv_index, 0);
+        assert_eq!(read("tsrg2 obf srg\na b\n\t\tstatic".as_bytes()), Err(SrgParseError::BadLine(3)));
+    }
+
+    #[test]
+    fn write_formats() {
+        let round_trip = |tree: &MappingTree, format| {
+            let mut written = vec![];
+            write(tree, format, &mut written).unwrap();
+            assert_eq!(SrgFormat::detect(&written.as_slice().lines().collect::<Result<Vec<_>, _>>().unwrap()), Some(format));
+            parse(written.as_slice(), format).unwrap()
+        };
+        let srg = read_str("CL: eei net/minecraft/world/phys/Vec3
+FD: eei/c net/minecraft/world/phys/Vec3/f_82479_
+MD: eei/e (Leei;)Leei; net/minecraft/world/phys/Vec3/m_82549_ (Lnet/minecraft/world/phys/Vec3;)Lnet/minecraft/world/phys/Vec3;
+FD: eev/p [Leei; net/minecraft/client/Minecraft/f_90981_ [Lnet/minecraft/world/phys/Vec3;
+");
+        assert_eq!(round_trip(&srg, SrgFormat::Srg), srg);
+        let mut written = vec![];
+        write(&srg, SrgFormat::Srg, &mut written).unwrap();
+        assert!(String::from_utf8(written).unwrap().ends_with("FD: eev/p [Leei; eev/f_90981_ [Lnet/minecraft/world/phys/Vec3;\n"));
+
+        let tsrg = read_str("eei net/minecraft/world/phys/Vec3
+\tc f_82479_
+\te (Leei;)Leei; m_82549_
+");
+        assert_eq!(round_trip(&tsrg, SrgFormat::Csrg), tsrg);
+        assert_eq!(round_trip(&tsrg, SrgFormat::Tsrg), tsrg);
+
+        let tsrg2 = read_str("tsrg2 obf srg id
+eei net/minecraft/world/phys/Vec3 1234
+\tc D f_82479_ 4567
+\td f_82480_ 4568
+\te (Leei;)Leei; m_82549_ 8901
+\t\t1 a p_82550_ 9000
+\ta (DDD)Leei; m_82498_ 8903
+\t\tstatic
+\t\t0 a p_82499_ 9001
+");
+        assert_eq!(round_trip(&tsrg2, SrgFormat::Tsrg2), tsrg2);
+    }
+}
@@ -1,126 +1,44 @@
 use std::{collections::HashMap, io::BufRead, sync::Arc};
 use parking_lot as pl;
 
+use super::{v1, v2::{TinyV2, TinyParseError}};
+use crate::mapping::tree::{MappingError, MappingTree};
 
-#[derive(Debug, Clone)]
-pub struct Tiny {
-    pub(crate) lookup:  Arc<pl::RwLock<HashMap<String, LookupType>>>
-}
-
-#[derive(Debug, Clone)]
-
-pub enum LookupType {
-    Class(Class),
-    Method(Method),
-    Field(Field),
-}
-#[derive(Debug, Clone)]
-
-pub struct Class {
-    pub(crate) from: String,
-    pub(crate) to: String,
-}
-#[derive(Debug, Clone)]
 
-pub struct Method {
-    pub(crate) parent: String,
-    pub(crate) Signature: String,
-    pub(crate) Obfuscated: String,
-    pub(crate) Yarn: String,
-}
-#[derive(Debug, Clone)]
-
-pub struct Field {
-    pub(crate) parent: String,
-    pub(crate) Signature: String,
-    pub(crate) Obfuscated: String,
-    pub(crate) Yarn: String,
-}
-
-impl LookupType {
-    pub fn get_obfuscated(&self) -> String {
-        match self {
-            LookupType::Class(class) => class.from.clone(),
-            LookupType::Method(method) => method.Obfuscated.clone(),
-            LookupType::Field(field) => field.Obfuscated.clone(),
-        }
-    }
-    pub fn get_yarn(&self) -> String {
-        match self {
-            LookupType::Class(class) => class.to.clone(),
-            LookupType::Method(method) => method.Yarn.clone(),
-            LookupType::Field(field) => field.Yarn.clone(),
-        }
-    }
-    pub fn get_signature(&self) -> String {
-        match self {
-            LookupType::Class(class) => class.to.clone(),
-            LookupType::Method(method) => method.Signature.clone(),
-            LookupType::Field(field) => field.Signature.clone(),
-        }
-    }
+/// the obfuscated names of classes by their intermediary names, what mci needs to find a class the jvm loads
+#[derive(Debug, Clone, Default)]
+pub struct Tiny {
+    pub(crate) classes:  Arc<pl::RwLock<HashMap<String, String>>>
 }
 
-
 impl Tiny {
     pub fn new() -> Self {
         Self {
-            lookup: Default::default(),
+            classes: Default::default(),
         }
     }
-    pub fn populate_from_reader<T: BufRead>(&self, reader :T) {
-        let lines = reader.lines();
-
-        let lookup = self.lookup.clone();
-
-        for line in lines {
-            if let Ok(line) = line {
-
-                let mut data = line.split_whitespace();
-                let cmd = data.nth(0).unwrap_or("");
-    
-                match cmd {
-    
-                    "CLASS" => {
-                        let obf = data.nth(0).unwrap_or("");
-                        let deobf = data.nth(0).unwrap_or("");
-                        let sc = Class {
-                            from: obf.to_string(),
-                            to: deobf.to_string(),
-                        };
-                        lookup.write().insert(format!("{}_c",deobf), LookupType::Class(sc));
-                    }
-                    "METHOD" => {
-                        let class_obf = data.nth(0).unwrap_or("");
-                        let sig = data.nth(0).unwrap_or("");
-                        let obf = data.nth(0).unwrap_or("");
-                        let id = data.nth(0).unwrap_or("");
-                        let sm = Method{
-                            Obfuscated: obf.to_string(),
-                            Yarn: id.to_string(),
-                            Signature: sig.to_string(),
-                            parent: class_obf.to_string(),
-                        };
-                        lookup.write().insert(format!("{}_m",id), LookupType::Method(sm));
-                    }
-                    "FIELD" => {
-                        let class_obf = data.nth(0).unwrap_or("");
-                        let sig = data.nth(0).unwrap_or("");
-                        let obf = data.nth(0).unwrap_or("");
-                        let id = data.nth(0).unwrap_or("");
-    
-                        let sf = Field{
-                            Obfuscated: obf.to_string(),
-                            Yarn: id.to_string(),
-                            Signature: sig.to_string(),
-                            parent: class_obf.to_string(),
-                        };
-                        lookup.write().insert(format!("{}_f",id), LookupType::Field(sf));
-                    }
-    
-                    _=>{}
-                }
+    /// adds the classes of a mapping tree that have a name in both `obfuscated` and `intermediary`
+    fn populate_from_tree(&self, tree: &MappingTree, obfuscated: &str, intermediary: &str) -> Result<(), MappingError> {
+        let obf = tree.namespace_index(obfuscated)?;
+        let int = tree.namespace_index(intermediary)?;
+        let mut classes = self.classes.write();
+        for class in &tree.classes {
+            if let (Some(class_obf), Some(class_int)) = (class.name(obf), class.name(int)) {
+                classes.insert(class_int.to_string(), class_obf.to_string());
             }
         }
+        Ok(())
     }
-}
\ No newline at end of file
+    /// the obfuscated name of the class with the intermediary name `name`, eg. `net/minecraft/class_310`
+    pub fn obfuscated_class(&self, name: &str) -> Option<String> {
+        self.classes.read().get(&name.replace('.', "/")).cloned()
+    }
+    /// reads a tiny v1 or v2 file, whichever the header says, with the namespaces `official` and `intermediary`
+    pub fn populate_from_reader<T: BufRead>(&self, mut reader :T) -> Result<(), TinyParseError> {
+        let v2 = reader.fill_buf().map_err(|e| TinyParseError::Io(e.to_string()))?.starts_with(b"tiny\t2\t");
+        let tree = if v2 { TinyV2::parse(reader)?.mappings } else { v1::parse(reader)? };
+        self.populate_from_tree(&tree, "official", "intermediary").map_err(|e| match e {
+            MappingError::UnknownNamespace(ns) | MappingError::DuplicateNamespace(ns) => TinyParseError::MissingNamespace(ns),
+        })
+    }
+}
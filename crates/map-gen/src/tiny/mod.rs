@@ -1 +1,3 @@
-pub mod map;
\ No newline at end of file
+pub mod map;
+pub mod v1;
+pub mod v2;
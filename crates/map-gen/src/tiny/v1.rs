@@ -0,0 +1,119 @@
+use std::{collections::HashMap, io::{self, BufRead, Write}};
+
+use crate::mapping::tree::{ClassMapping, FieldMapping, MappingTree, MethodMapping};
+
+use super::v2::TinyParseError;
+
+/// reads a tiny v1 file (`v1 official intermediary`, then `CLASS`, `FIELD` and `METHOD` lines) into a [`MappingTree`].
+/// members name their class in the first namespace, classes that only show up that way get no other names
+pub fn parse<R: BufRead>(reader: R) -> Result<MappingTree, TinyParseError> {
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or(TinyParseError::BadHeader)?.map_err(|e| TinyParseError::Io(e.to_string()))?;
+    let header = header.trim_end_matches('\r').split('\t').collect::<Vec<_>>();
+    if header.len() < 3 || header[0] != "v1" {
+        return Err(TinyParseError::BadHeader);
+    }
+    let mut tree = MappingTree::new(header[1..].iter().map(|ns| ns.to_string()).collect());
+    let count = tree.namespaces.len();
+    let names = |columns: &[&str]| (0..count).map(|i| columns.get(i).copied().unwrap_or("").to_string()).collect::<Vec<_>>();
+    // first namespace name -> index in tree.classes
+    let mut classes = HashMap::new();
+    for (i, line) in lines.enumerate() {
+        let line = line.map_err(|e| TinyParseError::Io(e.to_string()))?;
+        let number = i + 2;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns = line.split('\t').collect::<Vec<_>>();
+        let mut class = |name: &str| -> usize {
+            *classes.entry(name.to_string()).or_insert_with(|| {
+                tree.classes.push(ClassMapping { names: names(&[name]), ..Default::default() });
+                tree.classes.len() - 1
+            })
+        };
+        match columns.as_slice() {
+            ["CLASS", rest @ ..] if !rest.is_empty() => {
+                let index = class(rest[0]);
+                tree.classes[index].names = names(rest);
+            }
+            ["FIELD", owner, descriptor, rest @ ..] if !rest.is_empty() => {
+                let index = class(owner);
+                tree.classes[index].fields.push(FieldMapping { descriptor: descriptor.to_string(), names: names(rest), comment: None });
+            }
+            ["METHOD", owner, descriptor, rest @ ..] if !rest.is_empty() => {
+                let index = class(owner);
+                tree.classes[index].methods.push(MethodMapping { descriptor: descriptor.to_string(), names: names(rest), ..Default::default() });
+            }
+            [kind, ..] if ["CLASS", "FIELD", "METHOD"].contains(kind) => return Err(TinyParseError::MissingColumn(number)),
+            _ => return Err(TinyParseError::UnexpectedLine(number)),
+        }
+    }
+    Ok(tree)
+}
+
+/// writes `tree` as a tiny v1 file, the layout [`parse`] reads.
+/// v1 has no parameters, locals or comments, and leaves out constructors
+pub fn write<W: Write>(tree: &MappingTree, mut writer: W) -> io::Result<()> {
+    let count = tree.namespaces.len();
+    let names = |names: &[String]| (0..count).map(|i| names.get(i).map_or("", String::as_str)).collect::<Vec<_>>().join("\t");
+    writeln!(writer, "v1\t{}", tree.namespaces.join("\t"))?;
+    for class in &tree.classes {
+        let owner = class.names.first().map_or("", String::as_str);
+        writeln!(writer, "CLASS\t{}", names(&class.names))?;
+        for field in &class.fields {
+            writeln!(writer, "FIELD\t{owner}\t{}\t{}", field.descriptor, names(&field.names))?;
+        }
+        for method in class.methods.iter().filter(|m| !m.names.first().is_some_and(|n| n.starts_with('<'))) {
+            writeln!(writer, "METHOD\t{owner}\t{}\t{}", method.descriptor, names(&method.names))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_v1() {
+        let tree = parse("v1\tofficial\tintermediary
+FIELD\teev\tLeev;\tE\tfield_1700
+CLASS\teev\tnet/minecraft/class_310
+METHOD\teev\t()Leev;\tG\tmethod_1551
+".as_bytes()).unwrap();
+        assert_eq!(tree.namespaces, ["official", "intermediary"]);
+        // the field came before its class
+        assert_eq!(tree.classes.len(), 1);
+        let client = &tree.classes[0];
+        assert_eq!(client.names, ["eev", "net/minecraft/class_310"]);
+        assert_eq!(client.fields[0].names, ["E", "field_1700"]);
+        assert_eq!((client.methods[0].descriptor.as_str(), client.methods[0].name(1)), ("()Leev;", Some("method_1551")));
+        assert_eq!(parse("tiny\t2\t0\ta\tb".as_bytes()), Err(TinyParseError::BadHeader));
+        assert_eq!(parse("v1\ta\tb\nMETHOD\teev".as_bytes()), Err(TinyParseError::MissingColumn(2)));
+    }
+
+    #[test]
+    fn write_v1() {
+        let mut tree = MappingTree::new(vec!["official".into(), "intermediary".into()]);
+        tree.classes.push(ClassMapping {
+            names: vec!["eev".into(), "net/minecraft/class_310".into()],
+            fields: vec![FieldMapping { descriptor: "Leev;".into(), names: vec!["E".into(), "field_1700".into()], comment: Some("dropped".into()) }],
+            methods: vec![
+                MethodMapping { descriptor: "()V".into(), names: vec!["<init>".into(), "<init>".into()], ..Default::default() },
+                MethodMapping { descriptor: "()Leev;".into(), names: vec!["G".into(), "method_1551".into()], ..Default::default() },
+            ],
+            comment: None,
+        });
+        let mut written = vec![];
+        write(&tree, &mut written).unwrap();
+        assert_eq!(String::from_utf8(written.clone()).unwrap(), "v1\tofficial\tintermediary
+CLASS\teev\tnet/minecraft/class_310
+FIELD\teev\tLeev;\tE\tfield_1700
+METHOD\teev\t()Leev;\tG\tmethod_1551
+");
+        let read = parse(written.as_slice()).unwrap();
+        assert_eq!(read.classes[0].methods.len(), 1);
+        assert_eq!(read.classes[0].fields[0].names, tree.classes[0].fields[0].names);
+    }
+}
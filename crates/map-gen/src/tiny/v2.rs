@@ -0,0 +1,365 @@
+use std::{fmt, io::{self, BufRead, Write}};
+
+use crate::mapping::tree::{ClassMapping, FieldMapping, LocalMapping, MappingTree, MethodMapping, ParameterMapping};
+
+/// A tiny v2 file (`tiny 2 0 official intermediary named`), see https://fabricmc.net/wiki/documentation:tiny2.
+/// the header and the mappings it holds
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TinyV2 {
+    pub major_version: u32,
+    pub minor_version: u32,
+    /// header properties such as `escaped-names`, with their value if they have one
+    pub properties: Vec<(String, Option<String>)>,
+    pub mappings: MappingTree,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TinyParseError {
+    /// the first line is not `tiny 2 <minor> <namespaces..>`
+    BadHeader,
+    /// a line (1 based) does not fit where it is, eg. an `m` line outside of a class
+    UnexpectedLine(usize),
+    /// a line is missing columns
+    MissingColumn(usize),
+    BadNumber(usize),
+    /// the header lacks a namespace the mappings are read from, eg. `intermediary`
+    MissingNamespace(String),
+    Io(String),
+}
+
+impl fmt::Display for TinyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TinyParseError::BadHeader => write!(f, "not a tiny v2 file"),
+            TinyParseError::UnexpectedLine(line) => write!(f, "unexpected line {line}"),
+            TinyParseError::MissingColumn(line) => write!(f, "missing column on line {line}"),
+            TinyParseError::BadNumber(line) => write!(f, "bad number on line {line}"),
+            TinyParseError::MissingNamespace(ns) => write!(f, "no namespace called {ns}"),
+            TinyParseError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for TinyParseError {}
+
+/// the line being parsed, split into columns after its indentation
+struct Line<'a> {
+    number: usize,
+    columns: Vec<&'a str>,
+}
+
+impl<'a> Line<'a> {
+    fn column(&self, index: usize) -> Result<&'a str, TinyParseError> {
+        self.columns.get(index).copied().ok_or(TinyParseError::MissingColumn(self.number))
+    }
+    fn number(&self, index: usize) -> Result<u32, TinyParseError> {
+        self.column(index)?.parse().map_err(|_| TinyParseError::BadNumber(self.number))
+    }
+    /// `count` names starting at column `from`, trailing empty names may be left out
+    fn names(&self, from: usize, count: usize, escaped: bool) -> Vec<String> {
+        (0..count).map(|i| {
+            let name = self.columns.get(from + i).copied().unwrap_or("");
+            if escaped { unescape(name) } else { name.to_string() }
+        }).collect()
+    }
+}
+
+impl TinyV2 {
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self, TinyParseError> {
+        Self::parse_lines(reader.lines().map(|l| l.map_err(|e| TinyParseError::Io(e.to_string()))))
+    }
+    pub fn parse_str(s: &str) -> Result<Self, TinyParseError> {
+        Self::parse_lines(s.lines().map(|l| Ok(l.to_string())))
+    }
+    pub(crate) fn parse_lines(mut lines: impl Iterator<Item = Result<String, TinyParseError>>) -> Result<Self, TinyParseError> {
+        let header = lines.next().ok_or(TinyParseError::BadHeader)??;
+        let header = header.trim_end_matches('\r').split('\t').collect::<Vec<_>>();
+        if header.len() < 5 || header[0] != "tiny" || header[1] != "2" {
+            return Err(TinyParseError::BadHeader);
+        }
+        let mut tiny = TinyV2 {
+            major_version: 2,
+            minor_version: header[2].parse().map_err(|_| TinyParseError::BadHeader)?,
+            properties: vec![],
+            mappings: MappingTree::new(header[3..].iter().map(|ns| ns.to_string()).collect()),
+        };
+        let ns = tiny.mappings.namespaces.len();
+        let mut escaped = false;
+        // properties are the indented lines straight after the header, the first other line ends them
+        let mut in_header = true;
+        // which kind of member / variable the comments on the following lines belong to
+        let mut member_is_field = false;
+        let mut var_is_local = false;
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            let indent = line.bytes().take_while(|b| *b == b'\t').count();
+            let line = Line { number: i + 2, columns: line[indent..].split('\t').collect() };
+            let kind = line.column(0)?;
+            let unexpected = TinyParseError::UnexpectedLine(line.number);
+            // a member or comment there has no class to go on, rather than being taken for a property called `f`
+            in_header &= indent == 1 && !matches!(kind, "c" | "f" | "m");
+            match (indent, kind) {
+                (1, _) if in_header => {
+                    escaped |= kind == "escaped-names";
+                    tiny.properties.push((kind.to_string(), line.columns.get(1).map(|v| unescape(v))));
+                }
+                (0, "c") => {
+                    tiny.mappings.classes.push(ClassMapping { names: line.names(1, ns, escaped), ..Default::default() });
+                    member_is_field = false;
+                }
+                (1, "c") => tiny.mappings.classes.last_mut().ok_or(unexpected)?.comment = Some(unescape(line.column(1)?)),
+                (1, "f") => {
+                    let field = FieldMapping { descriptor: line.column(1)?.to_string(), names: line.names(2, ns, escaped), comment: None };
+                    tiny.mappings.classes.last_mut().ok_or(unexpected)?.fields.push(field);
+                    member_is_field = true;
+                }
+                (1, "m") => {
+                    let method = MethodMapping { descriptor: line.column(1)?.to_string(), names: line.names(2, ns, escaped), ..Default::default() };
+                    tiny.mappings.classes.last_mut().ok_or(unexpected)?.methods.push(method);
+                    member_is_field = false;
+                }
+                (2, "c") if member_is_field => {
+                    let field = tiny.mappings.classes.last_mut().and_then(|c| c.fields.last_mut()).ok_or(unexpected)?;
+                    field.comment = Some(unescape(line.column(1)?));
+                }
+                (2, "c") => last_method(&mut tiny.mappings.classes, line.number)?.comment = Some(unescape(line.column(1)?)),
+                (2, "p") => {
+                    let parameter = ParameterMapping { lv_index: line.number(1)?, names: line.names(2, ns, escaped), comment: None };
+                    last_method(&mut tiny.mappings.classes, line.number)?.parameters.push(parameter);
+                    var_is_local = false;
+                }
+                (2, "v") => {
+                    let lvt_row_index = match line.column(3)? {
+                        "-1" => None,
+                        _ => Some(line.number(3)?),
+                    };
+                    let local = LocalMapping { lv_index: line.number(1)?, lv_start_offset: line.number(2)?, lvt_row_index, names: line.names(4, ns, escaped), comment: None };
+                    last_method(&mut tiny.mappings.classes, line.number)?.locals.push(local);
+                    var_is_local = true;
+                }
+                (3, "c") if !member_is_field => {
+                    let method = last_method(&mut tiny.mappings.classes, line.number)?;
+                    let comment = Some(unescape(line.column(1)?));
+                    if var_is_local {
+                        method.locals.last_mut().ok_or(unexpected)?.comment = comment;
+                    } else {
+                        method.parameters.last_mut().ok_or(unexpected)?.comment = comment;
+                    }
+                }
+                _ => return Err(unexpected),
+            }
+        }
+        Ok(tiny)
+    }
+
+    pub fn has_property(&self, key: &str) -> bool {
+        self.properties.iter().any(|(k, _)| k == key)
+    }
+
+    /// a tiny v2 file holding `mappings`, `escaped-names` is set when a name has a tab, newline or backslash in it
+    pub fn from_tree(mappings: MappingTree) -> Self {
+        let mut names = mappings.classes.iter().flat_map(|c| {
+            c.names.iter()
+                .chain(c.fields.iter().flat_map(|f| &f.names))
+                .chain(c.methods.iter().flat_map(|m| m.names.iter()
+                    .chain(m.parameters.iter().flat_map(|p| &p.names))
+                    .chain(m.locals.iter().flat_map(|l| &l.names))))
+        });
+        let escaped = names.any(|n| n.contains(['\\', '\n', '\r', '\t', '\0']));
+        TinyV2 {
+            major_version: 2,
+            minor_version: 0,
+            properties: if escaped { vec![("escaped-names".to_string(), None)] } else { vec![] },
+            mappings,
+        }
+    }
+    /// writes the file in the layout [`Self::parse`] reads
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let tree = &self.mappings;
+        let escaped = self.has_property("escaped-names");
+        let count = tree.namespaces.len();
+        let names = |names: &[String]| (0..count)
+            .map(|i| names.get(i).map_or(String::new(), |n| if escaped { escape(n) } else { n.clone() }))
+            .collect::<Vec<_>>()
+            .join("\t");
+        writeln!(writer, "tiny\t{}\t{}\t{}", self.major_version, self.minor_version, tree.namespaces.join("\t"))?;
+        for (key, value) in &self.properties {
+            match value {
+                Some(value) => writeln!(writer, "\t{key}\t{}", escape(value))?,
+                None => writeln!(writer, "\t{key}")?,
+            }
+        }
+        // comments are always escaped
+        let comment = |writer: &mut W, indent: &str, comment: &Option<String>| match comment {
+            Some(comment) => writeln!(writer, "{indent}c\t{}", escape(comment)),
+            None => Ok(()),
+        };
+        for class in &tree.classes {
+            writeln!(writer, "c\t{}", names(&class.names))?;
+            comment(&mut writer, "\t", &class.comment)?;
+            for field in &class.fields {
+                writeln!(writer, "\tf\t{}\t{}", field.descriptor, names(&field.names))?;
+                comment(&mut writer, "\t\t", &field.comment)?;
+            }
+            for method in &class.methods {
+                writeln!(writer, "\tm\t{}\t{}", method.descriptor, names(&method.names))?;
+                comment(&mut writer, "\t\t", &method.comment)?;
+                for parameter in &method.parameters {
+                    writeln!(writer, "\t\tp\t{}\t{}", parameter.lv_index, names(&parameter.names))?;
+                    comment(&mut writer, "\t\t\t", &parameter.comment)?;
+                }
+                for local in &method.locals {
+                    let row = local.lvt_row_index.map_or("-1".to_string(), |r| r.to_string());
+                    writeln!(writer, "\t\tv\t{}\t{}\t{row}\t{}", local.lv_index, local.lv_start_offset, names(&local.names))?;
+                    comment(&mut writer, "\t\t\t", &local.comment)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn last_method(classes: &mut [ClassMapping], line: usize) -> Result<&mut MethodMapping, TinyParseError> {
+    classes.last_mut()
+        .and_then(|c| c.methods.last_mut())
+        .ok_or(TinyParseError::UnexpectedLine(line))
+}
+
+/// tiny v2 escaping, the other way around from [`unescape`]
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// undoes tiny v2 escaping: `\\`, `\n`, `\r`, `\t` and `\0`
+fn unescape(s: &str) -> String {
+    if !s.contains('\\') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiny::map::Tiny;
+
+    const MERGED: &str = "tiny\t2\t0\tofficial\tintermediary\tnamed
+\tescaped-names
+\tmissing-lvt-indices\t
+c\teei\tnet/minecraft/class_243\tnet/minecraft/util/math/Vec3d
+\tc\tAn immutable vector.\\nWith three components.
+\tf\tD\tc\tfield_1352\tx
+\tm\t(Leei;)Leei;\te\tmethod_1020\tadd
+\t\tc\tadds two vectors
+\t\tp\t1\t\t\tother
+\t\t\tc\tthe other one
+\t\tv\t2\t4\t-1\t\t\tsum
+\t\tv\t3\t8\t1\t\t\ttab\\tbed
+\t\t\tc\ta local
+c\teev\tnet/minecraft/class_310
+\tm\t()Leev;\tG\tmethod_1551\tgetInstance
+";
+
+    #[test]
+    fn parse() {
+        let tiny = TinyV2::parse_str(MERGED).unwrap();
+        assert_eq!(tiny.mappings.namespaces, ["official", "intermediary", "named"]);
+        assert!(tiny.has_property("escaped-names"));
+        assert_eq!(tiny.mappings.namespace("named"), Some(2));
+
+        let vec = &tiny.mappings.classes[0];
+        assert_eq!(vec.name(2), Some("net/minecraft/util/math/Vec3d"));
+        assert_eq!(vec.comment.as_deref(), Some("An immutable vector.\nWith three components."));
+        assert_eq!(vec.fields[0].descriptor, "D");
+        let add = &vec.methods[0];
+        assert_eq!(add.names, ["e", "method_1020", "add"]);
+        assert_eq!(add.comment.as_deref(), Some("adds two vectors"));
+        assert_eq!(add.parameters[0].lv_index, 1);
+        assert_eq!(add.parameters[0].name(0), None);
+        assert_eq!(add.parameters[0].name(2), Some("other"));
+        assert_eq!(add.parameters[0].comment.as_deref(), Some("the other one"));
+        assert_eq!(add.locals[0], LocalMapping { lv_index: 2, lv_start_offset: 4, lvt_row_index: None, names: vec!["".into(), "".into(), "sum".into()], comment: None });
+        assert_eq!(add.locals[1].lvt_row_index, Some(1));
+        assert_eq!(add.locals[1].name(2), Some("tab\tbed"));
+        assert_eq!(add.locals[1].comment.as_deref(), Some("a local"));
+
+        // trailing empty names can be left out
+        assert_eq!(tiny.mappings.classes[1].names, ["eev", "net/minecraft/class_310", ""]);
+        assert_eq!(tiny.mappings.classes[1].name(2), None);
+
+        let names = tiny.mappings.class_names(1);
+        assert_eq!(MappingTree::remap_descriptor("(Leei;[Leev;ILjava/lang/String;)Leei;", &names), "(Lnet/minecraft/class_243;[Lnet/minecraft/class_310;ILjava/lang/String;)Lnet/minecraft/class_243;");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(TinyV2::parse_str("v1\tofficial\tintermediary"), Err(TinyParseError::BadHeader));
+        assert_eq!(TinyV2::parse_str("tiny\t2\t0\ta\tb\n\tm\t()V\ta\tb"), Err(TinyParseError::UnexpectedLine(2)));
+        assert_eq!(TinyV2::parse_str("tiny\t2\t0\ta\tb\n\tsorted\n\tf\tI\tx\ty"), Err(TinyParseError::UnexpectedLine(3)));
+        // nor once the first class is through
+        assert_eq!(TinyV2::parse_str("tiny\t2\t0\ta\tb\nc\tx\ty\n\tsorted"), Err(TinyParseError::UnexpectedLine(3)));
+        assert_eq!(TinyV2::parse_str("tiny\t2\t0\ta\tb\nc\tx\ty\n\t\tp\t1\ta\tb"), Err(TinyParseError::UnexpectedLine(3)));
+        assert_eq!(TinyV2::parse_str("tiny\t2\t0\ta\tb\nc\tx\ty\n\tm\t()V\ta\tb\n\t\tp\tone\ta\tb"), Err(TinyParseError::BadNumber(4)));
+    }
+
+    #[test]
+    fn write() {
+        let tiny = TinyV2::parse_str(MERGED).unwrap();
+        let mut written = vec![];
+        tiny.write(&mut written).unwrap();
+        assert_eq!(TinyV2::parse(written.as_slice()).unwrap(), tiny);
+        // names with a tab in them turn escaping on
+        let rebuilt = TinyV2::from_tree(tiny.mappings.clone());
+        assert_eq!(rebuilt.properties, [("escaped-names".to_string(), None)]);
+        let mut written = vec![];
+        rebuilt.write(&mut written).unwrap();
+        assert_eq!(TinyV2::parse(written.as_slice()).unwrap().mappings, tiny.mappings);
+    }
+
+    #[test]
+    fn populate_tiny() {
+        let tiny = Tiny::new();
+        tiny.populate_from_reader(MERGED.as_bytes()).unwrap();
+        assert_eq!(tiny.obfuscated_class("net/minecraft/class_243").as_deref(), Some("eei"));
+        assert_eq!(tiny.obfuscated_class("net.minecraft.class_243").as_deref(), Some("eei"));
+        assert_eq!(tiny.obfuscated_class("net/minecraft/util/math/Vec3d"), None);
+        // and the v1 intermediary files
+        tiny.populate_from_reader("v1\tofficial\tintermediary\nCLASS\tdzd\tnet/minecraft/class_310\nFIELD\tdzd\tI\ta\tfield_1\n".as_bytes()).unwrap();
+        assert_eq!(tiny.obfuscated_class("net/minecraft/class_310").as_deref(), Some("dzd"));
+        assert_eq!(tiny.obfuscated_class("net/minecraft/class_243").as_deref(), Some("eei"));
+
+        assert_eq!(Tiny::new().populate_from_reader("tiny\t2\t0\tofficial\tnamed\n".as_bytes()), Err(TinyParseError::MissingNamespace("intermediary".into())));
+        assert_eq!(Tiny::new().populate_from_reader("tiny\t2\t0\tofficial\tintermediary\n\tf\tI\ta\tb\n".as_bytes()), Err(TinyParseError::UnexpectedLine(2)));
+    }
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, fmt, fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path};
+
+use crate::mapping::tree::{ClassMapping, FieldMapping, MappingTree, MethodMapping, ParameterMapping};
+
+/// the namespaces of a yarn checkout, see [`read_dir`]
+pub const INTERMEDIARY: &str = "intermediary";
+pub const NAMED: &str = "named";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EnigmaParseError {
+    /// a line (1 based) that does not fit where it is, eg. an `ARG` outside of a method
+    UnexpectedLine(usize),
+    BadNumber(usize),
+    Io(String),
+}
+
+impl fmt::Display for EnigmaParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnigmaParseError::UnexpectedLine(line) => write!(f, "unexpected line {line}"),
+            EnigmaParseError::BadNumber(line) => write!(f, "bad number on line {line}"),
+            EnigmaParseError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for EnigmaParseError {}
+
+/// what a line is nested under, by indentation
+#[derive(Clone, Copy)]
+enum Element {
+    Class(usize),
+    Field(usize, usize),
+    Method(usize, usize),
+    Parameter(usize, usize, usize),
+}
+
+/// every `.mapping` file under `path` (yarn's `mappings` directory) as one tree with the namespaces [`INTERMEDIARY`] and [`NAMED`].
+/// files are read in path order so the tree comes out the same every time
+pub fn read_dir(path: impl AsRef<Path>) -> Result<MappingTree, EnigmaParseError> {
+    let mut tree = MappingTree::new(vec![INTERMEDIARY.to_string(), NAMED.to_string()]);
+    let mut files = vec![];
+    collect_files(path.as_ref(), &mut files)?;
+    files.sort();
+    for file in files {
+        let reader = File::open(&file).map_err(|e| EnigmaParseError::Io(format!("could not open {}: {e}", file.display())))?;
+        parse(BufReader::new(reader), &mut tree)?;
+    }
+    Ok(tree)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), EnigmaParseError> {
+    for entry in std::fs::read_dir(path).map_err(|e| EnigmaParseError::Io(format!("could not read {}: {e}", path.display())))? {
+        let path = entry.map_err(|e| EnigmaParseError::Io(e.to_string()))?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "mapping") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// adds the classes of one enigma file (`CLASS`, `FIELD`, `METHOD`, `ARG` and `COMMENT` lines) to a two namespace `tree`.
+/// inner classes get their full name, `net/minecraft/class_310$class_311`
+pub fn parse<R: BufRead>(reader: R, tree: &mut MappingTree) -> Result<(), EnigmaParseError> {
+    let mut stack: Vec<Element> = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| EnigmaParseError::Io(e.to_string()))?;
+        let number = i + 1;
+        let line = line.trim_end_matches('\r');
+        let depth = line.bytes().take_while(|b| *b == b'\t').count();
+        let content = &line[depth..];
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if depth > stack.len() {
+            return Err(EnigmaParseError::UnexpectedLine(number));
+        }
+        stack.truncate(depth);
+        let parent = stack.last().copied();
+        // access modifiers (`ACC:PUBLIC`) are left out
+        let columns = content.split(' ').filter(|c| !c.is_empty() && !c.starts_with("ACC:")).collect::<Vec<_>>();
+        let unexpected = EnigmaParseError::UnexpectedLine(number);
+        let element = match (columns.as_slice(), parent) {
+            (["COMMENT", ..], Some(parent)) => {
+                let text = content.strip_prefix("COMMENT").unwrap_or("").strip_prefix(' ').unwrap_or("");
+                let comment = match parent {
+                    Element::Class(c) => &mut tree.classes[c].comment,
+                    Element::Field(c, f) => &mut tree.classes[c].fields[f].comment,
+                    Element::Method(c, m) => &mut tree.classes[c].methods[m].comment,
+                    Element::Parameter(c, m, p) => &mut tree.classes[c].methods[m].parameters[p].comment,
+                };
+                // a comment over several lines is a COMMENT line for each
+                match comment {
+                    Some(comment) => {
+                        comment.push('\n');
+                        comment.push_str(text);
+                    }
+                    None => *comment = Some(text.to_string()),
+                }
+                continue;
+            }
+            (["CLASS", from, to @ ..], None) if to.len() <= 1 => {
+                tree.classes.push(ClassMapping { names: vec![from.to_string(), to.first().unwrap_or(&"").to_string()], ..Default::default() });
+                Element::Class(tree.classes.len() - 1)
+            }
+            (["CLASS", from, to @ ..], Some(Element::Class(outer))) if to.len() <= 1 => {
+                let outer = &tree.classes[outer];
+                let from = format!("{}${from}", outer.names[0]);
+                let to = match (to.first(), outer.name(1)) {
+                    (Some(to), Some(outer)) => format!("{outer}${to}"),
+                    (Some(to), None) => format!("{}${to}", outer.names[0]),
+                    (None, _) => String::new(),
+                };
+                tree.classes.push(ClassMapping { names: vec![from, to], ..Default::default() });
+                Element::Class(tree.classes.len() - 1)
+            }
+            (["FIELD", from, rest @ ..], Some(Element::Class(c))) if !rest.is_empty() && rest.len() <= 2 => {
+                let (to, descriptor) = if rest.len() == 2 { (rest[0], rest[1]) } else { ("", rest[0]) };
+                let class = &mut tree.classes[c];
+                class.fields.push(FieldMapping { descriptor: descriptor.to_string(), names: vec![from.to_string(), to.to_string()], comment: None });
+                Element::Field(c, class.fields.len() - 1)
+            }
+            (["METHOD", from, rest @ ..], Some(Element::Class(c))) if !rest.is_empty() && rest.len() <= 2 => {
+                let (to, descriptor) = if rest.len() == 2 { (rest[0], rest[1]) } else { ("", rest[0]) };
+                let class = &mut tree.classes[c];
+                class.methods.push(MethodMapping { descriptor: descriptor.to_string(), names: vec![from.to_string(), to.to_string()], ..Default::default() });
+                Element::Method(c, class.methods.len() - 1)
+            }
+            (["ARG", index, to], Some(Element::Method(c, m))) => {
+                let method = &mut tree.classes[c].methods[m];
+                let lv_index = index.parse().map_err(|_| EnigmaParseError::BadNumber(number))?;
+                method.parameters.push(ParameterMapping { lv_index, names: vec![String::new(), to.to_string()], comment: None });
+                Element::Parameter(c, m, method.parameters.len() - 1)
+            }
+            _ => return Err(unexpected),
+        };
+        stack.push(element);
+    }
+    Ok(())
+}
+
+/// the top level classes of `tree` with the inner classes nested under each, by first namespace name
+fn nest(tree: &MappingTree) -> (Vec<usize>, Vec<Vec<usize>>) {
+    let indices = tree.classes.iter().enumerate().map(|(i, c)| (c.names[0].as_str(), i)).collect::<HashMap<_, _>>();
+    let mut top = vec![];
+    let mut inner = vec![vec![]; tree.classes.len()];
+    for (i, class) in tree.classes.iter().enumerate() {
+        match class.names[0].rsplit_once('$').and_then(|(outer, _)| indices.get(outer)) {
+            Some(&outer) if outer != i => inner[outer].push(i),
+            _ => top.push(i),
+        }
+    }
+    (top, inner)
+}
+
+/// writes the first two namespaces of `tree` as one enigma file, the way [`parse`] reads it.
+/// inner classes go under their outer class, names that are missing or the same as the first namespace are left out
+pub fn write<W: Write>(tree: &MappingTree, mut writer: W) -> io::Result<()> {
+    let (top, inner) = nest(tree);
+    for class in top {
+        write_class(tree, class, &inner, 0, &mut writer)?;
+    }
+    Ok(())
+}
+
+/// writes `tree` as a yarn style `mappings` directory, a `.mapping` file per top level class named after its second namespace name
+pub fn write_dir(tree: &MappingTree, path: impl AsRef<Path>) -> io::Result<()> {
+    let (top, inner) = nest(tree);
+    for class in top {
+        let names = &tree.classes[class];
+        let name = names.name(1).unwrap_or(&names.names[0]);
+        let file = path.as_ref().join(format!("{name}.mapping"));
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(file)?);
+        write_class(tree, class, &inner, 0, &mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn write_class<W: Write>(tree: &MappingTree, index: usize, inner: &[Vec<usize>], depth: usize, writer: &mut W) -> io::Result<()> {
+    let class = &tree.classes[index];
+    let indent = "\t".repeat(depth);
+    // inner classes are written by their own name, `class_311` under `net/minecraft/class_310`
+    let simple = |name: &str| if depth == 0 { name.to_string() } else { name.rsplit_once('$').map_or(name, |(_, n)| n).to_string() };
+    let from = simple(&class.names[0]);
+    match class.name(1).map(simple) {
+        Some(to) if to != from => writeln!(writer, "{indent}CLASS {from} {to}")?,
+        _ => writeln!(writer, "{indent}CLASS {from}")?,
+    }
+    write_comment(writer, &indent, &class.comment)?;
+    for field in &class.fields {
+        write_member(writer, &indent, "FIELD", &field.names, &field.descriptor)?;
+        write_comment(writer, &format!("{indent}\t"), &field.comment)?;
+    }
+    for method in &class.methods {
+        write_member(writer, &indent, "METHOD", &method.names, &method.descriptor)?;
+        write_comment(writer, &format!("{indent}\t"), &method.comment)?;
+        for parameter in &method.parameters {
+            if let Some(name) = parameter.name(1) {
+                writeln!(writer, "{indent}\t\tARG {} {name}", parameter.lv_index)?;
+                write_comment(writer, &format!("{indent}\t\t"), &parameter.comment)?;
+            }
+        }
+    }
+    for &class in &inner[index] {
+        write_class(tree, class, inner, depth + 1, writer)?;
+    }
+    Ok(())
+}
+
+fn write_member<W: Write>(writer: &mut W, indent: &str, kind: &str, names: &[String], descriptor: &str) -> io::Result<()> {
+    let from = names.first().map_or("", String::as_str);
+    match names.get(1) {
+        Some(to) if !to.is_empty() && to != from => writeln!(writer, "{indent}\t{kind} {from} {to} {descriptor}"),
+        _ => writeln!(writer, "{indent}\t{kind} {from} {descriptor}"),
+    }
+}
+
+/// a COMMENT line for each line of `comment`, one level under `indent`
+fn write_comment<W: Write>(writer: &mut W, indent: &str, comment: &Option<String>) -> io::Result<()> {
+    for line in comment.iter().flat_map(|c| c.split('\n')) {
+        match line {
+            "" => writeln!(writer, "{indent}\tCOMMENT")?,
+            line => writeln!(writer, "{indent}\tCOMMENT {line}")?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mapping() {
+        let mut tree = MappingTree::new(vec![INTERMEDIARY.to_string(), NAMED.to_string()]);
+        parse("CLASS net/minecraft/class_310 net/minecraft/client/MinecraftClient
+\tCOMMENT the client.
+\tCOMMENT one per game
+\tFIELD field_1700 instance Lnet/minecraft/class_310;
+\tFIELD field_1 I
+\tMETHOD method_1000 setPos (Lnet/minecraft/class_243;D)V
+\t\tARG 1 pos
+\t\t\tCOMMENT where to
+\tCLASS class_311 Inner
+\t\tMETHOD method_2 ()V
+\tCLASS class_312
+".as_bytes(), &mut tree).unwrap();
+        let client = &tree.classes[0];
+        assert_eq!(client.comment.as_deref(), Some("the client.\none per game"));
+        assert_eq!(client.fields[0], FieldMapping { descriptor: "Lnet/minecraft/class_310;".into(), names: vec!["field_1700".into(), "instance".into()], comment: None });
+        assert_eq!((client.fields[1].descriptor.as_str(), client.fields[1].name(1)), ("I", None));
+        let set_pos = &client.methods[0];
+        assert_eq!(set_pos.names, ["method_1000", "setPos"]);
+        assert_eq!(set_pos.parameters[0].lv_index, 1);
+        assert_eq!(set_pos.parameters[0].name(1), Some("pos"));
+        assert_eq!(set_pos.parameters[0].comment.as_deref(), Some("where to"));
+        assert_eq!(tree.classes[1].names, ["net/minecraft/class_310$class_311", "net/minecraft/client/MinecraftClient$Inner"]);
+        assert_eq!(tree.classes[1].methods[0].descriptor, "()V");
+        assert_eq!(tree.classes[2].names, ["net/minecraft/class_310$class_312", ""]);
+
+        assert_eq!(parse("\tFIELD a b I".as_bytes(), &mut tree), Err(EnigmaParseError::UnexpectedLine(1)));
+        assert_eq!(parse("CLASS a\n\tMETHOD b ()V\n\t\tARG x y".as_bytes(), &mut tree), Err(EnigmaParseError::BadNumber(3)));
+    }
+
+    #[test]
+    fn write_mapping() {
+        let mapping = "CLASS net/minecraft/class_310 net/minecraft/client/MinecraftClient
+\tCOMMENT the client.
+\tCOMMENT
+\tCOMMENT one per game
+\tFIELD field_1700 instance Lnet/minecraft/class_310;
+\tFIELD field_1 I
+\tMETHOD method_1000 setPos (Lnet/minecraft/class_243;D)V
+\t\tARG 1 pos
+\t\t\tCOMMENT where to
+\tCLASS class_311 Inner
+\t\tMETHOD method_2 ()V
+\tCLASS class_312
+";
+        let mut tree = MappingTree::new(vec![INTERMEDIARY.to_string(), NAMED.to_string()]);
+        parse(mapping.as_bytes(), &mut tree).unwrap();
+        let mut written = vec![];
+        write(&tree, &mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), mapping);
+
+        let dir = std::env::temp_dir().join(format!("map-gen-enigma-{}", std::process::id()));
+        write_dir(&tree, &dir).unwrap();
+        assert!(dir.join("net/minecraft/client/MinecraftClient.mapping").is_file());
+        assert_eq!(read_dir(&dir).unwrap(), tree);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
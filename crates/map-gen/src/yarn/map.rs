@@ -320,7 +320,6 @@ impl Signatures {
 
 pub struct Yarn{
     pub(crate) modules: Vec<Arc<RwLock<Module>>>,
-    pub(crate) lookup: HashMap<String, String>
 }
 
 impl Module {
@@ -556,7 +555,6 @@ impl Yarn {
     pub fn new() -> Self {
         Self {
             modules: Vec::new(),
-            lookup: HashMap::new()
         }
     }
 
@@ -579,10 +577,6 @@ impl Yarn {
             None => return Err(YarnParseError::LexingError),
         }));
         // println!("root_class = {:?}", root_class);
-        {
-            let up = root_class.read();
-            self.lookup.insert(up.map_data.from.to_uppercase(), up.get_namespaced());
-        }
 
         let mut stack = vec![root_class.clone()];
 
@@ -592,12 +586,10 @@ impl Yarn {
             match tok {
                 YarnTokens::Class(x) => {
                     let clzz = Class::from_token(&x,modu.clone());
-                    self.lookup.insert(clzz.map_data.from.to_uppercase(), clzz.get_namespaced());
                     stack.last_mut().unwrap().write().inner_classes.push(Arc::new(RwLock::new(clzz)));
                 },
                 YarnTokens::ClassId(x) => {
                     let clzz = Class::from_token(&x,modu.clone());
-                    self.lookup.insert(clzz.map_data.from.to_uppercase(), clzz.get_namespaced());
                     stack.last_mut().unwrap().write().inner_classes.push(Arc::new(RwLock::new(clzz)));
 
                 },
@@ -720,7 +712,6 @@ mod tests {
         let mut wf = File::create("test.ron").unwrap();
         let data = format!("{:#?}",yarn_instance.modules);
         println!("got data");
-        println!("lookup table = {:#?}",yarn_instance.lookup);
         write!(wf,"{}",data).unwrap();
         // println!("{:#?}",yarn_instance.modules);
     }
@@ -1 +1,2 @@
-pub mod map;
\ No newline at end of file
+pub mod map;
+pub mod enigma;
@@ -1,8 +1,14 @@
 use std::fs::File;
-use std::io::{BufReader, Write};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use map_gen::generator::Generator;
+use map_gen::class::jar::Jar;
+use map_gen::tiny::{v1, v2::TinyV2};
+use map_gen::proguard::map::{self as proguard, ProGuard};
+use map_gen::yarn::enigma;
+use map_gen::srg;
+use map_gen::mapping::json;
 
 fn main() {
     // let mc_ver = std::env::var("MCI_GAME_VER").unwrap_or("1.19".to_string());
@@ -31,9 +37,57 @@ fn main() {
 
         let mut gen = Generator::new();
 
-        gen.Yarn.run_directory(MCM_DIRP.join(format!("{MCM_DIR}/yarn-maps/mappings")), None).expect("unable to parse yarn mappings");
-        let mut tiny = BufReader::new(File::open(MCM_DIRP.join(format!("maps.tiny"))).expect("unable to parse tiny mappings"));
-        gen.Tiny.populate_from_reader(&mut tiny);
+        // the obfuscated client jar is optional, without it every member gets both a static and an instance accessor
+        let jar = MCM_DIRP.join("client.jar");
+        let jar = jar.exists().then(|| {
+            println!("cargo:rerun-if-changed={}", jar.display());
+            let jar = Jar::open(&jar).expect("unable to read client jar");
+            for (entry, e) in &jar.skipped {
+                println!("cargo:warning=skipping {entry} in the client jar: {e}");
+            }
+            jar
+        });
+        // forge runs with srg names, MCMAPS_NAMESPACE picks the namespace of a tsrg2 file the bindings look members up by
+        let srg = ["joined.tsrg", "joined.csrg", "joined.srg"].iter().map(|f| MCM_DIRP.join(f)).find(|f| f.exists());
+        println!("cargo:rerun-if-env-changed=MCMAPS_NAMESPACE");
+        let namespace = std::env::var("MCMAPS_NAMESPACE").unwrap_or(srg::map::SRG.to_string());
+
+        // every format is read into one mapping tree, which the generator takes with the namespaces to use
+        let merged = MCM_DIRP.join("merged.tiny");
+        let (tree, [runtime, key, named]) = if merged.exists() {
+            // a merged tiny v2 file (official, intermediary and named) replaces both the yarn checkout and the intermediary tiny
+            let tiny = TinyV2::parse(BufReader::new(File::open(&merged).expect("unable to open merged tiny mappings"))).expect("unable to parse merged tiny mappings");
+            (tiny.mappings, ["official", "intermediary", "named"])
+        } else if let Some(srg) = srg {
+            let mut tree = srg::map::read(BufReader::new(File::open(&srg).expect("unable to open srg mappings"))).expect("unable to parse srg mappings");
+            // srg and csrg leave field descriptors out
+            if let Some(jar) = &jar {
+                tree.fill_field_descriptors(jar);
+            }
+            (tree, [namespace.as_str(); 3])
+        } else if MCM_DIRP.join("client.txt").exists() {
+            // mojang's official names instead of yarn
+            let mappings = ProGuard::parse(BufReader::new(File::open(MCM_DIRP.join("client.txt")).expect("unable to open proguard mappings"))).expect("unable to parse proguard mappings");
+            (mappings.to_tree(), [proguard::OFFICIAL, proguard::IDS, proguard::NAMED])
+        } else {
+            // intermediary tiny (official -> intermediary) chained with the yarn checkout (intermediary -> named)
+            let tiny = v1::parse(BufReader::new(File::open(MCM_DIRP.join("maps.tiny")).expect("unable to open tiny mappings"))).expect("unable to parse tiny mappings");
+            let yarn = enigma::read_dir(MCM_DIRP.join(format!("{MCM_DIR}/yarn-maps/mappings"))).expect("unable to parse yarn mappings");
+            (tiny.compose(&yarn, enigma::INTERMEDIARY).expect("unable to chain tiny and yarn mappings"), ["official", enigma::INTERMEDIARY, enigma::NAMED])
+        };
+        gen.load(&tree, runtime, key, named).expect("unable to read names from the mappings");
+
+        // MCMAPS_EXPORT writes the exact mappings the bindings come from, as tiny v2 and json, into that directory
+        println!("cargo:rerun-if-env-changed=MCMAPS_EXPORT");
+        if let Ok(export) = std::env::var("MCMAPS_EXPORT") {
+            let export = PathBuf::from(export);
+            std::fs::create_dir_all(&export).expect("unable to create mapping export directory");
+            let tiny = File::create(export.join("mappings.tiny")).expect("unable to create exported tiny mappings");
+            TinyV2::from_tree(tree.clone()).write(BufWriter::new(tiny)).expect("unable to write exported tiny mappings");
+            let js = File::create(export.join("mappings.json")).expect("unable to create exported json mappings");
+            json::write(&tree, BufWriter::new(js)).expect("unable to write exported json mappings");
+        }
+        gen.Jar = jar;
 
         let code = gen.generate();
         let min_code = rust_minify::minify(&code).expect("unable to minify generated code");
@@ -0,0 +1,171 @@
+//! jvmti agent entry points, so mci can be started with `-agentpath:/path/to/libmci.so=opts`
+//! or loaded into a running jvm through the attach api (`VirtualMachine::loadAgentPath`).
+use std::{ffi::{c_char, c_void, CStr}, sync::{atomic::{AtomicBool, Ordering}, OnceLock}};
+
+use jdk_sys::{jint, JavaVM, JNI_OK, JNI_ERR};
+use jni::{vm::JavaVm, jvmti::{JvmtiEnv, Capabilities}, error::JniError};
+
+/// the jvmti environment of the agent, set once `Agent_OnLoad` / `Agent_OnAttach` has run
+static JVMTI : OnceLock<JvmtiEnv> = OnceLock::new();
+/// `JNI_GetCreatedJavaVMs` does not list the vm until `JNI_CreateJavaVM` returns, which is after `VMInit`
+static VM : OnceLock<JavaVm> = OnceLock::new();
+static OPTIONS : OnceLock<AgentOptions> = OnceLock::new();
+static STARTED : AtomicBool = AtomicBool::new(false);
+
+/// The option string of the agent, `key=value` or bare `flag` entries separated by commas,
+/// eg. `-agentpath:libmci.so=verbose,delay=500,hooks,tiny=mappings/maps.tiny`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentOptions {
+    entries : Vec<(String, Option<String>)>,
+}
+
+impl AgentOptions {
+    pub fn parse(options : &str) -> Self {
+        let entries = options.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once('=') {
+                Some((key, value)) => (key.trim().to_string(), Some(value.trim().to_string())),
+                None => (entry.to_string(), None),
+            })
+            .collect();
+        Self { entries }
+    }
+    /// the value of the last `key=value` entry for `key`
+    pub fn get(&self, key : &str) -> Option<&str> {
+        self.entries.iter().rev().find(|(k, _)| k == key).and_then(|(_, v)| v.as_deref())
+    }
+    /// true when `key` was given at all, with or without a value
+    pub fn flag(&self, key : &str) -> bool {
+        self.entries.iter().any(|(k, _)| k == key)
+    }
+}
+
+/// the agent's jvmti environment, `None` when mci was injected some other way
+pub fn jvmti() -> Option<JvmtiEnv> {
+    JVMTI.get().copied()
+}
+/// the vm that loaded the agent
+pub fn vm() -> Option<JavaVm> {
+    VM.get().copied()
+}
+pub fn options() -> Option<&'static AgentOptions> {
+    OPTIONS.get()
+}
+/// true when the jvm loaded us as an agent, the other entry points back off in that case
+pub fn is_agent() -> bool {
+    JVMTI.get().is_some()
+}
+
+/// the start for injected loads (the library entry points). `-agentpath` and the attach api load the library the same way
+/// and call `Agent_OnLoad` / `Agent_OnAttach` right after, so this waits `wait` for that and backs off if we turned out to be an agent
+pub fn start_injected(wait : std::time::Duration) {
+    std::thread::sleep(wait);
+    if is_agent() {
+        return;
+    }
+    start();
+}
+
+/// starts the main thread, only the first call does anything
+fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let delay = options().and_then(|o| o.get("delay")).and_then(|d| d.parse().ok());
+    std::thread::spawn(move || {
+        if let Some(delay) = delay {
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+        }
+        crate::main_thread_wrap();
+    });
+}
+
+unsafe fn options_from_raw(options : *const c_char) -> AgentOptions {
+    if options.is_null() {
+        return AgentOptions::default();
+    }
+    AgentOptions::parse(&CStr::from_ptr(options).to_string_lossy())
+}
+
+fn init(vm : *mut JavaVM, options : *const c_char) -> Result<JvmtiEnv,JniError> {
+    let vm = unsafe { JavaVm::from_raw(vm) }?;
+    let options = unsafe { options_from_raw(options) };
+    let jvmti = JvmtiEnv::from_vm(&vm)?;
+    // class hooks, retransforming and heap walking, some of these can only be had during `Agent_OnLoad`
+    jvmti.add_available_capabilities(&Capabilities {
+        can_retransform_classes : true,
+        can_retransform_any_class : true,
+        can_generate_all_class_hook_events : true,
+        can_tag_objects : true,
+        ..Capabilities::default()
+    })?;
+    // method hooks make the jvm a lot slower (entry / exit events keep it interpreting), so they are opt in
+    if options.flag("hooks") {
+        jvmti.add_available_capabilities(&Capabilities {
+            can_access_local_variables : true,
+            can_generate_breakpoint_events : true,
+            can_generate_method_entry_events : true,
+            can_generate_method_exit_events : true,
+            ..Capabilities::default()
+        })?;
+    }
+    if options.flag("verbose") {
+        println!("mci loaded as an agent with {:?}", options);
+    }
+    let _ = OPTIONS.set(options);
+    let _ = VM.set(vm);
+    // loading twice (eg. attaching again) keeps the first environment
+    Ok(*JVMTI.get_or_init(|| jvmti))
+}
+
+/// `-agentpath:` / `-agentlib:`, runs before the jvm has started so the main thread waits for `VMInit`
+#[no_mangle]
+pub unsafe extern "system" fn Agent_OnLoad(vm : *mut JavaVM, options : *mut c_char, _reserved : *mut c_void) -> jint {
+    let result = init(vm, options).and_then(|jvmti| jvmti.on_vm_init(|_, _| start()));
+    match result {
+        Ok(_) => JNI_OK as jint,
+        Err(e) => {
+            eprintln!("mci: Agent_OnLoad failed: {e}");
+            JNI_ERR
+        }
+    }
+}
+
+/// attach api, the jvm is already running so the main thread starts straight away
+#[no_mangle]
+pub unsafe extern "system" fn Agent_OnAttach(vm : *mut JavaVM, options : *mut c_char, _reserved : *mut c_void) -> jint {
+    match init(vm, options) {
+        Ok(_) => {
+            start();
+            JNI_OK as jint
+        }
+        Err(e) => {
+            eprintln!("mci: Agent_OnAttach failed: {e}");
+            JNI_ERR
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Agent_OnUnload(_vm : *mut JavaVM) {
+    if options().map_or(false, |o| o.flag("verbose")) {
+        println!("mci unloaded");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AgentOptions;
+
+    #[test]
+    fn parse_options() {
+        let options = AgentOptions::parse("verbose, delay=500,,path=a=b,delay=10");
+        assert!(options.flag("verbose"));
+        assert_eq!(options.get("verbose"), None);
+        assert_eq!(options.get("delay"), Some("10"));
+        assert_eq!(options.get("path"), Some("a=b"));
+        assert!(!options.flag("missing"));
+        assert_eq!(AgentOptions::parse(""), AgentOptions::default());
+    }
+}
@@ -1,5 +1,6 @@
 #![feature(new_uninit)]
 mod mci;
+mod agent;
 use std::ffi::CString;
 
 use jni::{object::JObject, jstring::JString};
@@ -14,15 +15,16 @@ fn entry() {
 
     if args.next().unwrap().contains("java") && args.filter_map(|a| if a.contains("minecraft") && a.ends_with(".jar") {Some(a)} else {None}).count() > 0  {
         println!("pogging");
-        std::thread::spawn(|| {
-            std::thread::sleep(Duration::from_secs(2));
-
-            main_thread_wrap();
-        });
+        std::thread::spawn(|| agent::start_injected(Duration::from_secs(2)));
     }
 }
+// -agentpath: runs this too, `start_injected` leaves the main thread to the agent then, which starts it once the jvm is ready
 #[cfg(not(target_os="macos"))]
 #[poggers_derive::create_entry(no_console)]
+fn entry() -> Result<(), String> {
+    agent::start_injected(std::time::Duration::from_millis(500));
+    Ok(())
+}
 fn main_thread() -> Result<(), String> {
     use mc_mappings::mappings::net::minecraft::{client::MinecraftClient, text::Text, util::math::Vec3d};
 
@@ -34,11 +36,14 @@ fn main_thread() -> Result<(), String> {
 
     mci.attach_current_thread()?;
 
+    if let Some(path) = agent::options().and_then(|o| o.get("tiny")) {
+        mci.load_tiny(path)?;
+    }
+
 
     println!("we're chillin");
     {
-        let jenv = mci.get_jenv();
-        let jenv = jenv.write().unwrap();
+        let jenv = mci.get_jenv().ok_or("thread is not attached".to_string())?;
         let ver = jenv.get_version();
         println!("version: {}", ver);
 
@@ -1,70 +1,93 @@
-use std::{mem, sync::{Arc, RwLock}, ptr};
+use std::{fs::File, io::BufReader, path::Path};
 
-use jdk_sys::{JNIEnv, JavaVM};
-// use toy_arms::internal::get_module_function_address;
+use jni::{env::Jenv, vm::{JavaVm, AttachGuard}, jvmti::{JvmtiEnv, ClassFileLoad, HookKind, Mapped, MethodEvent, HeapHistogram}, object::JObject};
+use map_gen::tiny::map::Tiny;
 
-use jni::env::Jenv;
-
-
-type GetJvms = unsafe extern "C" fn (vmBuf : *mut *mut JavaVM,BufLen: isize, nVMs : *mut i32) -> jdk_sys::jint;
-
-#[derive(Debug)]
-pub struct MCI<'a> {
-    jvm : Arc<RwLock<*mut JavaVM>>,
-    jenv : Arc<RwLock<Jenv<'a>>>,
-}
-impl Default for MCI<'_> {
-    fn default() -> Self {
-        Self { jvm: Arc::new(RwLock::new(ptr::null_mut())), jenv: Arc::new(RwLock::new(Jenv::default())) }
-    }
+/// the jvm of the game we are injected into, and the attachment of the thread that found it
+#[derive(Default)]
+pub struct MCI {
+    vm : Option<JavaVm>,
+    guard : Option<AttachGuard>,
+    /// intermediary -> obfuscated names, used to find the classes to transform
+    tiny : Option<Tiny>,
 }
 
-impl<'a> MCI<'a> {
-    pub fn get_jvm(&self) -> Arc<RwLock<*mut JavaVM>> {
-        Arc::clone(&self.jvm)
+impl MCI {
+    pub fn get_vm(&self) -> Option<JavaVm> {
+        self.vm
     }
-    pub fn get_jenv(&self) -> Arc<RwLock<Jenv<'a>>> {
-        Arc::clone(&self.jenv)
+    /// the jvmti environment, only there when mci was loaded as an agent
+    pub fn get_jvmti(&self) -> Option<JvmtiEnv> {
+        crate::agent::jvmti()
+    }
+    /// the env of the thread that called [`Self::attach_current_thread`]
+    pub fn get_jenv(&self) -> Option<&Jenv<'_>> {
+        self.guard.as_ref().map(|guard| guard.env())
     }
 
     pub fn load_jvm(&mut self) -> Result<(), String> {
-        let get_jvms = jdk_sys::JNI_GetCreatedJavaVMs;
-        let i32_null_ptr : *mut i32 = std::ptr::null_mut();
-        let mut jvm_ptr: *mut *mut JavaVM = &mut *self.jvm.write().or(Err("unable to write to jvm".to_string()))?;
-        
-        unsafe {get_jvms(jvm_ptr,1, i32_null_ptr);};
-
+        let vm = match crate::agent::vm() {
+            Some(vm) => Some(vm),
+            None => JavaVm::get_created().map_err(|e| e.to_string())?,
+        };
+        self.vm = Some(vm.ok_or("no jvm has been created".to_string())?);
         Ok(())
     }
 
+    /// attaches the calling thread, it stays attached until the [`MCI`] is dropped.
+    /// other threads should use [`JavaVm::attach_current_thread`] on [`Self::get_vm`] instead.
     pub fn attach_current_thread(&mut self) -> Result<(),String> {
-        let mut jvm = *self.jvm.write().or(Err("unable to write to jvm".to_string()))?;
-        let mut jenv2 :*mut *mut JNIEnv = &mut self.jenv.write().or(Err("unable to write to jvm".to_string()))?.ptr;
-        unsafe {(*(*jvm)).AttachCurrentThread.unwrap()(jvm,std::mem::transmute(jenv2),ptr::null_mut());};
-
-        if jenv2.is_null() {
-            Err("AttachCurrentThread failed".to_string())
-        } else {
-            unsafe {
-                if (*jenv2).is_null() {
-                    return Err("AttachCurrentThread failed".to_string());
-                }
-            }
-            Ok(())
-        }
+        let vm = self.vm.ok_or("load_jvm has not been called".to_string())?;
+        let guard = vm.attach_current_thread().map_err(|e| format!("AttachCurrentThread failed: {e}"))?;
+        self.guard = Some(guard);
+        Ok(())
     }
-}
-impl Drop for MCI<'_> {
-    fn drop(&mut self) {
-        let mut jvm = *self.jvm.write().unwrap();
-        let mut jenv = *self.jenv.write().unwrap();
-
-        if jenv.ptr.is_null() || jvm.is_null() {
-            return;
-        } else {
-            println!("detaching");
-            unsafe {(*(*jvm)).DetachCurrentThread.unwrap()(jvm);};
-        }
 
+    /// reads the intermediary tiny mappings (`mappings/maps.tiny`), needed by [`Self::add_transformer`]
+    pub fn load_tiny(&mut self, path : impl AsRef<Path>) -> Result<(),String> {
+        let file = File::open(path.as_ref()).map_err(|e| format!("could not open {}: {e}", path.as_ref().display()))?;
+        let tiny = Tiny::new();
+        tiny.populate_from_reader(BufReader::new(file)).map_err(|e| format!("could not read {}: {e}", path.as_ref().display()))?;
+        self.tiny = Some(tiny);
+        Ok(())
+    }
+    /// the name the jvm knows the class with the intermediary name `class` (`net/minecraft/class_310`) by
+    pub fn obfuscated_class(&self, class : &str) -> Result<String,String> {
+        let tiny = self.tiny.as_ref().ok_or("load_tiny has not been called".to_string())?;
+        tiny.obfuscated_class(class).ok_or(format!("{class} is not in the tiny mappings"))
+    }
+    /// transforms the class with the intermediary name `class` when it is loaded, see [`JvmtiEnv::add_transformer`].
+    /// call [`Self::retransform`] afterwards for classes that are already loaded
+    pub fn add_transformer(&self, class : &str, f : impl for<'e> Fn(&'e Jenv<'e>, &ClassFileLoad<'e>, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static) -> Result<(),String> {
+        let jvmti = self.get_jvmti().ok_or("mci was not loaded as an agent".to_string())?;
+        let obfuscated = self.obfuscated_class(class)?;
+        jvmti.add_transformer(&obfuscated, f).map_err(|e| e.to_string())
+    }
+    /// retransforms the loaded classes that have transformers
+    pub fn retransform(&self) -> Result<usize,String> {
+        let jvmti = self.get_jvmti().ok_or("mci was not loaded as an agent".to_string())?;
+        let jenv = self.get_jenv().ok_or("thread is not attached".to_string())?;
+        jvmti.retransform_transformed(jenv).map_err(|e| e.to_string())
     }
-}
\ No newline at end of file
+    /// hooks the method with the yarn name `yarn` of the generated class `T`, eg. `mci.hook::<MinecraftClient>("tick", HookKind::Breakpoint, ..)`.
+    /// needs the agent to be started with the `hooks` option, see [`JvmtiEnv::hook_mapped`]
+    pub fn hook<T : Mapped>(&self, yarn : &str, kind : HookKind, f : impl for<'e> Fn(&'e Jenv<'e>, &MethodEvent<'e>) + Send + Sync + 'static) -> Result<usize,String> {
+        let jvmti = self.get_jvmti().ok_or("mci was not loaded as an agent".to_string())?;
+        let jenv = self.get_jenv().ok_or("thread is not attached".to_string())?;
+        jvmti.hook_mapped::<T>(jenv, yarn, kind, f).map_err(|e| e.to_string())
+    }
+    /// every live instance of the generated class `T`, eg. `mci.instances_of::<ClientWorld>()`
+    pub fn instances_of<'a, T : Mapped + From<JObject<'a>>>(&'a self) -> Result<Vec<T>,String> {
+        let jvmti = self.get_jvmti().ok_or("mci was not loaded as an agent".to_string())?;
+        let jenv = self.get_jenv().ok_or("thread is not attached".to_string())?;
+        jvmti.instances_of::<T>(jenv).map_err(|e| e.to_string())
+    }
+    /// objects and bytes on the heap per class, with minecraft's classes under their yarn names
+    pub fn heap_histogram(&self) -> Result<HeapHistogram,String> {
+        let jvmti = self.get_jvmti().ok_or("mci was not loaded as an agent".to_string())?;
+        let jenv = self.get_jenv().ok_or("thread is not attached".to_string())?;
+        let mut histogram = jvmti.heap_histogram(jenv).map_err(|e| e.to_string())?;
+        histogram.remap(mc_mappings::MAPPED_CLASSES);
+        Ok(histogram)
+    }
+}